@@ -63,5 +63,73 @@ fn tpch1(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, load_csv, tpch1);
+async fn filter_projection_limit_execute_and_materialize(df: Dataframe) {
+    let sql = "
+    select
+        l_orderkey,
+        l_extendedprice
+    from
+        lineitem
+    where
+        l_discount > 0.05
+    limit 10000;
+    ";
+    zaku::execute(sql, df)
+        .await
+        .unwrap()
+        .materialize()
+        .await
+        .unwrap();
+}
+
+fn filter_projection_limit(c: &mut Criterion) {
+    let mut group = c.benchmark_group("zaku filter projection limit benchmark");
+    let df = Dataframe::from_csv(PATH, Some(DELIMITER)).unwrap();
+
+    group.sample_size(SAMPLE_SIZE);
+    group.bench_function("filter_projection_limit", |b| {
+        b.iter(|| {
+            tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .worker_threads(NUM_THREADS)
+                .build()
+                .unwrap()
+                .block_on(filter_projection_limit_execute_and_materialize(df.clone()))
+        });
+    });
+}
+
+async fn count_execute_and_materialize(sql: &str, df: Dataframe) {
+    zaku::execute(sql, df)
+        .await
+        .unwrap()
+        .materialize()
+        .await
+        .unwrap();
+}
+
+fn count(c: &mut Criterion) {
+    let mut group = c.benchmark_group("zaku count benchmark");
+    let df = Dataframe::from_csv(PATH, Some(DELIMITER)).unwrap();
+
+    group.sample_size(SAMPLE_SIZE);
+    // COUNT(*) is answered from the batch row counts, COUNT(column) runs the generic aggregate
+    for (name, sql) in [
+        ("count_rows", "select count(*) from lineitem;"),
+        ("count_column", "select count(l_orderkey) from lineitem;"),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                tokio::runtime::Builder::new_multi_thread()
+                    .enable_all()
+                    .worker_threads(NUM_THREADS)
+                    .build()
+                    .unwrap()
+                    .block_on(count_execute_and_materialize(sql, df.clone()))
+            });
+        });
+    }
+}
+
+criterion_group!(benches, load_csv, tpch1, filter_projection_limit, count);
 criterion_main!(benches);
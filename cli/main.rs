@@ -1,22 +1,177 @@
 #![feature(stmt_expr_attributes)]
 #![feature(proc_macro_hygiene)]
-#![feature(coroutines)]
 
 use argparse::ArgumentParser;
 use crossterm::event::{read, Event, KeyCode, KeyEvent, KeyModifiers};
 use futures_async_stream::for_await;
-use rustyline::{error::ReadlineError, DefaultEditor};
-use std::path::Path;
-use zaku::{execute, Dataframe, ZakuError};
+use rustyline::{error::ReadlineError, Behavior, Config, DefaultEditor};
+use std::{fs::File, path::Path, str::FromStr};
+use zaku::{
+    execute_dataframe, execute_in_catalog, Catalog, CsvOptions, DataType, Dataframe, Datasink,
+    ExecutionConfig, ExtraFields, OutputSummary, ZakuError,
+};
 
-async fn execute_sql(sql: &str, df: Dataframe) -> Result<String, ZakuError> {
+// Where query results are written, changed with the .output command
+enum Output {
+    Stdout,
+    File(String, File),
+}
+
+impl Output {
+    // .output prints the current target, .output stdout reverts to stdout,
+    // and .output <path> writes the results of subsequent queries to the file as csv
+    fn redirect(self, arg: &str) -> Output {
+        match arg {
+            "" => {
+                match &self {
+                    Output::Stdout => eprintln!("Writing results to stdout\n"),
+                    Output::File(path, _) => eprintln!("Writing results to {}\n", path),
+                }
+                self
+            }
+            "stdout" => {
+                // dropping the previous target closes its file
+                drop(self);
+                eprintln!("Writing results to stdout\n");
+                Output::Stdout
+            }
+            path => match File::create(path) {
+                Ok(file) => {
+                    drop(self);
+                    eprintln!("Writing results to {}\n", path);
+                    Output::File(path.to_string(), file)
+                }
+                Err(e) => {
+                    eprintln!("Failed to open {}: {}\n", path, e);
+                    self
+                }
+            },
+        }
+    }
+}
+
+// How rows are formatted, set with --format
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Table,
+    // one json object per row, without the table borders, paging or row count footer
+    Ndjson,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(Format::Table),
+            "ndjson" => Ok(Format::Ndjson),
+            _ => Err(format!("Unknown format: {}", s)),
+        }
+    }
+}
+
+// What print_result did with the rows of a result
+enum Printed {
+    Rows(usize),
+    // rows written to a file with .output
+    Written(usize, String),
+    // ndjson is streamed without a footer, so that it can be piped
+    Streamed,
+}
+
+// The message printed after a result. The row count can be turned off with --no-footer, but
+// rows written to a file are always confirmed, as nothing else is shown for them
+fn footer(printed: &Printed, row_count: bool) -> Option<String> {
+    match printed {
+        Printed::Rows(count) if row_count => Some(format!("({} rows)", count)),
+        Printed::Written(count, path) => Some(format!("({} rows written to {})", count, path)),
+        _ => None,
+    }
+}
+
+async fn execute_sql(
+    sql: &str,
+    catalog: &mut Catalog,
+    show_types: bool,
+    config: &ExecutionConfig,
+    output: &mut Output,
+    format: Format,
+    describe_output: bool,
+) -> Result<Printed, ZakuError> {
+    let res = execute_in_catalog(sql, catalog, config.clone()).await?;
+    print_result(res, show_types, config, output, format, describe_output).await
+}
+
+// Splits the arguments of .value_counts into the table and the column to count
+// Without a table name, the column is counted in the loaded file
+fn value_counts_target<'a>(
+    args: &'a str,
+    catalog: &'a Catalog,
+    default_table: &'a str,
+) -> Result<(&'a Dataframe, &'a str), ZakuError> {
+    let (table, column) = match args.split_once(char::is_whitespace) {
+        Some((table, column)) => (table, column.trim()),
+        None => (default_table, args),
+    };
+    let df = catalog
+        .get(table)
+        .ok_or_else(|| ZakuError::new(format!("Table '{table}' does not exist").as_str()))?;
+    Ok((df, column))
+}
+
+async fn value_counts(
+    column: &str,
+    df: &Dataframe,
+    show_types: bool,
+    config: &ExecutionConfig,
+    output: &mut Output,
+    format: Format,
+    describe_output: bool,
+) -> Result<Printed, ZakuError> {
+    let res = execute_dataframe(df.value_counts(column)?, config.clone()).await?;
+    print_result(res, show_types, config, output, format, describe_output).await
+}
+
+async fn print_result(
+    res: Datasink,
+    show_types: bool,
+    config: &ExecutionConfig,
+    output: &mut Output,
+    format: Format,
+    describe_output: bool,
+) -> Result<Printed, ZakuError> {
     let mut row_count = 0;
-    let res = execute(sql, df.clone()).await?;
+    if let Some(plan) = res.debug_plan() {
+        eprintln!("{}", plan);
+    }
+    // on stderr, so that they do not mix with piped results
+    for warning in res.warnings() {
+        eprintln!("{}", warning);
+    }
+    if let Output::File(path, file) = output {
+        let row_count = match format {
+            Format::Table => {
+                res.write_csv_with_delimiter(file, config.delimiter_out())
+                    .await?
+            }
+            Format::Ndjson => res.write_ndjson(file).await?,
+        };
+        return Ok(Printed::Written(row_count, path.clone()));
+    }
+    if format == Format::Ndjson {
+        res.write_ndjson(std::io::stdout().lock()).await?;
+        return Ok(Printed::Streamed);
+    }
+    if show_types {
+        println!("{}", res.schema());
+    }
+    // summarizes the rows printed, so it stops with the paging
+    let mut summary = describe_output.then(|| OutputSummary::new(res.schema()));
     let mut is_first_batch = true;
     #[for_await]
     for rb in res.iter() {
         if !is_first_batch {
-            println!("(Press (ENTER) to print next rows, any other key to stop)");
+            eprintln!("(Press (ENTER) to print next rows, any other key to stop)");
             match read().unwrap() {
                 Event::Key(KeyEvent {
                     code: KeyCode::Enter,
@@ -36,19 +191,45 @@ async fn execute_sql(sql: &str, df: Dataframe) -> Result<String, ZakuError> {
             println!("{}", rb.print(false));
         }
         row_count += rb.row_count();
+        if let Some(summary) = summary.as_mut() {
+            summary.update(&rb)?;
+        }
+    }
+    if let Some(rb) = summary.map(|s| s.to_record_batch()).transpose()?.flatten() {
+        println!("\n{}", rb.print(true));
     }
-    Ok(format!("({} rows)", row_count))
+    Ok(Printed::Rows(row_count))
 }
 
-async fn event_loop(df: Dataframe) {
-    let mut rl = match DefaultEditor::new() {
+// row_count is whether to print the row count after each result
+async fn event_loop(
+    df: Dataframe,
+    mut show_types: bool,
+    config: ExecutionConfig,
+    format: Format,
+    row_count: bool,
+    describe_output: bool,
+    data_from_stdin: bool,
+) {
+    // queries are read from the terminal when the csv data is piped through stdin,
+    // and otherwise from stdin, so that they can be piped in as well
+    let behavior = if data_from_stdin {
+        Behavior::PreferTerm
+    } else {
+        Behavior::Stdio
+    };
+    let editor_config = Config::builder().behavior(behavior).build();
+    let mut rl = match DefaultEditor::with_config(editor_config) {
         Ok(e) => e,
         Err(err) => {
-            println!("Failed to initialize Zaku cli: {}", err);
+            eprintln!("Failed to initialize Zaku cli: {}", err);
             return;
         }
     };
 
+    // tables created with CREATE TABLE AS last until the cli exits
+    let mut catalog = Catalog::from_dataframe(df.clone());
+    let mut output = Output::Stdout;
     loop {
         let readline = rl.readline("Zaku >>> ");
         match readline {
@@ -56,31 +237,80 @@ async fn event_loop(df: Dataframe) {
                 match rl.add_history_entry(line.as_str()) {
                     Ok(_) => (),
                     Err(err) => {
-                        println!("Failed to add history entry: {}", err);
+                        eprintln!("Failed to add history entry: {}", err);
                     }
                 }
                 match line.as_str() {
                     "quit" => {
-                        println!("Exiting Zaku...");
+                        eprintln!("Exiting Zaku...");
                         break;
                     }
                     "schema" => println!("{}\n", df.schema().to_record_batch().print(true)),
-                    _ => match execute_sql(&line, df.clone()).await {
-                        Ok(res) => println!("{}\n", res),
-                        Err(e) => println!("{}\n", e),
+                    "\\d" => {
+                        show_types = !show_types;
+                        let state = if show_types { "on" } else { "off" };
+                        eprintln!("Result types display is {}\n", state);
+                    }
+                    cmd if cmd == ".output" || cmd.starts_with(".output ") => {
+                        output = output.redirect(cmd[".output".len()..].trim());
+                    }
+                    cmd if cmd.starts_with(".value_counts ") => {
+                        let args = cmd[".value_counts".len()..].trim();
+                        let default_table = df.table_name().unwrap_or_default();
+                        let res = match value_counts_target(args, &catalog, &default_table) {
+                            Ok((table, column)) => {
+                                value_counts(
+                                    column,
+                                    table,
+                                    show_types,
+                                    &config,
+                                    &mut output,
+                                    format,
+                                    describe_output,
+                                )
+                                .await
+                            }
+                            Err(e) => Err(e),
+                        };
+                        match res {
+                            Ok(printed) => {
+                                if let Some(message) = footer(&printed, row_count) {
+                                    eprintln!("{}\n", message);
+                                }
+                            }
+                            Err(e) => eprintln!("{}\n", e),
+                        }
+                    }
+                    _ => match execute_sql(
+                        &line,
+                        &mut catalog,
+                        show_types,
+                        &config,
+                        &mut output,
+                        format,
+                        describe_output,
+                    )
+                    .await
+                    {
+                        Ok(printed) => {
+                            if let Some(message) = footer(&printed, row_count) {
+                                eprintln!("{}\n", message);
+                            }
+                        }
+                        Err(e) => eprintln!("{}\n", e),
                     },
                 }
             }
             Err(ReadlineError::Interrupted) => {
-                println!("Exiting Zaku...");
+                eprintln!("Exiting Zaku...");
                 break;
             }
             Err(ReadlineError::Eof) => {
-                println!("Exiting Zaku...");
+                eprintln!("Exiting Zaku...");
                 break;
             }
             Err(err) => {
-                println!("Error: {:?}", err);
+                eprintln!("Error: {:?}", err);
                 break;
             }
         }
@@ -91,27 +321,160 @@ async fn event_loop(df: Dataframe) {
 async fn main() {
     let mut path = Path::new("resources").join("test.csv");
     let mut delimiter = ',';
+    let mut delimiter_out = ',';
+    let mut show_types = false;
+    let mut no_footer = false;
+    let mut describe_output = false;
+    let mut debug_plan = false;
+    let mut format = Format::Table;
+    let mut ignore_extra_fields = false;
+    let mut empty_column_type = DataType::Text.to_string();
     {
         let mut parser = ArgumentParser::new();
         parser.set_description("Zaku is a simple SQL query enginer on CSV files written in Rust");
-        parser
-            .refer(&mut path)
-            .add_argument("path", argparse::Store, "Path to CSV file");
+        parser.refer(&mut path).add_argument(
+            "path",
+            argparse::Store,
+            "Path to CSV file, or - to read from stdin",
+        );
         parser.refer(&mut delimiter).add_option(
             &["-d", "--delimiter"],
             argparse::Store,
             "Delimiter used in the CSV file. Defaults to ','",
         );
+        parser.refer(&mut delimiter_out).add_option(
+            &["--delimiter-out"],
+            argparse::Store,
+            "Delimiter of csv files written with .output or COPY. Defaults to ','",
+        );
+        parser.refer(&mut show_types).add_option(
+            &["--show-types"],
+            argparse::StoreTrue,
+            "Print the schema of query results before the rows. Toggle with \\d",
+        );
+        parser.refer(&mut no_footer).add_option(
+            &["--no-footer"],
+            argparse::StoreTrue,
+            "Do not print the row count after each result",
+        );
+        parser.refer(&mut describe_output).add_option(
+            &["--describe-output"],
+            argparse::StoreTrue,
+            "Print the count, min, max and mean of each numeric column after the rows of a result",
+        );
+        parser.refer(&mut debug_plan).add_option(
+            &["--debug-plan"],
+            argparse::StoreTrue,
+            "Print the logical plan before and after optimization for each query",
+        );
+        parser.refer(&mut format).add_option(
+            &["--format"],
+            argparse::Store,
+            "Output format of query results, table or ndjson. Defaults to table",
+        );
+        parser.refer(&mut ignore_extra_fields).add_option(
+            &["--ignore-extra-fields"],
+            argparse::StoreTrue,
+            "Ignore fields beyond the header instead of failing to load the CSV file",
+        );
+        parser.refer(&mut empty_column_type).add_option(
+            &["--empty-column-type"],
+            argparse::Store,
+            "Type of columns without any values, such as num or date. Defaults to text",
+        );
         parser.parse_args_or_exit();
     }
 
-    match Dataframe::from_csv(
-        path.to_str()
-            .expect("File test.csv should exist in resources directory"),
-        Some(delimiter as u8),
-    ) {
-        Ok(df) => event_loop(df).await,
-        Err(e) => println!("Failed to load CSV file: {}", e),
+    let extra_fields = if ignore_extra_fields {
+        ExtraFields::Ignore
+    } else {
+        ExtraFields::Error
+    };
+    let empty_column_type = match DataType::from_str(&empty_column_type) {
+        Ok(empty_column_type) => empty_column_type,
+        Err(e) => {
+            eprintln!("Invalid --empty-column-type: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let options = CsvOptions::default()
+        .with_delimiter(delimiter as u8)
+        .with_extra_fields(extra_fields)
+        .with_empty_column_type(empty_column_type);
+    let data_from_stdin = path == Path::new("-");
+    let df = if data_from_stdin {
+        Dataframe::from_csv_reader(std::io::stdin().lock(), "stdin", &options)
+    } else {
+        Dataframe::from_csv_with_options(
+            path.to_str()
+                .expect("File test.csv should exist in resources directory"),
+            &options,
+        )
+    };
+    match df {
+        Ok(df) => {
+            if !delimiter_out.is_ascii() {
+                eprintln!("Output delimiter must be an ASCII character");
+                std::process::exit(1);
+            }
+            let delimiter_out = delimiter_out as u8;
+            let config = ExecutionConfig::default()
+                .with_debug_plan(debug_plan)
+                .with_delimiter_out(delimiter_out);
+            if let Err(e) = config.validate() {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            event_loop(
+                df,
+                show_types,
+                config,
+                format,
+                !no_footer,
+                describe_output,
+                data_from_stdin,
+            )
+            .await
+        }
+        Err(e) => eprintln!("Failed to load CSV file: {}", e),
     }
     std::process::exit(0);
 }
+
+#[cfg(test)]
+mod test {
+    use super::{footer, value_counts_target, Printed};
+    use zaku::{Catalog, Dataframe};
+
+    #[test]
+    fn test_value_counts_target() {
+        let df = Dataframe::from_csv("resources/test.csv", None).unwrap();
+        let mut catalog = Catalog::from_dataframe(df.clone());
+        catalog.register("summary", df.clone());
+
+        let (_, column) = value_counts_target("product_name", &catalog, "test").unwrap();
+        assert_eq!(column, "product_name");
+        let (_, column) = value_counts_target("summary  price", &catalog, "test").unwrap();
+        assert_eq!(column, "price");
+        assert_eq!(
+            value_counts_target("missing price", &catalog, "test")
+                .unwrap_err()
+                .to_string(),
+            "ZakuError: Table 'missing' does not exist"
+        );
+    }
+
+    #[test]
+    fn test_footer() {
+        assert_eq!(
+            footer(&Printed::Rows(3), true),
+            Some("(3 rows)".to_string())
+        );
+        assert_eq!(footer(&Printed::Rows(3), false), None);
+        let written = Printed::Written(2, "out.csv".to_string());
+        let confirmation = Some("(2 rows written to out.csv)".to_string());
+        assert_eq!(footer(&written, true), confirmation);
+        assert_eq!(footer(&written, false), confirmation);
+        assert_eq!(footer(&Printed::Streamed, true), None);
+    }
+}
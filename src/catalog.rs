@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use crate::{error::ZakuError, logical_plans::dataframe::Dataframe};
+
+// Tables that queries can refer to by name, matched case-insensitively
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    tables: HashMap<String, Dataframe>,
+}
+
+impl Catalog {
+    pub fn new() -> Catalog {
+        Catalog {
+            tables: HashMap::new(),
+        }
+    }
+
+    // A catalog with a single table, named after its file
+    // Sources without a name, such as in-memory data, are registered without one
+    pub fn from_dataframe(df: Dataframe) -> Catalog {
+        let mut catalog = Catalog::new();
+        catalog.register(&df.table_name().unwrap_or_default(), df);
+        catalog
+    }
+
+    // Replaces any table already registered under the name
+    pub fn register(&mut self, name: &str, df: Dataframe) {
+        self.tables.insert(name.to_lowercase(), df);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Dataframe> {
+        self.tables.get(&name.to_lowercase())
+    }
+
+    pub fn table_names(&self) -> Vec<&String> {
+        let mut names: Vec<&String> = self.tables.keys().collect();
+        names.sort();
+        names
+    }
+
+    // Returns the table a query reads from, along with a warning if it was not found by name
+    // Older scripts refer to a single loaded file by any name, so when only one table is
+    // registered it is used for unknown names, with a deprecation warning
+    pub fn resolve(&self, name: Option<&str>) -> Result<(Dataframe, Option<String>), ZakuError> {
+        if let Some(df) = name.and_then(|name| self.get(name)) {
+            return Ok((df.clone(), None));
+        }
+        if self.tables.len() == 1 {
+            let (table_name, df) = self.tables.iter().next().expect("Catalog has one table");
+            let warning = name.filter(|_| !table_name.is_empty()).map(|name| {
+                format!(
+                    "Warning: table '{name}' does not exist, reading from '{table_name}' instead. \
+                     Referring to the loaded table by another name is deprecated"
+                )
+            });
+            return Ok((df.clone(), warning));
+        }
+        match name {
+            Some(name) => Err(ZakuError::new(
+                format!("Table '{name}' does not exist").as_str(),
+            )),
+            None => Err(ZakuError::new("Queries must read from a table")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Catalog;
+    use crate::logical_plans::dataframe::Dataframe;
+
+    fn test_df() -> Result<Dataframe, crate::error::ZakuError> {
+        Dataframe::from_csv("resources/test.csv", None)
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut catalog = Catalog::from_dataframe(test_df().unwrap());
+        assert_eq!(catalog.table_names(), vec!["test"]);
+
+        let (_, warning) = catalog.resolve(Some("TEST")).unwrap();
+        assert!(warning.is_none());
+        let (_, warning) = catalog.resolve(Some("lineitem")).unwrap();
+        assert!(warning.is_some());
+
+        catalog.register("summary", test_df().unwrap());
+        assert_eq!(catalog.table_names(), vec!["summary", "test"]);
+        assert!(catalog.resolve(Some("Summary")).unwrap().1.is_none());
+        assert_eq!(
+            catalog.resolve(Some("lineitem")).unwrap_err().to_string(),
+            "ZakuError: Table 'lineitem' does not exist"
+        );
+        assert!(catalog.resolve(None).is_err());
+    }
+}
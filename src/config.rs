@@ -0,0 +1,189 @@
+use std::{
+    fmt::{Debug, Formatter},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    datatypes::record_batch::BATCH_SIZE,
+    error::ZakuError,
+    optimizer::rule::{OptimizerRule, OptimizerRules},
+};
+
+const MAX_EXPR_DEPTH: usize = 100;
+
+// Settings that apply to the execution of a query
+#[derive(Clone)]
+pub struct ExecutionConfig {
+    batch_size: usize,
+    deterministic_hash: bool,
+    spill_threshold: Option<usize>,
+    spill_dir: PathBuf,
+    debug_plan: bool,
+    max_expr_depth: usize,
+    delimiter_out: u8,
+    optimizer_rules: OptimizerRules,
+}
+
+impl ExecutionConfig {
+    pub fn new() -> ExecutionConfig {
+        ExecutionConfig {
+            batch_size: BATCH_SIZE,
+            deterministic_hash: false,
+            spill_threshold: None,
+            spill_dir: std::env::temp_dir(),
+            debug_plan: false,
+            max_expr_depth: MAX_EXPR_DEPTH,
+            delimiter_out: b',',
+            optimizer_rules: vec![],
+        }
+    }
+
+    // Number of rows per record batch emitted by operators that materialize their input
+    pub fn with_batch_size(mut self, batch_size: usize) -> ExecutionConfig {
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    // Hash group keys with fixed keys so grouping is reproducible between runs
+    pub fn with_deterministic_hash(mut self, deterministic_hash: bool) -> ExecutionConfig {
+        self.deterministic_hash = deterministic_hash;
+        self
+    }
+
+    pub fn deterministic_hash(&self) -> bool {
+        self.deterministic_hash
+    }
+
+    // Most groups an aggregation keeps in memory, after which rows of new groups are spilled to disk
+    pub fn with_spill_threshold(mut self, spill_threshold: Option<usize>) -> ExecutionConfig {
+        self.spill_threshold = spill_threshold;
+        self
+    }
+
+    pub fn spill_threshold(&self) -> Option<usize> {
+        self.spill_threshold
+    }
+
+    // Directory of the spill files, the system temporary directory by default
+    pub fn with_spill_dir(mut self, spill_dir: PathBuf) -> ExecutionConfig {
+        self.spill_dir = spill_dir;
+        self
+    }
+
+    pub fn spill_dir(&self) -> &Path {
+        &self.spill_dir
+    }
+
+    // Keep the logical plan before and after optimization on the query result
+    pub fn with_debug_plan(mut self, debug_plan: bool) -> ExecutionConfig {
+        self.debug_plan = debug_plan;
+        self
+    }
+
+    pub fn debug_plan(&self) -> bool {
+        self.debug_plan
+    }
+
+    // Deepest nesting of expressions and subqueries a query may have, checked while parsing
+    pub fn with_max_expr_depth(mut self, max_expr_depth: usize) -> ExecutionConfig {
+        self.max_expr_depth = max_expr_depth;
+        self
+    }
+
+    pub fn max_expr_depth(&self) -> usize {
+        self.max_expr_depth
+    }
+
+    // Delimiter of exported csv files, unless a COPY statement sets its own
+    pub fn with_delimiter_out(mut self, delimiter_out: u8) -> ExecutionConfig {
+        self.delimiter_out = delimiter_out;
+        self
+    }
+
+    pub fn delimiter_out(&self) -> u8 {
+        self.delimiter_out
+    }
+
+    // Registers a rule to run after the built-in optimizer rules
+    pub fn with_optimizer_rule(
+        mut self,
+        rule: Arc<dyn OptimizerRule + Send + Sync>,
+    ) -> ExecutionConfig {
+        self.optimizer_rules.push(rule);
+        self
+    }
+
+    pub fn optimizer_rules(&self) -> &OptimizerRules {
+        &self.optimizer_rules
+    }
+
+    pub fn validate(&self) -> Result<(), ZakuError> {
+        if self.batch_size == 0 {
+            return Err(ZakuError::new("Batch size must be greater than 0"));
+        }
+        if self.spill_threshold == Some(0) {
+            return Err(ZakuError::new("Spill threshold must be greater than 0"));
+        }
+        if self.max_expr_depth == 0 {
+            return Err(ZakuError::new(
+                "Maximum expression depth must be greater than 0",
+            ));
+        }
+        if !self.delimiter_out.is_ascii() {
+            return Err(ZakuError::new(
+                "Output delimiter must be an ASCII character",
+            ));
+        }
+        if matches!(self.delimiter_out, b'"' | b'\n' | b'\r') {
+            return Err(ZakuError::new(
+                "Output delimiter cannot be a quote or a line break",
+            ));
+        }
+        Ok(())
+    }
+}
+
+// Rules are trait objects, so they are listed by count and compared by identity
+impl Debug for ExecutionConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExecutionConfig")
+            .field("batch_size", &self.batch_size)
+            .field("deterministic_hash", &self.deterministic_hash)
+            .field("spill_threshold", &self.spill_threshold)
+            .field("spill_dir", &self.spill_dir)
+            .field("debug_plan", &self.debug_plan)
+            .field("max_expr_depth", &self.max_expr_depth)
+            .field("delimiter_out", &self.delimiter_out)
+            .field("optimizer_rules", &self.optimizer_rules.len())
+            .finish()
+    }
+}
+
+impl PartialEq for ExecutionConfig {
+    fn eq(&self, other: &Self) -> bool {
+        self.batch_size == other.batch_size
+            && self.deterministic_hash == other.deterministic_hash
+            && self.spill_threshold == other.spill_threshold
+            && self.spill_dir == other.spill_dir
+            && self.debug_plan == other.debug_plan
+            && self.max_expr_depth == other.max_expr_depth
+            && self.delimiter_out == other.delimiter_out
+            && self.optimizer_rules.len() == other.optimizer_rules.len()
+            && self
+                .optimizer_rules
+                .iter()
+                .zip(other.optimizer_rules.iter())
+                .all(|(a, b)| Arc::ptr_eq(a, b))
+    }
+}
+
+impl Default for ExecutionConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
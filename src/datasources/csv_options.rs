@@ -0,0 +1,70 @@
+use crate::datatypes::types::DataType;
+
+// What to do with rows that have more fields than the header
+// Rows with fewer fields are always an error
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ExtraFields {
+    #[default]
+    Error,
+    Ignore,
+}
+
+// Settings for reading csv files
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvOptions {
+    delimiter: Option<u8>,
+    extra_fields: ExtraFields,
+    empty_column_type: DataType,
+}
+
+impl CsvOptions {
+    pub fn new() -> CsvOptions {
+        CsvOptions {
+            delimiter: None,
+            extra_fields: ExtraFields::default(),
+            empty_column_type: DataType::default(),
+        }
+    }
+
+    pub(crate) fn from_delimiter(delimiter: Option<u8>) -> CsvOptions {
+        CsvOptions {
+            delimiter,
+            ..CsvOptions::new()
+        }
+    }
+
+    // Defaults to ','
+    pub fn with_delimiter(mut self, delimiter: u8) -> CsvOptions {
+        self.delimiter = Some(delimiter);
+        self
+    }
+
+    pub fn delimiter(&self) -> u8 {
+        self.delimiter.unwrap_or(b',')
+    }
+
+    pub fn with_extra_fields(mut self, extra_fields: ExtraFields) -> CsvOptions {
+        self.extra_fields = extra_fields;
+        self
+    }
+
+    pub fn extra_fields(&self) -> ExtraFields {
+        self.extra_fields
+    }
+
+    // The type of columns with no values to infer a type from, text by default
+    pub fn with_empty_column_type(mut self, empty_column_type: DataType) -> CsvOptions {
+        self.empty_column_type = empty_column_type;
+        self
+    }
+
+    pub fn empty_column_type(&self) -> DataType {
+        self.empty_column_type
+    }
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
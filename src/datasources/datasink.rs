@@ -1,20 +1,84 @@
-use csv::Writer;
+use std::{fs::File, io::Write};
+
+use csv::WriterBuilder;
 
 use crate::{
-    datatypes::{column_vector::Vector, record_batch::RecordBatch, schema::Schema},
+    datatypes::{column_vector::Vector, record_batch::RecordBatch, schema::Schema, types::Value},
     error::ZakuError,
     physical_plans::physical_plan::PhysicalPlans,
 };
 use futures_async_stream::{for_await, try_stream};
 
+fn json_string(s: &str) -> String {
+    let mut res = String::with_capacity(s.len() + 2);
+    res.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if (c as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+fn json_value(value: &Value) -> String {
+    match value {
+        Value::Number(_) | Value::Boolean(_) => value.to_string(),
+        Value::Text(s) => json_string(s),
+        Value::Date(d) => json_string(&d.to_string()),
+        Value::List(values) => format!(
+            "[{}]",
+            values
+                .iter()
+                .map(json_value)
+                .collect::<Vec<String>>()
+                .join(",")
+        ),
+        Value::Null => "null".to_string(),
+    }
+}
+
 pub struct Datasink {
     schema: Schema,
     input: PhysicalPlans,
+    debug_plan: Option<String>,
+    warnings: Vec<String>,
 }
 
 impl Datasink {
     pub fn new(schema: Schema, input: PhysicalPlans) -> Datasink {
-        Datasink { schema, input }
+        Datasink {
+            schema,
+            input,
+            debug_plan: None,
+            warnings: vec![],
+        }
+    }
+
+    pub fn with_debug_plan(mut self, debug_plan: Option<String>) -> Datasink {
+        self.debug_plan = debug_plan;
+        self
+    }
+
+    // the logical plan before and after optimization, if requested in the execution config
+    pub fn debug_plan(&self) -> Option<&String> {
+        self.debug_plan.as_ref()
+    }
+
+    pub fn with_warnings(mut self, warnings: Vec<String>) -> Datasink {
+        self.warnings = warnings;
+        self
+    }
+
+    // problems with the query that did not stop it from running, such as deprecated syntax
+    pub fn warnings(&self) -> &Vec<String> {
+        &self.warnings
     }
 
     pub async fn materialize(&self) -> Result<Vec<RecordBatch>, ZakuError> {
@@ -38,23 +102,71 @@ impl Datasink {
         }
     }
 
-    pub async fn to_csv(&self, path: &String) -> Result<(), ZakuError> {
-        let mut file = Writer::from_path(path)?;
+    pub async fn to_csv(&self, path: &String, delimiter: u8) -> Result<(), ZakuError> {
+        self.write_csv_with_delimiter(File::create(path)?, delimiter)
+            .await?;
+        Ok(())
+    }
+
+    // writes the header and rows as csv, returning the number of rows written
+    pub async fn write_csv<W: Write>(&self, writer: W) -> Result<usize, ZakuError> {
+        self.write_csv_with_delimiter(writer, b',').await
+    }
+
+    pub async fn write_csv_with_delimiter<W: Write>(
+        &self,
+        writer: W,
+        delimiter: u8,
+    ) -> Result<usize, ZakuError> {
+        let mut file = WriterBuilder::new()
+            .delimiter(delimiter)
+            .from_writer(writer);
         file.write_record(self.schema.as_header())?;
+        let mut row_count = 0;
 
         #[for_await]
         for res in self.input.execute() {
             let rb = res?;
-            (0..rb.row_count()).for_each(|i| {
+            (0..rb.row_count()).try_for_each(|i| {
                 let row = rb
                     .iter()
                     .map(|col| col.get_value(&i).to_string())
                     .collect::<Vec<String>>();
-                file.write_record(row).unwrap();
-            });
+                file.write_record(row)
+            })?;
             file.flush()?;
+            row_count += rb.row_count();
         }
 
-        Ok(())
+        Ok(row_count)
+    }
+
+    // writes each row as a compact json object on its own line, flushing after every batch
+    // so consumers can start reading before the query finishes
+    pub async fn write_ndjson<W: Write>(&self, mut writer: W) -> Result<usize, ZakuError> {
+        let keys = self
+            .schema
+            .as_header()
+            .iter()
+            .map(|name| json_string(name))
+            .collect::<Vec<String>>();
+        let mut row_count = 0;
+
+        #[for_await]
+        for res in self.input.execute() {
+            let rb = res?;
+            for i in 0..rb.row_count() {
+                let row = rb
+                    .iter()
+                    .zip(keys.iter())
+                    .map(|(col, key)| format!("{}:{}", key, json_value(col.get_value(&i))))
+                    .collect::<Vec<String>>();
+                writeln!(writer, "{{{}}}", row.join(","))?;
+            }
+            writer.flush()?;
+            row_count += rb.row_count();
+        }
+
+        Ok(row_count)
     }
 }
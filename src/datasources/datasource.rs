@@ -1,5 +1,8 @@
-use csv::ReaderBuilder;
+use std::{fs::File, io::Read, path::Path};
+
+use csv::{Reader, ReaderBuilder};
 use enum_dispatch::enum_dispatch;
+use flate2::read::GzDecoder;
 
 use crate::{
     datatypes::{
@@ -10,11 +13,15 @@ use crate::{
     error::ZakuError,
 };
 
+use super::csv_options::{CsvOptions, ExtraFields};
+
 #[enum_dispatch]
 pub trait Datasource {
     fn schema(&self) -> &Schema;
     fn get_data(&self) -> &Vec<RecordBatch>;
     fn path(&self) -> String;
+    // the name queries use to refer to the source, if it has one
+    fn table_name(&self) -> Option<String>;
 }
 
 #[derive(Debug, Clone)]
@@ -48,75 +55,313 @@ impl Datasource for MemDatasource {
     fn path(&self) -> String {
         "In memory".to_string()
     }
+
+    fn table_name(&self) -> Option<String> {
+        None
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct CSVDatasource {
-    path: String,
+    // the files the source was read from, several when files are concatenated
+    paths: Vec<String>,
     schema: Schema,
     data: Vec<RecordBatch>,
 }
 
 impl CSVDatasource {
     pub fn new(path: String, schema: Schema, data: Vec<RecordBatch>) -> CSVDatasource {
-        CSVDatasource { path, schema, data }
+        CSVDatasource {
+            paths: vec![path],
+            schema,
+            data,
+        }
     }
 
     pub fn from_csv(path: &str, delimiter: Option<u8>) -> Result<CSVDatasource, ZakuError> {
-        let schema = CSVDatasource::get_csv_schema(path, delimiter)?;
-        let record_batch = CSVDatasource::load_csv_data(path, schema.clone(), delimiter)?;
+        CSVDatasource::from_csv_with_options(path, &CsvOptions::from_delimiter(delimiter))
+    }
+
+    pub fn from_csv_with_options(
+        path: &str,
+        options: &CsvOptions,
+    ) -> Result<CSVDatasource, ZakuError> {
+        let schema = CSVDatasource::get_csv_schema(path, options)?;
+        let record_batch = CSVDatasource::load_csv_data(path, schema.clone(), options)?;
         Ok(CSVDatasource::new(path.to_string(), schema, record_batch))
     }
 
-    fn get_csv_schema(path: &str, delimiter: Option<u8>) -> Result<Schema, ZakuError> {
-        let mut rdr = ReaderBuilder::new()
-            .delimiter(delimiter.unwrap_or(b','))
-            .from_path(path)?;
+    // Reads the columns of a declared schema instead of inferring their types,
+    // for example to load a currency column as decimal(10,2)
+    pub fn from_csv_with_schema(
+        path: &str,
+        schema: Schema,
+        delimiter: Option<u8>,
+    ) -> Result<CSVDatasource, ZakuError> {
+        let options = CsvOptions::from_delimiter(delimiter);
+        let file_schema = CSVDatasource::get_csv_schema(path, &options)?;
+        if let Some(field) = schema
+            .fields()
+            .iter()
+            .find(|field| file_schema.get_field(field.name()).is_err())
+        {
+            return Err(ZakuError::new(
+                format!("Column '{}' not found in {}", field.name(), path).as_str(),
+            ));
+        }
+        let cols = CSVDatasource::read_csv_columns(path, &file_schema, &schema, &options)?;
+        let record_batch = RecordBatch::to_record_batch(cols, &schema, BATCH_SIZE);
+        Ok(CSVDatasource::new(path.to_string(), schema, record_batch))
+    }
+
+    // Reads csv data from a stream such as stdin, which can only be read once.
+    // The input is buffered in memory so that the schema can be inferred before loading it.
+    // The source is named after name, as if it were read from that path
+    pub fn from_reader<R: Read>(
+        mut input: R,
+        name: &str,
+        options: &CsvOptions,
+    ) -> Result<CSVDatasource, ZakuError> {
+        let mut buf = vec![];
+        input.read_to_end(&mut buf)?;
+        let schema = CSVDatasource::infer_schema(
+            &mut CSVDatasource::reader(buf.as_slice(), options),
+            name,
+            options,
+        )?;
+        let cols = CSVDatasource::read_columns(
+            &mut CSVDatasource::reader(buf.as_slice(), options),
+            name,
+            &schema,
+            &schema,
+            options,
+        )?;
+        let record_batch = RecordBatch::to_record_batch(cols, &schema, BATCH_SIZE);
+        Ok(CSVDatasource::new(name.to_string(), schema, record_batch))
+    }
+
+    // Files ending in .gz are transparently decompressed
+    fn open(path: &str) -> Result<Box<dyn Read>, ZakuError> {
+        let file = File::open(path)?;
+        if path.ends_with(".gz") {
+            Ok(Box::new(GzDecoder::new(file)))
+        } else {
+            Ok(Box::new(file))
+        }
+    }
+
+    // Rows of any length are read, and checked against the header when loading
+    fn reader<R: Read>(input: R, options: &CsvOptions) -> Reader<R> {
+        ReaderBuilder::new()
+            .delimiter(options.delimiter())
+            .flexible(true)
+            .from_reader(input)
+    }
+
+    // Reports invalid UTF-8 with the file and line it occurs in
+    fn csv_error(path: &str, err: csv::Error) -> ZakuError {
+        match err.kind() {
+            csv::ErrorKind::Utf8 { pos, err } => {
+                let line = pos.as_ref().map_or(0, |p| p.line());
+                ZakuError::new(
+                    format!(
+                        "Invalid UTF-8 in {path} at line {line}, field {}",
+                        err.field() + 1
+                    )
+                    .as_str(),
+                )
+            }
+            _ => err.into(),
+        }
+    }
 
-        let mut fields: Vec<Field> = rdr
-            .headers()?
+    fn get_csv_schema(path: &str, options: &CsvOptions) -> Result<Schema, ZakuError> {
+        let mut rdr = CSVDatasource::reader(CSVDatasource::open(path)?, options);
+        CSVDatasource::infer_schema(&mut rdr, path, options)
+    }
+
+    fn infer_schema<R: Read>(
+        rdr: &mut Reader<R>,
+        path: &str,
+        options: &CsvOptions,
+    ) -> Result<Schema, ZakuError> {
+        let (fields, datatypes) = CSVDatasource::infer_datatypes(rdr, path)?;
+        Ok(CSVDatasource::to_schema(fields, &datatypes, options))
+    }
+
+    // Infers the type of each column from a sample of the rows,
+    // which is None for columns without any values in the sample
+    fn infer_datatypes<R: Read>(
+        rdr: &mut Reader<R>,
+        path: &str,
+    ) -> Result<(Vec<Field>, Vec<Option<DataType>>), ZakuError> {
+        // Files saved by Excel start with a byte order mark, which is not part of the first header
+        let fields: Vec<Field> = rdr
+            .headers()
+            .map_err(|e| CSVDatasource::csv_error(path, e))?
             .iter()
-            .map(|h| Field::new(h.to_string(), DataType::default()))
+            .enumerate()
+            .map(|(i, h)| {
+                let name = if i == 0 {
+                    h.trim_start_matches('\u{feff}')
+                } else {
+                    h
+                };
+                Field::new(name.to_string(), DataType::default())
+            })
             .collect();
 
         let mut datatypes: Vec<Option<DataType>> = fields.iter().map(|_| None).collect();
 
         for (i, record) in rdr.records().enumerate() {
-            let r = record?;
-            r.iter().enumerate().for_each(|(i, field)| {
-                if !field.is_empty() && datatypes[i] != Some(DataType::Text) {
-                    let datatype = DataType::get_type_from_string_val(field);
-                    fields[i].set_datatype(datatype);
-                    datatypes[i] = Some(datatype);
-                }
-            });
+            let r = record.map_err(|e| CSVDatasource::csv_error(path, e))?;
+            r.iter()
+                .take(fields.len())
+                .enumerate()
+                .for_each(|(i, field)| {
+                    if !field.is_empty() && datatypes[i] != Some(DataType::Text) {
+                        datatypes[i] = Some(DataType::get_type_from_string_val(field));
+                    }
+                });
             if i == BATCH_SIZE {
                 break;
             }
         }
-        Ok(Schema::new(fields))
+        Ok((fields, datatypes))
+    }
+
+    fn to_schema(
+        mut fields: Vec<Field>,
+        datatypes: &[Option<DataType>],
+        options: &CsvOptions,
+    ) -> Schema {
+        // columns without any values in the sample take the configured type
+        fields
+            .iter_mut()
+            .zip(datatypes.iter())
+            .for_each(|(field, datatype)| {
+                field.set_datatype(datatype.unwrap_or(options.empty_column_type()))
+            });
+        Schema::new(fields)
+    }
+
+    pub fn from_csv_files(
+        paths: &[&str],
+        options: &CsvOptions,
+    ) -> Result<CSVDatasource, ZakuError> {
+        if paths.is_empty() {
+            return Err(ZakuError::new("No CSV files provided"));
+        }
+        let file_datatypes = paths
+            .iter()
+            .map(|path| {
+                let mut rdr = CSVDatasource::reader(CSVDatasource::open(path)?, options);
+                CSVDatasource::infer_datatypes(&mut rdr, path)
+            })
+            .collect::<Result<Vec<_>, ZakuError>>()?;
+
+        // a column without values in one file takes its type from the other files
+        let mut fields: Vec<Field> = vec![];
+        let mut datatypes: Vec<Option<DataType>> = vec![];
+        file_datatypes
+            .iter()
+            .flat_map(|(file_fields, file_types)| file_fields.iter().zip(file_types))
+            .for_each(|(field, datatype)| {
+                match fields.iter().position(|f| f.name() == field.name()) {
+                    Some(i) => {
+                        datatypes[i] = match (datatypes[i], datatype) {
+                            (Some(l), Some(r)) if l != *r => Some(DataType::Text),
+                            (l, r) => l.or(*r),
+                        }
+                    }
+                    None => {
+                        fields.push(field.clone());
+                        datatypes.push(*datatype);
+                    }
+                }
+            });
+        let schema = CSVDatasource::to_schema(fields, &datatypes, options);
+        let file_schemas: Vec<Schema> = file_datatypes
+            .into_iter()
+            .map(|(fields, datatypes)| CSVDatasource::to_schema(fields, &datatypes, options))
+            .collect();
+
+        let mut cols: Vec<Vec<Value>> = schema.fields().iter().map(|_| Vec::new()).collect();
+        for (path, file_schema) in paths.iter().zip(file_schemas.iter()) {
+            CSVDatasource::read_csv_columns(path, file_schema, &schema, options)?
+                .into_iter()
+                .zip(cols.iter_mut())
+                .for_each(|(file_col, col)| col.extend(file_col));
+        }
+        let record_batch = RecordBatch::to_record_batch(cols, &schema, BATCH_SIZE);
+        Ok(CSVDatasource {
+            paths: paths.iter().map(|path| path.to_string()).collect(),
+            schema,
+            data: record_batch,
+        })
     }
 
     fn load_csv_data(
         path: &str,
         schema: Schema,
-        delimiter: Option<u8>,
+        options: &CsvOptions,
     ) -> Result<Vec<RecordBatch>, ZakuError> {
-        let mut rdr = ReaderBuilder::new()
-            .delimiter(delimiter.unwrap_or(b','))
-            .from_path(path)?;
-        let schema_len = schema.fields().len();
-        let mut cols: Vec<Vec<Value>> = (0..schema_len).map(|_| Vec::new()).collect();
+        let cols = CSVDatasource::read_csv_columns(path, &schema, &schema, options)?;
+        Ok(RecordBatch::to_record_batch(cols, &schema, BATCH_SIZE))
+    }
+
+    // Reads the columns of the schema from a file with the columns of file_schema
+    fn read_csv_columns(
+        path: &str,
+        file_schema: &Schema,
+        schema: &Schema,
+        options: &CsvOptions,
+    ) -> Result<Vec<Vec<Value>>, ZakuError> {
+        let mut rdr = CSVDatasource::reader(CSVDatasource::open(path)?, options);
+        CSVDatasource::read_columns(&mut rdr, path, file_schema, schema, options)
+    }
+
+    fn read_columns<R: Read>(
+        rdr: &mut Reader<R>,
+        path: &str,
+        file_schema: &Schema,
+        schema: &Schema,
+        options: &CsvOptions,
+    ) -> Result<Vec<Vec<Value>>, ZakuError> {
+        let header_len = file_schema.fields().len();
+        let indices: Vec<Option<usize>> = schema
+            .fields()
+            .iter()
+            .map(|f| file_schema.get_index(f.name()).ok())
+            .collect();
+        let mut cols: Vec<Vec<Value>> = indices.iter().map(|_| Vec::new()).collect();
 
         for record in rdr.records() {
-            let r = record?;
-            for i in 0..schema.fields().len() {
-                let datatype = schema.get_datatype_from_index(&i)?;
-                let val = Value::get_value_from_string_val(&r[i], datatype);
+            let r = record.map_err(|e| CSVDatasource::csv_error(path, e))?;
+            if r.len() < header_len
+                || (r.len() > header_len && options.extra_fields() == ExtraFields::Error)
+            {
+                let line = r.position().map_or(0, |p| p.line());
+                return Err(ZakuError::new(
+                    format!(
+                        "Row at line {line} of {path} has {} fields, but the header has {header_len}",
+                        r.len()
+                    )
+                    .as_str(),
+                ));
+            }
+            for (i, index) in indices.iter().enumerate() {
+                let val = match index {
+                    Some(index) => {
+                        let datatype = schema.get_datatype_from_index(&i)?;
+                        Value::get_value_from_string_val(&r[*index], datatype)?
+                    }
+                    None => Value::Null,
+                };
                 cols[i].push(val);
             }
         }
-        Ok(RecordBatch::to_record_batch(cols, &schema))
+        Ok(cols)
     }
 }
 
@@ -130,7 +375,20 @@ impl Datasource for CSVDatasource {
     }
 
     fn path(&self) -> String {
-        self.path.clone()
+        self.paths.join(", ")
+    }
+
+    // the file name without its extensions, so resources/test.csv.gz is the table test
+    // sources made of several files have no single name
+    fn table_name(&self) -> Option<String> {
+        let [path] = self.paths.as_slice() else {
+            return None;
+        };
+        Path::new(path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.split('.').next())
+            .map(|name| name.to_string())
     }
 }
 
@@ -144,7 +402,8 @@ mod test {
         types::{DataType, Value},
     };
 
-    use super::CSVDatasource;
+    use super::{CSVDatasource, Datasource};
+    use crate::datasources::csv_options::{CsvOptions, ExtraFields};
 
     fn csv_test_file() -> String {
         Path::new("resources")
@@ -154,9 +413,18 @@ mod test {
             .to_string()
     }
 
+    fn gzip_csv_test_file() -> String {
+        Path::new("resources")
+            .join("test.csv.gz")
+            .to_str()
+            .expect("test.csv.gz file should exist")
+            .to_string()
+    }
+
     #[test]
     fn test_get_csv_schema() {
-        let schema = CSVDatasource::get_csv_schema(&csv_test_file(), None).unwrap();
+        let schema =
+            CSVDatasource::get_csv_schema(&csv_test_file(), &CsvOptions::default()).unwrap();
         assert_eq!(
             schema.fields(),
             &vec![
@@ -170,12 +438,75 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_bom_csv_schema() {
+        let path = Path::new("resources").join("test_bom.csv");
+        let schema =
+            CSVDatasource::get_csv_schema(path.to_str().unwrap(), &CsvOptions::default()).unwrap();
+        assert_eq!(schema.fields()[0].name(), "id");
+    }
+
+    #[test]
+    fn test_invalid_utf8_csv() {
+        let path = Path::new("resources").join("test_invalid_utf8.csv");
+        let err = CSVDatasource::from_csv(path.to_str().unwrap(), None)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ZakuError: Invalid UTF-8 in resources/test_invalid_utf8.csv at line 2, field 2"
+        );
+    }
+
+    #[test]
+    fn test_from_csv_files() {
+        let path = |name: &str| {
+            Path::new("resources")
+                .join(name)
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+        let datasource = CSVDatasource::from_csv_files(
+            &[&path("test_union_a.csv"), &path("test_union_b.csv")],
+            &CsvOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            datasource.schema().fields(),
+            &vec![
+                Field::new("id".to_string(), DataType::Number),
+                Field::new("name".to_string(), DataType::Text),
+                Field::new("price".to_string(), DataType::Number),
+                Field::new("category".to_string(), DataType::Text),
+            ]
+        );
+        assert_eq!(datasource.get_data()[0].row_count(), 4);
+        assert_eq!(datasource.table_name(), None);
+
+        // price has no values in test_union_c.csv, so it keeps its type from test_union_a.csv
+        let datasource = CSVDatasource::from_csv_files(
+            &[&path("test_union_c.csv"), &path("test_union_a.csv")],
+            &CsvOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            datasource.schema().fields(),
+            &vec![
+                Field::new("id".to_string(), DataType::Number),
+                Field::new("price".to_string(), DataType::Number),
+                Field::new("category".to_string(), DataType::Text),
+                Field::new("name".to_string(), DataType::Text),
+            ]
+        );
+    }
+
     #[test]
     fn test_load_csv_data() {
         let record_batch = &CSVDatasource::load_csv_data(
             &csv_test_file(),
-            CSVDatasource::get_csv_schema(&csv_test_file(), None).unwrap(),
-            None,
+            CSVDatasource::get_csv_schema(&csv_test_file(), &CsvOptions::default()).unwrap(),
+            &CsvOptions::default(),
         )
         .unwrap()[0];
         assert_eq!(record_batch.row_count(), 5);
@@ -240,4 +571,106 @@ mod test {
         ];
         assert_eq!(cols, &ex_cols);
     }
+
+    #[test]
+    fn test_from_reader() {
+        let input = "id,name,price\n1,apple,0.50\n2,pear,\n";
+        let datasource =
+            CSVDatasource::from_reader(input.as_bytes(), "stdin", &CsvOptions::default()).unwrap();
+        assert_eq!(
+            datasource.schema().fields(),
+            &vec![
+                Field::new("id".to_string(), DataType::Number),
+                Field::new("name".to_string(), DataType::Text),
+                Field::new("price".to_string(), DataType::Number),
+            ]
+        );
+        assert_eq!(datasource.get_data()[0].row_count(), 2);
+        assert_eq!(datasource.table_name(), Some("stdin".to_string()));
+        let named =
+            CSVDatasource::from_reader(input.as_bytes(), "fruit, veg", &CsvOptions::default())
+                .unwrap();
+        assert_eq!(named.table_name(), Some("fruit, veg".to_string()));
+
+        let file = std::fs::File::open(csv_test_file()).unwrap();
+        let csv = CSVDatasource::from_csv(&csv_test_file(), None).unwrap();
+        let read = CSVDatasource::from_reader(file, "test", &CsvOptions::default()).unwrap();
+        assert_eq!(read.schema, csv.schema);
+        assert_eq!(read.data, csv.data);
+    }
+
+    #[test]
+    fn test_extra_fields() {
+        let path = Path::new("resources").join("test_extra_fields.csv");
+        let path = path.to_str().unwrap();
+        let err = CSVDatasource::from_csv(path, None).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ZakuError: Row at line 3 of resources/test_extra_fields.csv has 3 fields, but the header has 2"
+        );
+
+        let options = CsvOptions::default().with_extra_fields(ExtraFields::Ignore);
+        let datasource = CSVDatasource::from_csv_with_options(path, &options).unwrap();
+        assert_eq!(
+            datasource.schema().fields(),
+            &vec![
+                Field::new("id".to_string(), DataType::Number),
+                Field::new("name".to_string(), DataType::Text),
+            ]
+        );
+        assert_eq!(datasource.get_data()[0].row_count(), 3);
+
+        let extra = "id,name\n1,apple\n2,pear,0.5\n";
+        let datasource = CSVDatasource::from_reader(extra.as_bytes(), "stdin", &options).unwrap();
+        assert_eq!(datasource.schema().fields().len(), 2);
+        assert_eq!(datasource.get_data()[0].row_count(), 2);
+
+        let short = "id,name\n1,apple\n2\n";
+        let err = CSVDatasource::from_reader(short.as_bytes(), "stdin", &options)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ZakuError: Row at line 3 of stdin has 1 fields, but the header has 2"
+        );
+    }
+
+    #[test]
+    fn test_empty_column_type() {
+        let path = Path::new("resources").join("test_empty_column.csv");
+        let path = path.to_str().unwrap();
+        let datasource = CSVDatasource::from_csv(path, None).unwrap();
+        assert_eq!(datasource.schema().fields()[1].datatype(), &DataType::Text);
+
+        let options = CsvOptions::default().with_empty_column_type(DataType::Number);
+        let datasource = CSVDatasource::from_csv_with_options(path, &options).unwrap();
+        assert_eq!(
+            datasource.schema().fields(),
+            &vec![
+                Field::new("id".to_string(), DataType::Number),
+                Field::new("note".to_string(), DataType::Number),
+                Field::new("price".to_string(), DataType::Number),
+            ]
+        );
+        let col = &datasource.get_data()[0].columns()[1];
+        assert_eq!(
+            col,
+            &Arc::new(Vectors::ColumnVector(ColumnVector::new(
+                DataType::Number,
+                vec![Value::Null, Value::Null],
+            )))
+        );
+
+        let file = std::fs::File::open(path).unwrap();
+        let read = CSVDatasource::from_reader(file, "stdin", &options).unwrap();
+        assert_eq!(read.schema, datasource.schema);
+    }
+
+    #[test]
+    fn test_from_gzip_csv() {
+        let csv = CSVDatasource::from_csv(&csv_test_file(), None).unwrap();
+        let gzip_csv = CSVDatasource::from_csv(&gzip_csv_test_file(), None).unwrap();
+        assert_eq!(gzip_csv.schema, csv.schema);
+        assert_eq!(gzip_csv.data, csv.data);
+    }
 }
@@ -1,2 +1,4 @@
+pub mod csv_options;
 pub mod datasink;
 pub mod datasource;
+pub mod output_summary;
@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use crate::{
+    datatypes::{
+        column_vector::{ColumnVector, Vector, Vectors},
+        record_batch::RecordBatch,
+        schema::{Field, Schema},
+        types::{DataType, Value},
+    },
+    error::ZakuError,
+    physical_plans::accumulator::{Accumulator, Avg, Count, Max, Min},
+};
+
+struct ColumnSummary {
+    index: usize,
+    name: String,
+    count: Count,
+    min: Min,
+    max: Max,
+    mean: Avg,
+}
+
+// Running count, min, max and mean of the numeric columns of a result,
+// updated with each batch as it is printed so that the query does not have to be run again.
+// Nulls are skipped, as in the aggregates
+pub struct OutputSummary {
+    columns: Vec<ColumnSummary>,
+}
+
+impl OutputSummary {
+    pub fn new(schema: &Schema) -> OutputSummary {
+        let columns = schema
+            .fields()
+            .iter()
+            .enumerate()
+            .filter(|(_, field)| field.datatype().is_numeric())
+            .map(|(index, field)| ColumnSummary {
+                index,
+                name: field.name().clone(),
+                count: Count::new(),
+                min: Min::new(),
+                max: Max::new(),
+                mean: Avg::new(),
+            })
+            .collect();
+        OutputSummary { columns }
+    }
+
+    pub fn update(&mut self, rb: &RecordBatch) -> Result<(), ZakuError> {
+        for column in self.columns.iter_mut() {
+            let values = rb.get(&column.index)?;
+            for i in 0..rb.row_count() {
+                let value = values.get_value(&i);
+                if value == &Value::Null {
+                    continue;
+                }
+                column.count.accumulate(value)?;
+                column.min.accumulate(value)?;
+                column.max.accumulate(value)?;
+                column.mean.accumulate(value)?;
+            }
+        }
+        Ok(())
+    }
+
+    // One row per numeric column, or None if the result has no numeric columns
+    pub fn to_record_batch(&self) -> Result<Option<RecordBatch>, ZakuError> {
+        if self.columns.is_empty() {
+            return Ok(None);
+        }
+        let schema = Schema::new(vec![
+            Field::new("column".to_string(), DataType::Text),
+            Field::new("count".to_string(), DataType::Number),
+            Field::new("min".to_string(), DataType::Number),
+            Field::new("max".to_string(), DataType::Number),
+            Field::new("mean".to_string(), DataType::Number),
+        ]);
+        let mut cols = vec![vec![], vec![], vec![], vec![], vec![]];
+        for column in &self.columns {
+            cols[0].push(Value::Text(column.name.clone()));
+            cols[1].push(column.count.get_value()?);
+            cols[2].push(column.min.get_value()?);
+            cols[3].push(column.max.get_value()?);
+            cols[4].push(column.mean.get_value()?);
+        }
+        let cols = schema
+            .fields()
+            .iter()
+            .zip(cols)
+            .map(|(field, values)| {
+                Arc::new(Vectors::ColumnVector(ColumnVector::new(
+                    *field.datatype(),
+                    values,
+                )))
+            })
+            .collect();
+        Ok(Some(RecordBatch::new(schema, cols)))
+    }
+}
@@ -12,12 +12,6 @@ pub trait Vector {
 
     fn size(&self) -> usize;
 
-    fn sort_indices(&self) -> Vec<usize> {
-        let mut indices = (0..self.size()).collect::<Vec<_>>();
-        indices.sort_by_key(|&i| self.get_value(&i));
-        indices
-    }
-
     fn reorder(&self, indices: &[usize]) -> Vectors;
 }
 
@@ -0,0 +1,53 @@
+use std::hash::{BuildHasher, DefaultHasher, RandomState};
+
+// Builds the hashers used to group rows by their values
+// The fixed variant hashes with constant keys, so the same rows always land in the same buckets,
+// making grouping and partitioning reproducible between runs of the same build
+#[derive(Clone, Debug)]
+pub enum ValueHasher {
+    Random(RandomState),
+    Fixed,
+}
+
+impl ValueHasher {
+    pub fn new(deterministic: bool) -> ValueHasher {
+        if deterministic {
+            ValueHasher::Fixed
+        } else {
+            ValueHasher::Random(RandomState::new())
+        }
+    }
+}
+
+impl BuildHasher for ValueHasher {
+    type Hasher = DefaultHasher;
+
+    fn build_hasher(&self) -> DefaultHasher {
+        match self {
+            ValueHasher::Random(state) => state.build_hasher(),
+            ValueHasher::Fixed => DefaultHasher::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{hash::BuildHasher, str::FromStr};
+
+    use bigdecimal::BigDecimal;
+
+    use super::ValueHasher;
+    use crate::datatypes::types::Value;
+
+    #[test]
+    fn test_fixed_hasher_is_stable() {
+        let key = vec![
+            Value::Text("shampoo".to_string()),
+            Value::Number(BigDecimal::from_str("15.50").unwrap()),
+            Value::Null,
+        ];
+        let first = ValueHasher::new(true).hash_one(&key);
+        let second = ValueHasher::new(true).hash_one(&key);
+        assert_eq!(first, second);
+    }
+}
@@ -1,4 +1,5 @@
 pub mod column_vector;
+pub mod hash;
 mod prettifier;
 pub mod record_batch;
 pub mod schema;
@@ -10,7 +10,7 @@ pub struct RecordBatchPrettifier<'a> {
 }
 
 impl RecordBatchPrettifier<'_> {
-    pub fn new(rb: &RecordBatch, with_schema: bool) -> RecordBatchPrettifier {
+    pub fn new(rb: &RecordBatch, with_schema: bool) -> RecordBatchPrettifier<'_> {
         RecordBatchPrettifier { rb, with_schema }
     }
 
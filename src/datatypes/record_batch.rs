@@ -1,4 +1,8 @@
-use std::sync::Arc;
+use std::{
+    cmp::Ordering,
+    fmt::{Display, Formatter},
+    sync::Arc,
+};
 
 use crate::error::ZakuError;
 
@@ -11,6 +15,107 @@ use super::{
 
 pub static BATCH_SIZE: usize = 1024;
 
+// How text sort keys are compared
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Collation {
+    #[default]
+    Binary,
+    // ignores case, as SQLite's NOCASE
+    NoCase,
+}
+
+impl std::str::FromStr for Collation {
+    type Err = ZakuError;
+
+    fn from_str(s: &str) -> Result<Collation, ZakuError> {
+        match s.to_lowercase().as_str() {
+            "binary" => Ok(Collation::Binary),
+            "nocase" => Ok(Collation::NoCase),
+            _ => Err(ZakuError::new(format!("Unknown collation: {s}").as_str())),
+        }
+    }
+}
+
+// The order of a single sort key. By default nulls are larger than any value,
+// so they come last in ascending and first in descending order, as in Postgres
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SortOptions {
+    asc: bool,
+    nulls_first: bool,
+    collation: Collation,
+}
+
+impl SortOptions {
+    pub fn new(asc: bool) -> SortOptions {
+        SortOptions {
+            asc,
+            nulls_first: !asc,
+            collation: Collation::default(),
+        }
+    }
+
+    pub fn with_nulls_first(mut self, nulls_first: bool) -> SortOptions {
+        self.nulls_first = nulls_first;
+        self
+    }
+
+    pub fn with_collation(mut self, collation: Collation) -> SortOptions {
+        self.collation = collation;
+        self
+    }
+
+    // nulls are placed regardless of the direction
+    fn compare(&self, a: &Value, b: &Value) -> Ordering {
+        let ordering = match (a, b, self.collation) {
+            (Value::Null, Value::Null, _) => return Ordering::Equal,
+            (Value::Null, _, _) if self.nulls_first => return Ordering::Less,
+            (Value::Null, _, _) => return Ordering::Greater,
+            (_, Value::Null, _) if self.nulls_first => return Ordering::Greater,
+            (_, Value::Null, _) => return Ordering::Less,
+            (Value::Text(a), Value::Text(b), Collation::NoCase) => {
+                a.to_lowercase().cmp(&b.to_lowercase())
+            }
+            _ => a.cmp(b),
+        };
+        if self.asc {
+            ordering
+        } else {
+            ordering.reverse()
+        }
+    }
+}
+
+// Only the modifiers that differ from the defaults are shown
+impl Display for SortOptions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.collation == Collation::NoCase {
+            write!(f, "collate nocase ")?;
+        }
+        write!(f, "{}", if self.asc { "asc" } else { "desc" })?;
+        match self.nulls_first {
+            nulls_first if nulls_first == !self.asc => Ok(()),
+            true => write!(f, " nulls first"),
+            false => write!(f, " nulls last"),
+        }
+    }
+}
+
+// Returns the row indices in sorted order, given the values of each sort key (column-format)
+// Ties on all sort keys are broken by the original row position, so rows with equal keys
+// keep their input order and the output is deterministic
+pub fn sort_indices(keys: &[Vec<Value>], options: &[SortOptions]) -> Vec<usize> {
+    let row_count = keys.first().map_or(0, |k| k.len());
+    let mut indices: Vec<usize> = (0..row_count).collect();
+    indices.sort_by(|a, b| {
+        keys.iter()
+            .zip(options.iter())
+            .map(|(key, options)| options.compare(&key[*a], &key[*b]))
+            .find(|ordering| ordering != &Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    });
+    indices
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct RecordBatch {
     schema: Schema,
@@ -26,7 +131,7 @@ impl RecordBatch {
         &self.schema
     }
 
-    pub fn iter(&self) -> RecordBatchIterator {
+    pub fn iter(&self) -> RecordBatchIterator<'_> {
         RecordBatchIterator {
             record_batch: self,
             index: 0,
@@ -53,20 +158,20 @@ impl RecordBatch {
     }
 
     pub fn sort(&self, keys: &[usize], asc: &[bool]) -> Result<RecordBatch, ZakuError> {
-        let mut sorted_cols = self.columns.clone();
-        let asc: Vec<&bool> = asc.iter().rev().collect();
-        keys.iter().rev().enumerate().for_each(|(i, k)| {
-            let mut new_sorted_cols = vec![];
-            let mut indices = sorted_cols[*k].sort_indices();
-            if !asc[i] {
-                indices.reverse();
-            }
-
-            sorted_cols.iter().for_each(|col| {
-                new_sorted_cols.push(Arc::new(col.reorder(&indices)));
-            });
-            sorted_cols = new_sorted_cols;
-        });
+        let key_values = keys
+            .iter()
+            .map(|k| Ok(self.get(k)?.iter().cloned().collect()))
+            .collect::<Result<Vec<Vec<Value>>, ZakuError>>()?;
+        let options = asc
+            .iter()
+            .map(|asc| SortOptions::new(*asc))
+            .collect::<Vec<_>>();
+        let indices = sort_indices(&key_values, &options);
+        let sorted_cols = self
+            .columns
+            .iter()
+            .map(|col| Arc::new(col.reorder(&indices)))
+            .collect();
         Ok(RecordBatch::new(self.schema.clone(), sorted_cols))
     }
 
@@ -86,20 +191,24 @@ impl RecordBatch {
     }
 
     // Takes a vector of vectors (column-format) and converts it to record batches
-    pub fn to_record_batch(cols: Vec<Vec<Value>>, schema: &Schema) -> Vec<RecordBatch> {
+    pub fn to_record_batch(
+        cols: Vec<Vec<Value>>,
+        schema: &Schema,
+        batch_size: usize,
+    ) -> Vec<RecordBatch> {
         let schema_len = schema.fields().len();
-        let num_batches = (cols[0].len() / BATCH_SIZE) + 1;
+        let num_batches = (cols[0].len() / batch_size) + 1;
         let mut seg_cols: Vec<Vec<Vec<Value>>> = (0..num_batches)
             .map(|_| {
                 (0..schema_len)
-                    .map(|_| Vec::with_capacity(BATCH_SIZE))
+                    .map(|_| Vec::with_capacity(batch_size))
                     .collect()
             })
             .collect();
 
         for (i, col) in cols.iter().enumerate() {
             for (j, val) in col.iter().enumerate() {
-                let batch_no = j / BATCH_SIZE;
+                let batch_no = j / batch_size;
                 seg_cols[batch_no][i].push(val.clone());
             }
         }
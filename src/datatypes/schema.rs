@@ -10,6 +10,21 @@ use super::{
 };
 use crate::error::ZakuError;
 
+// Levenshtein distance between the characters of two names
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut curr = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        prev = curr;
+    }
+    prev[b.len()]
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Field {
     name: String,
@@ -44,13 +59,33 @@ impl Schema {
         Schema { fields }
     }
 
+    pub fn from_pairs(pairs: &[(&str, DataType)]) -> Schema {
+        let fields = pairs
+            .iter()
+            .map(|(name, datatype)| Field::new(name.to_string(), *datatype))
+            .collect();
+        Schema::new(fields)
+    }
+
+    // suggests the closest field, when it is within a third of the length of the name
+    fn field_not_found(&self, field: &str) -> ZakuError {
+        let max_distance = (field.chars().count() / 3).max(1);
+        let suggestion = self
+            .fields
+            .iter()
+            .map(|f| (edit_distance(field, &f.name), &f.name))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, name)| format!(" (did you mean '{}'?)", name))
+            .unwrap_or_default();
+        ZakuError::new(format!("Field '{}' not found{}", field, suggestion).as_str())
+    }
+
     pub fn get_field(&self, field: &String) -> Result<&Field, ZakuError> {
         self.fields
             .iter()
             .find(|f| &f.name == field)
-            .ok_or(ZakuError::new(
-                format!("Field '{}' not found", field).as_str(),
-            ))
+            .ok_or_else(|| self.field_not_found(field))
     }
 
     pub fn get_field_by_index(&self, index: &usize) -> Result<&Field, ZakuError> {
@@ -66,9 +101,7 @@ impl Schema {
         self.fields
             .iter()
             .position(|f| &f.name == field)
-            .ok_or(ZakuError::new(
-                format!("Field '{}' not found", field).as_str(),
-            ))
+            .ok_or_else(|| self.field_not_found(field))
     }
 
     pub fn fields(&self) -> &Vec<Field> {
@@ -80,9 +113,7 @@ impl Schema {
             .iter()
             .find(|f| &f.name == field)
             .map(|f| &f.datatype)
-            .ok_or(ZakuError::new(
-                format!("Field '{}' not found", field).as_str(),
-            ))
+            .ok_or_else(|| self.field_not_found(field))
     }
 
     pub fn get_datatype_from_index(&self, index: &usize) -> Result<&DataType, ZakuError> {
@@ -92,14 +123,13 @@ impl Schema {
         Ok(&self.fields[*index].datatype)
     }
 
-    pub fn select(&self, fields: &[String]) -> Schema {
+    // errors on fields that don't exist, so that a mistyped column is not silently dropped
+    pub fn select(&self, fields: &[String]) -> Result<Schema, ZakuError> {
         let selected_fields = fields
             .iter()
-            .map(|f| self.get_field(f))
-            .filter(|f| f.is_ok()) // Ignore fields that don't exist
-            .map(|f| f.expect("Field should be returned as an Ok result").clone())
-            .collect();
-        Schema::new(selected_fields)
+            .map(|f| self.get_field(f).cloned())
+            .collect::<Result<Vec<Field>, ZakuError>>()?;
+        Ok(Schema::new(selected_fields))
     }
 
     pub fn as_header(&self) -> Vec<String> {
@@ -176,15 +206,60 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_from_pairs() {
+        let schema = Schema::from_pairs(&[
+            ("id", DataType::Number),
+            ("name", DataType::Text),
+            ("age", DataType::Number),
+            ("weight", DataType::Number),
+        ]);
+        assert_eq!(schema, get_schema());
+    }
+
     #[test]
     fn test_select() {
         let schema = get_schema();
-        let selected_schema = schema.select(&["id".to_string(), "name".to_string()]);
+        let selected_schema = schema
+            .select(&["id".to_string(), "name".to_string()])
+            .unwrap();
         let ex_fields = vec![
             Field::new("id".to_string(), DataType::Number),
             Field::new("name".to_string(), DataType::Text),
         ];
         let ex_schema = Schema::new(ex_fields);
         assert_eq!(selected_schema, ex_schema);
+
+        let err = schema
+            .select(&["id".to_string(), "nme".to_string()])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ZakuError: Field 'nme' not found (did you mean 'name'?)"
+        );
+    }
+
+    #[test]
+    fn test_field_not_found_suggestion() {
+        let schema = get_schema();
+        let err = |field: &str| {
+            schema
+                .get_index(&field.to_string())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            err("wieght"),
+            "ZakuError: Field 'wieght' not found (did you mean 'weight'?)"
+        );
+        assert_eq!(
+            schema
+                .get_datatype(&"Age".to_string())
+                .unwrap_err()
+                .to_string(),
+            "ZakuError: Field 'Age' not found (did you mean 'age'?)"
+        );
+        assert_eq!(err("salary"), "ZakuError: Field 'salary' not found");
+        assert_eq!(err("x"), "ZakuError: Field 'x' not found");
     }
 }
@@ -3,8 +3,8 @@ use std::{
     hash::Hash,
 };
 
-use bigdecimal::BigDecimal;
-use chrono::NaiveDate;
+use bigdecimal::{BigDecimal, RoundingMode, ToPrimitive, Zero};
+use chrono::{Datelike, Months, NaiveDate};
 use std::str::FromStr;
 
 use crate::ZakuError;
@@ -16,9 +16,87 @@ pub enum DataType {
     Boolean,
     Number,
     Date,
+    // numbers rounded to a declared scale, with at most precision digits in total
+    Decimal {
+        precision: u8,
+        scale: u8,
+    },
+    // values collected by ARRAY_AGG, which can hold elements of any type
+    List,
 }
 
+// + is reserved for numbers, as in Postgres
+pub fn text_addition_error() -> ZakuError {
+    ZakuError::new("Operator + is not supported for text values, use || to concatenate strings")
+}
+
+// lists have no ordering or equality, so comparisons with a list on the left fail
+fn list_comparison_error() -> ZakuError {
+    ZakuError::new("Lists cannot be compared")
+}
+
+// the largest precision a decimal can be declared with, as in most databases
+pub const MAX_DECIMAL_PRECISION: u8 = 38;
+
 impl DataType {
+    pub fn decimal(precision: u8, scale: u8) -> Result<DataType, ZakuError> {
+        if precision == 0 || precision > MAX_DECIMAL_PRECISION || scale > precision {
+            return Err(ZakuError::new(
+                format!(
+                    "Invalid decimal({precision},{scale}), precision must be between 1 and \
+                     {MAX_DECIMAL_PRECISION} and at least the scale"
+                )
+                .as_str(),
+            ));
+        }
+        Ok(DataType::Decimal { precision, scale })
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, DataType::Number | DataType::Decimal { .. })
+    }
+
+    // whether values of the types can be compared or combined, as numbers and decimals can
+    pub fn is_compatible_with(&self, other: &DataType) -> bool {
+        self == other || (self.is_numeric() && other.is_numeric())
+    }
+
+    // The type of arithmetic on two numeric types. A decimal keeps its precision and scale
+    // when combined with a number, and two decimals combine to the larger of each
+    pub fn numeric_result(&self, other: &DataType) -> Option<DataType> {
+        match (self, other) {
+            (DataType::Number, DataType::Number) => Some(DataType::Number),
+            (decimal @ DataType::Decimal { .. }, DataType::Number)
+            | (DataType::Number, decimal @ DataType::Decimal { .. }) => Some(*decimal),
+            (
+                DataType::Decimal { precision, scale },
+                DataType::Decimal {
+                    precision: other_precision,
+                    scale: other_scale,
+                },
+            ) => Some(DataType::Decimal {
+                precision: *precision.max(other_precision),
+                scale: *scale.max(other_scale),
+            }),
+            _ => None,
+        }
+    }
+
+    // whether values of this type can be cast to the target, checked when planning
+    pub fn can_cast_to(&self, target: &DataType) -> bool {
+        let numeric = |datatype: &DataType| match datatype {
+            DataType::Decimal { .. } => DataType::Number,
+            datatype => *datatype,
+        };
+        matches!(
+            (numeric(self), numeric(target)),
+            (_, DataType::Text)
+                | (DataType::Text, _)
+                | (DataType::Boolean, DataType::Number)
+                | (DataType::Number, DataType::Boolean)
+        ) || numeric(self) == numeric(target)
+    }
+
     pub fn get_type_from_string_val(val: &str) -> DataType {
         if parse_iso_date_from_str(val).is_ok() {
             return DataType::Date;
@@ -33,6 +111,32 @@ impl DataType {
     }
 }
 
+impl FromStr for DataType {
+    type Err = ZakuError;
+
+    // Accepts the names printed by Display, as well as the short forms num and bool
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unknown = || ZakuError::new(format!("Unknown datatype: {}", s).as_str());
+        match s.to_lowercase().replace(' ', "").as_str() {
+            "text" => Ok(DataType::Text),
+            "number" | "num" => Ok(DataType::Number),
+            "boolean" | "bool" => Ok(DataType::Boolean),
+            "date" => Ok(DataType::Date),
+            decimal => {
+                let (precision, scale) = decimal
+                    .strip_prefix("decimal(")
+                    .and_then(|args| args.strip_suffix(')'))
+                    .and_then(|args| args.split_once(','))
+                    .ok_or_else(unknown)?;
+                DataType::decimal(
+                    precision.parse().map_err(|_| unknown())?,
+                    scale.parse().map_err(|_| unknown())?,
+                )
+            }
+        }
+    }
+}
+
 impl Display for DataType {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -40,6 +144,8 @@ impl Display for DataType {
             DataType::Boolean => write!(f, "boolean"),
             DataType::Number => write!(f, "number"),
             DataType::Date => write!(f, "date"),
+            DataType::Decimal { precision, scale } => write!(f, "decimal({},{})", precision, scale),
+            DataType::List => write!(f, "list"),
         }
     }
 }
@@ -50,6 +156,7 @@ pub enum Value {
     Text(String),
     Boolean(bool),
     Date(NaiveDate),
+    List(Vec<Value>),
     Null,
 }
 
@@ -66,295 +173,576 @@ impl Value {
         Value::Date(parse_iso_date_from_str(val).expect("Val should be a date value"))
     }
 
-    pub fn get_value_from_string_val(val: &str, datatype: &DataType) -> Value {
+    // Numbers are exact decimals, which cannot hold NaN or infinity, so any computation done
+    // in floating point errors when its result is not finite, as division by zero does
+    fn from_f64(res: f64, operation: &str) -> Result<Value, ZakuError> {
+        if !res.is_finite() {
+            return Err(ZakuError::new(
+                format!("{operation} result is not a finite number").as_str(),
+            ));
+        }
+        Ok(Value::Number(BigDecimal::from_str(&res.to_string())?))
+    }
+
+    pub fn get_value_from_string_val(val: &str, datatype: &DataType) -> Result<Value, ZakuError> {
         if val.is_empty() {
-            return Value::Null;
+            return Ok(Value::Null);
         }
         match datatype {
-            DataType::Number => Value::Number(
-                BigDecimal::from_str(val.replace(',', "").as_str())
-                    .unwrap_or_else(|_| panic!("Expected number, got {val}")),
-            ),
-            DataType::Date => Value::Date(
-                parse_iso_date_from_str(val)
-                    .unwrap_or_else(|_| panic!("Expected date, got '{val}'")),
-            ),
-            DataType::Boolean => Value::Boolean(
-                val.parse::<bool>()
-                    .unwrap_or_else(|_| panic!("Expected boolean, got '{val}'")),
-            ),
-            DataType::Text => Value::Text(val.to_string()),
+            DataType::Number => BigDecimal::from_str(val.replace(',', "").as_str())
+                .map(Value::Number)
+                .map_err(|_| ZakuError::new(format!("Expected number, got '{val}'").as_str())),
+            DataType::Decimal { .. } => {
+                Value::get_value_from_string_val(val, &DataType::Number)?.round_to_type(datatype)
+            }
+            DataType::Date => parse_iso_date_from_str(val)
+                .map(Value::Date)
+                .map_err(|_| ZakuError::new(format!("Expected date, got '{val}'").as_str())),
+            DataType::Boolean => val
+                .parse::<bool>()
+                .map(Value::Boolean)
+                .map_err(|_| ZakuError::new(format!("Expected boolean, got '{val}'").as_str())),
+            DataType::Text => Ok(Value::Text(val.to_string())),
+            DataType::List => Err(ZakuError::new(
+                format!("Cannot read a list from '{val}'").as_str(),
+            )),
         }
     }
 
-    pub fn and(&self, other: &Value) -> Value {
-        match self {
-            Value::Boolean(l) => match other {
-                Value::Boolean(r) => Value::Boolean(*l && *r),
-                _ => panic!("Type mismatch"),
-            },
-            _ => panic!("Type not supported for and"),
+    // Every conversion between values goes through here, so CAST and any implicit coercion agree
+    // Nulls stay null, text is parsed into the target type, and any value can be written as text
+    // Which casts are allowed is decided by DataType::can_cast_to, so that plans are checked
+    // against the same rules that are applied to the values
+    pub fn cast(&self, target: &DataType) -> Result<Value, ZakuError> {
+        let datatype = match self {
+            Value::Null => return Ok(Value::Null),
+            Value::Number(_) => DataType::Number,
+            Value::Text(_) => DataType::Text,
+            Value::Boolean(_) => DataType::Boolean,
+            Value::Date(_) => DataType::Date,
+            Value::List(_) => DataType::List,
+        };
+        if !datatype.can_cast_to(target) {
+            return Err(ZakuError::new(
+                format!("Cannot cast {} to {}", self, target).as_str(),
+            ));
+        }
+        match (self, target) {
+            (Value::Text(s), datatype) => Value::get_value_from_string_val(s, datatype),
+            (value, DataType::Text) => Ok(Value::Text(value.to_string())),
+            (Value::Boolean(b), DataType::Number) => Ok(Value::Number(BigDecimal::from(*b as i32))),
+            (Value::Boolean(_) | Value::Number(_), DataType::Decimal { .. }) => {
+                self.cast(&DataType::Number)?.round_to_type(target)
+            }
+            // as in SQL, zero is false and any other number is true
+            (Value::Number(n), DataType::Boolean) => Ok(Value::Boolean(!n.is_zero())),
+            _ => Ok(self.clone()),
+        }
+    }
+
+    // Rounds numbers half away from zero to the scale of a decimal type, and checks that they fit
+    // its precision. Values of other types are returned as they are
+    pub fn round_to_type(&self, datatype: &DataType) -> Result<Value, ZakuError> {
+        match (self, datatype) {
+            (Value::Number(n), DataType::Decimal { precision, scale }) => {
+                let rounded = n.with_scale_round(*scale as i64, RoundingMode::HalfUp);
+                if rounded.digits() > *precision as u64 {
+                    return Err(ZakuError::new(
+                        format!("Value {} does not fit in {}", n, datatype).as_str(),
+                    ));
+                }
+                Ok(Value::Number(rounded))
+            }
+            _ => Ok(self.clone()),
+        }
+    }
+
+    // AND, OR and NOT follow SQL's three-valued logic, where null represents an unknown value
+    pub fn and(&self, other: &Value) -> Result<Value, ZakuError> {
+        match (self, other) {
+            (Value::Boolean(false), Value::Boolean(_) | Value::Null)
+            | (Value::Boolean(_) | Value::Null, Value::Boolean(false)) => Ok(Value::Boolean(false)),
+            (Value::Boolean(true), Value::Boolean(true)) => Ok(Value::Boolean(true)),
+            (Value::Boolean(_) | Value::Null, Value::Boolean(_) | Value::Null) => Ok(Value::Null),
+            (Value::Boolean(_) | Value::Null, _) => Err(ZakuError::new("Type mismatch")),
+            _ => Err(ZakuError::new("Type not supported for and")),
+        }
+    }
+
+    pub fn or(&self, other: &Value) -> Result<Value, ZakuError> {
+        match (self, other) {
+            (Value::Boolean(true), Value::Boolean(_) | Value::Null)
+            | (Value::Boolean(_) | Value::Null, Value::Boolean(true)) => Ok(Value::Boolean(true)),
+            (Value::Boolean(false), Value::Boolean(false)) => Ok(Value::Boolean(false)),
+            (Value::Boolean(_) | Value::Null, Value::Boolean(_) | Value::Null) => Ok(Value::Null),
+            (Value::Boolean(_) | Value::Null, _) => Err(ZakuError::new("Type mismatch")),
+            _ => Err(ZakuError::new("Type not supported for or")),
         }
     }
 
-    pub fn or(&self, other: &Value) -> Value {
+    pub fn not(&self) -> Result<Value, ZakuError> {
         match self {
-            Value::Boolean(l) => match other {
-                Value::Boolean(r) => Value::Boolean(*l || *r),
-                _ => panic!("Type mismatch"),
-            },
-            _ => panic!("Type not supported for or"),
+            Value::Boolean(b) => Ok(Value::Boolean(!b)),
+            Value::Null => Ok(Value::Null),
+            _ => Err(ZakuError::new("Type not supported for not")),
         }
     }
 
-    pub fn eq(&self, other: &Value) -> Value {
+    pub fn eq(&self, other: &Value) -> Result<Value, ZakuError> {
         match self {
             Value::Number(l) => match other {
-                Value::Number(r) => Value::Boolean(*l == *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Number(r) => Ok(Value::Boolean(*l == *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
             Value::Boolean(l) => match other {
-                Value::Boolean(r) => Value::Boolean(*l == *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Boolean(r) => Ok(Value::Boolean(*l == *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
             Value::Text(l) => match other {
-                Value::Text(r) => Value::Boolean(*l == *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Text(r) => Ok(Value::Boolean(*l == *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
             Value::Date(l) => match other {
-                Value::Date(r) => Value::Boolean(*l == *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Date(r) => Ok(Value::Boolean(*l == *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
-            Value::Null => Value::Boolean(false),
+            Value::List(_) => Err(list_comparison_error()),
+            Value::Null => Ok(Value::Null),
+        }
+    }
+
+    // null-safe equality, where two nulls are equal and the result is never null
+    pub fn not_distinct(&self, other: &Value) -> Result<Value, ZakuError> {
+        match (self, other) {
+            (Value::Null, Value::Null) => Ok(Value::Boolean(true)),
+            (Value::Null, _) | (_, Value::Null) => Ok(Value::Boolean(false)),
+            _ => self.eq(other),
         }
     }
 
-    pub fn neq(&self, other: &Value) -> Value {
+    pub fn neq(&self, other: &Value) -> Result<Value, ZakuError> {
         match self {
             Value::Number(l) => match other {
-                Value::Number(r) => Value::Boolean(*l != *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Number(r) => Ok(Value::Boolean(*l != *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
             Value::Boolean(l) => match other {
-                Value::Boolean(r) => Value::Boolean(*l != *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Boolean(r) => Ok(Value::Boolean(*l != *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
             Value::Text(l) => match other {
-                Value::Text(r) => Value::Boolean(*l != *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Text(r) => Ok(Value::Boolean(*l != *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
             Value::Date(l) => match other {
-                Value::Date(r) => Value::Boolean(*l != *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Date(r) => Ok(Value::Boolean(*l != *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
-            Value::Null => Value::Boolean(false),
+            Value::List(_) => Err(list_comparison_error()),
+            Value::Null => Ok(Value::Null),
         }
     }
 
-    pub fn gt(&self, other: &Value) -> Value {
+    pub fn gt(&self, other: &Value) -> Result<Value, ZakuError> {
         match self {
             Value::Number(l) => match other {
-                Value::Number(r) => Value::Boolean(*l > *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Number(r) => Ok(Value::Boolean(*l > *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
-            Value::Boolean(_) => panic!("Type mismatch"),
+            Value::Boolean(_) => Err(ZakuError::new("Type mismatch")),
             Value::Text(l) => match other {
-                Value::Text(r) => Value::Boolean(*l > *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Text(r) => Ok(Value::Boolean(*l > *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
             Value::Date(l) => match other {
-                Value::Date(r) => Value::Boolean(*l > *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Date(r) => Ok(Value::Boolean(*l > *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
-            Value::Null => Value::Boolean(false),
+            Value::List(_) => Err(list_comparison_error()),
+            Value::Null => Ok(Value::Null),
         }
     }
 
-    pub fn gte(&self, other: &Value) -> Value {
+    pub fn gte(&self, other: &Value) -> Result<Value, ZakuError> {
         match self {
             Value::Number(l) => match other {
-                Value::Number(r) => Value::Boolean(*l >= *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Number(r) => Ok(Value::Boolean(*l >= *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
-            Value::Boolean(_) => panic!("Type mismatch"),
+            Value::Boolean(_) => Err(ZakuError::new("Type mismatch")),
             Value::Text(l) => match other {
-                Value::Text(r) => Value::Boolean(*l >= *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Text(r) => Ok(Value::Boolean(*l >= *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
             Value::Date(l) => match other {
-                Value::Date(r) => Value::Boolean(*l >= *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Date(r) => Ok(Value::Boolean(*l >= *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
-            Value::Null => Value::Boolean(false),
+            Value::List(_) => Err(list_comparison_error()),
+            Value::Null => Ok(Value::Null),
         }
     }
 
-    pub fn lt(&self, other: &Value) -> Value {
+    pub fn lt(&self, other: &Value) -> Result<Value, ZakuError> {
         match self {
             Value::Number(l) => match other {
-                Value::Number(r) => Value::Boolean(*l < *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Number(r) => Ok(Value::Boolean(*l < *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
-            Value::Boolean(_) => panic!("Type mismatch"),
+            Value::Boolean(_) => Err(ZakuError::new("Type mismatch")),
             Value::Text(l) => match other {
-                Value::Text(r) => Value::Boolean(*l < *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Text(r) => Ok(Value::Boolean(*l < *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
             Value::Date(l) => match other {
-                Value::Date(r) => Value::Boolean(*l < *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Date(r) => Ok(Value::Boolean(*l < *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
-            Value::Null => Value::Boolean(false),
+            Value::List(_) => Err(list_comparison_error()),
+            Value::Null => Ok(Value::Null),
         }
     }
 
-    pub fn lte(&self, other: &Value) -> Value {
+    pub fn lte(&self, other: &Value) -> Result<Value, ZakuError> {
         match self {
             Value::Number(l) => match other {
-                Value::Number(r) => Value::Boolean(*l <= *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Number(r) => Ok(Value::Boolean(*l <= *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
-            Value::Boolean(_) => panic!("Type mismatch"),
+            Value::Boolean(_) => Err(ZakuError::new("Type mismatch")),
             Value::Text(l) => match other {
-                Value::Text(r) => Value::Boolean(*l <= *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Text(r) => Ok(Value::Boolean(*l <= *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
             Value::Date(l) => match other {
-                Value::Date(r) => Value::Boolean(*l <= *r),
-                Value::Null => Value::Boolean(false),
-                _ => panic!("Type mismatch"),
+                Value::Date(r) => Ok(Value::Boolean(*l <= *r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
-            Value::Null => Value::Boolean(false),
+            Value::List(_) => Err(list_comparison_error()),
+            Value::Null => Ok(Value::Null),
         }
     }
 
-    pub fn add(&self, other: &Value) -> Value {
+    pub fn add(&self, other: &Value) -> Result<Value, ZakuError> {
         match self {
             Value::Number(l) => match other {
-                Value::Number(r) => Value::Number(l + r),
-                Value::Null => Value::Null,
-                _ => panic!("Type mismatch"),
+                Value::Number(r) => Ok(Value::Number(l + r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
-            Value::Null => Value::Null,
-            _ => panic!("Type not supported for addition"),
+            Value::Text(_) => Err(text_addition_error()),
+            Value::Null => Ok(Value::Null),
+            _ => Err(ZakuError::new("Type not supported for addition")),
         }
     }
 
-    pub fn sub(&self, other: &Value) -> Value {
+    pub fn sub(&self, other: &Value) -> Result<Value, ZakuError> {
         match self {
             Value::Number(l) => match other {
-                Value::Number(r) => Value::Number(l - r),
-                Value::Null => Value::Null,
-                _ => panic!("Type mismatch"),
+                Value::Number(r) => Ok(Value::Number(l - r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
-            Value::Null => Value::Null,
-            _ => panic!("Type not supported for subtraction"),
+            Value::Null => Ok(Value::Null),
+            _ => Err(ZakuError::new("Type not supported for subtraction")),
         }
     }
 
-    pub fn mul(&self, other: &Value) -> Value {
+    pub fn mul(&self, other: &Value) -> Result<Value, ZakuError> {
         match self {
             Value::Number(l) => match other {
-                Value::Number(r) => Value::Number(l * r),
-                Value::Null => Value::Null,
-                _ => panic!("Type mismatch"),
+                Value::Number(r) => Ok(Value::Number(l * r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
-            Value::Null => Value::Null,
-            _ => panic!("Type not supported for multiplication"),
+            Value::Null => Ok(Value::Null),
+            _ => Err(ZakuError::new("Type not supported for multiplication")),
         }
     }
 
-    pub fn div(&self, other: &Value) -> Value {
+    pub fn div(&self, other: &Value) -> Result<Value, ZakuError> {
         match self {
             Value::Number(l) => match other {
-                Value::Number(r) => Value::Number(l / r),
-                Value::Null => Value::Null,
-                _ => panic!("Type mismatch"),
+                Value::Number(r) if r.is_zero() => Err(ZakuError::new("Division by zero")),
+                Value::Number(r) => Ok(Value::Number(l / r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
-            Value::Null => Value::Null,
-            _ => panic!("Type not supported for division"),
+            Value::Null => Ok(Value::Null),
+            _ => Err(ZakuError::new("Type not supported for division")),
         }
     }
 
-    pub fn modulo(&self, other: &Value) -> Value {
+    pub fn modulo(&self, other: &Value) -> Result<Value, ZakuError> {
         match self {
             Value::Number(l) => match other {
-                Value::Number(r) => Value::Number(l % r),
-                Value::Null => Value::Null,
-                _ => panic!("Type mismatch"),
+                Value::Number(r) if r.is_zero() => Err(ZakuError::new("Division by zero")),
+                Value::Number(r) => Ok(Value::Number(l % r)),
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
-            Value::Null => Value::Null,
-            _ => panic!("Type not supported for modulo"),
+            Value::Null => Ok(Value::Null),
+            _ => Err(ZakuError::new("Type not supported for modulo")),
         }
     }
 
-    pub fn maximum(&self, other: &Value) -> Value {
+    // Exponentiation is computed in f64 precision, as BigDecimal only supports integer powers
+    // The result is converted back through its shortest decimal representation
+    pub fn pow(&self, other: &Value) -> Result<Value, ZakuError> {
         match self {
             Value::Number(l) => match other {
-                Value::Number(r) => Value::Number(l.max(r).clone()),
-                Value::Null => self.clone(),
-                _ => panic!("Type mismatch"),
+                Value::Number(r) => {
+                    let out_of_range = || ZakuError::new("Number out of range for exponentiation");
+                    let base = l.to_f64().ok_or_else(out_of_range)?;
+                    let exp = r.to_f64().ok_or_else(out_of_range)?;
+                    Value::from_f64(base.powf(exp), "Exponentiation")
+                }
+                Value::Null => Ok(Value::Null),
+                _ => Err(ZakuError::new("Type mismatch")),
+            },
+            Value::Null => Ok(Value::Null),
+            _ => Err(ZakuError::new("Type not supported for exponentiation")),
+        }
+    }
+
+    // code point of the first character, 0 for an empty string
+    pub fn ascii(&self) -> Result<Value, ZakuError> {
+        match self {
+            Value::Text(s) => Ok(Value::Number(BigDecimal::from(
+                s.chars().next().map_or(0, |c| c as u32),
+            ))),
+            Value::Null => Ok(Value::Null),
+            _ => Err(ZakuError::new("ASCII only supports text values")),
+        }
+    }
+
+    pub fn chr(&self) -> Result<Value, ZakuError> {
+        match self {
+            Value::Number(n) => n
+                .to_u32()
+                .filter(|_| n.is_integer())
+                .and_then(char::from_u32)
+                .filter(|c| *c != '\0')
+                .map(|c| Value::Text(c.to_string()))
+                .ok_or_else(|| ZakuError::new(format!("Invalid code point: {n}").as_str())),
+            Value::Null => Ok(Value::Null),
+            _ => Err(ZakuError::new("CHR only supports number values")),
+        }
+    }
+
+    // removes any of the given characters from the start and/or end of the text
+    pub fn trim(&self, chars: &Value, leading: bool, trailing: bool) -> Result<Value, ZakuError> {
+        match (self, chars) {
+            (Value::Text(s), Value::Text(chars)) => {
+                let is_trimmed = |c: char| chars.contains(c);
+                let mut res = s.as_str();
+                if leading {
+                    res = res.trim_start_matches(is_trimmed);
+                }
+                if trailing {
+                    res = res.trim_end_matches(is_trimmed);
+                }
+                Ok(Value::Text(res.to_string()))
+            }
+            (Value::Text(_) | Value::Null, Value::Text(_) | Value::Null) => Ok(Value::Null),
+            _ => Err(ZakuError::new("TRIM only supports text values")),
+        }
+    }
+
+    // number of unit boundaries crossed from self to end, negative if end is earlier
+    // so the months between 2023-01-31 and 2023-02-01 is 1, as with DATEDIFF elsewhere
+    pub fn date_diff(&self, unit: &Value, end: &Value) -> Result<Value, ZakuError> {
+        let unit = match unit {
+            Value::Text(unit) => unit.to_lowercase(),
+            Value::Null => return Ok(Value::Null),
+            _ => return Err(ZakuError::new("DATEDIFF unit must be text")),
+        };
+        let (start, end) = match (self, end) {
+            (Value::Date(start), Value::Date(end)) => (start, end),
+            (Value::Date(_) | Value::Null, Value::Date(_) | Value::Null) => return Ok(Value::Null),
+            _ => return Err(ZakuError::new("DATEDIFF only supports date values")),
+        };
+        let years = (end.year() - start.year()) as i64;
+        let diff = match unit.as_str() {
+            "day" | "days" => (*end - *start).num_days(),
+            "month" | "months" => years * 12 + end.month() as i64 - start.month() as i64,
+            "year" | "years" => years,
+            _ => {
+                return Err(ZakuError::new(
+                    format!("Unknown DATEDIFF unit: {unit}").as_str(),
+                ))
+            }
+        };
+        Ok(Value::Number(BigDecimal::from(diff)))
+    }
+
+    // years, months and days from start to self, as with AGE in PostgreSQL, so the age of
+    // 2023-03-01 from 2023-01-31 is 1 mon 1 day. Days are borrowed from the month of the
+    // earlier date, and every part is negative if self is before start
+    pub fn age(&self, start: &Value) -> Result<Value, ZakuError> {
+        let (end, start) = match (self, start) {
+            (Value::Date(end), Value::Date(start)) => (end, start),
+            (Value::Date(_) | Value::Null, Value::Date(_) | Value::Null) => return Ok(Value::Null),
+            _ => return Err(ZakuError::new("AGE only supports date values")),
+        };
+        let (later, earlier, sign) = if end >= start {
+            (end, start, 1)
+        } else {
+            (start, end, -1)
+        };
+        let mut years = later.year() - earlier.year();
+        let mut months = later.month() as i32 - earlier.month() as i32;
+        let mut days = later.day() as i32 - earlier.day() as i32;
+        if days < 0 {
+            let month_start = earlier
+                .with_day(1)
+                .expect("The first day of a month is valid");
+            let next_month_start = month_start
+                .checked_add_months(Months::new(1))
+                .ok_or_else(|| ZakuError::new("Date out of range"))?;
+            days += (next_month_start - month_start).num_days() as i32;
+            months -= 1;
+        }
+        if months < 0 {
+            months += 12;
+            years -= 1;
+        }
+        let parts: Vec<String> = [
+            (years, "year", "years"),
+            (months, "mon", "mons"),
+            (days, "day", "days"),
+        ]
+        .into_iter()
+        .filter(|(n, _, _)| *n != 0)
+        .map(|(n, one, many)| {
+            let n = sign * n;
+            format!("{n} {}", if n == 1 { one } else { many })
+        })
+        .collect();
+        if parts.is_empty() {
+            return Ok(Value::Text("0 days".to_string()));
+        }
+        Ok(Value::Text(parts.join(" ")))
+    }
+
+    pub fn concat(&self, other: &Value) -> Result<Value, ZakuError> {
+        match (self, other) {
+            (Value::Text(l), Value::Text(r)) => Ok(Value::Text(format!("{l}{r}"))),
+            (Value::Text(_) | Value::Null, Value::Text(_) | Value::Null) => Ok(Value::Null),
+            _ => Err(ZakuError::new(
+                "Operator || is only supported for text values",
+            )),
+        }
+    }
+
+    pub fn maximum(&self, other: &Value) -> Result<Value, ZakuError> {
+        match self {
+            Value::Number(l) => match other {
+                Value::Number(r) => Ok(Value::Number(l.max(r).clone())),
+                Value::Null => Ok(self.clone()),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
             Value::Null => match other {
-                Value::Null | Value::Number(_) => other.clone(),
-                _ => panic!("Type not supported for max"),
+                Value::Null | Value::Number(_) => Ok(other.clone()),
+                _ => Err(ZakuError::new("Type not supported for max")),
             },
             Value::Date(l) => match other {
-                Value::Date(r) => Value::Date(*l.max(r)),
-                Value::Null => self.clone(),
-                _ => panic!("Type mismatch"),
+                Value::Date(r) => Ok(Value::Date(*l.max(r))),
+                Value::Null => Ok(self.clone()),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
-            _ => panic!("Type not supported for max"),
+            _ => Err(ZakuError::new("Type not supported for max")),
         }
     }
 
-    pub fn minimum(&self, other: &Value) -> Value {
+    pub fn minimum(&self, other: &Value) -> Result<Value, ZakuError> {
         match self {
             Value::Number(l) => match other {
-                Value::Number(r) => Value::Number(l.min(r).clone()),
-                Value::Null => self.clone(),
-                _ => panic!("Type mismatch"),
+                Value::Number(r) => Ok(Value::Number(l.min(r).clone())),
+                Value::Null => Ok(self.clone()),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
             Value::Null => match other {
-                Value::Null | Value::Number(_) => other.clone(),
-                _ => panic!("Type not supported for max"),
+                Value::Null | Value::Number(_) => Ok(other.clone()),
+                _ => Err(ZakuError::new("Type not supported for max")),
             },
             Value::Date(l) => match other {
-                Value::Date(r) => Value::Date(*l.min(r)),
-                Value::Null => self.clone(),
-                _ => panic!("Type mismatch"),
+                Value::Date(r) => Ok(Value::Date(*l.min(r))),
+                Value::Null => Ok(self.clone()),
+                _ => Err(ZakuError::new("Type mismatch")),
             },
-            _ => panic!("Type not supported for min"),
+            _ => Err(ZakuError::new("Type not supported for min")),
         }
     }
 }
 
+// Numbers keep the scale they were read or computed with, so 5.00 from a csv is written back as 5.00
+// BigDecimal switches to scientific notation for results such as 1 * 10, which has a negative
+// scale, and for very small fractions, so those are written out in full instead
+fn write_number(f: &mut Formatter<'_>, val: &BigDecimal) -> std::fmt::Result {
+    let (int_val, scale) = val.as_bigint_and_exponent();
+    if scale <= 0 {
+        return write!(f, "{}", val.with_scale(0));
+    }
+    let int_val = int_val.to_string();
+    let (sign, digits) = match int_val.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", int_val.as_str()),
+    };
+    let scale = scale as usize;
+    if digits.len() > scale {
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{}{}.{}", sign, whole, fraction)
+    } else {
+        write!(
+            f,
+            "{}0.{}{}",
+            sign,
+            "0".repeat(scale - digits.len()),
+            digits
+        )
+    }
+}
+
 impl Display for Value {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Value::Number(val) => write!(f, "{}", val),
+            Value::Number(val) => write_number(f, val),
             Value::Boolean(val) => write!(f, "{}", val),
             Value::Text(val) => write!(f, "{}", val),
             Value::Date(val) => write!(f, "{}", val),
+            // nulls are written out so they can be told apart from empty text
+            Value::List(values) => {
+                let values = values
+                    .iter()
+                    .map(|v| match v {
+                        Value::Null => "null".to_string(),
+                        v => v.to_string(),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "[{}]", values)
+            }
             Value::Null => write!(f, ""),
         }
     }
@@ -383,27 +771,295 @@ mod tests {
         assert_eq!(DataType::get_type_from_string_val("hello"), DataType::Text);
     }
 
+    #[test]
+    fn test_datatype_from_str() {
+        assert_eq!(DataType::from_str("text").unwrap(), DataType::Text);
+        assert_eq!(DataType::from_str("Number").unwrap(), DataType::Number);
+        assert_eq!(DataType::from_str("num").unwrap(), DataType::Number);
+        assert_eq!(DataType::from_str("boolean").unwrap(), DataType::Boolean);
+        assert_eq!(DataType::from_str("date").unwrap(), DataType::Date);
+        assert_eq!(
+            DataType::from_str("integer").unwrap_err().to_string(),
+            "ZakuError: Unknown datatype: integer"
+        );
+    }
+
     #[test]
     fn test_get_value_from_string_val() {
         assert_eq!(
-            super::Value::get_value_from_string_val("1", &DataType::Number),
+            super::Value::get_value_from_string_val("1", &DataType::Number).unwrap(),
             super::Value::Number(BigDecimal::from_str("1").unwrap())
         );
         assert_eq!(
-            super::Value::get_value_from_string_val("1.0", &DataType::Number),
+            super::Value::get_value_from_string_val("1.0", &DataType::Number).unwrap(),
             super::Value::Number(BigDecimal::from_str("1").unwrap())
         );
         assert_eq!(
-            super::Value::get_value_from_string_val("true", &DataType::Boolean),
+            super::Value::get_value_from_string_val("true", &DataType::Boolean).unwrap(),
             super::Value::Boolean(true)
         );
         assert_eq!(
-            super::Value::get_value_from_string_val("false", &DataType::Boolean),
+            super::Value::get_value_from_string_val("false", &DataType::Boolean).unwrap(),
             super::Value::Boolean(false)
         );
         assert_eq!(
-            super::Value::get_value_from_string_val("hello", &DataType::Text),
+            super::Value::get_value_from_string_val("hello", &DataType::Text).unwrap(),
             super::Value::Text("hello".to_string())
         );
     }
+
+    // numbers compare and hash by value, so 10.00 and 10 are the same number
+    #[test]
+    fn test_number_scale() {
+        use super::Value::{self, Boolean};
+        use std::hash::BuildHasher;
+        let (scaled, whole) = (Value::number("10.00"), Value::number("10"));
+        assert_eq!(scaled.eq(&whole).unwrap(), Boolean(true));
+        assert_eq!(scaled.neq(&whole).unwrap(), Boolean(false));
+        assert_eq!(scaled.lt(&whole).unwrap(), Boolean(false));
+        assert_eq!(scaled.gte(&whole).unwrap(), Boolean(true));
+        assert_eq!(Value::number("9.99").lt(&whole).unwrap(), Boolean(true));
+        assert_eq!(scaled, whole);
+        let hasher = std::hash::RandomState::new();
+        assert_eq!(hasher.hash_one(&scaled), hasher.hash_one(&whole));
+    }
+
+    #[test]
+    fn test_number_display() {
+        use super::Value;
+        let display = |val: &str| Value::number(val).to_string();
+        assert_eq!(display("5.00"), "5.00");
+        assert_eq!(display("-0.50"), "-0.50");
+        assert_eq!(display("0.00"), "0.00");
+        assert_eq!(display("1e3"), "1000");
+        assert_eq!(display("-1.5e2"), "-150");
+        assert_eq!(display("0.0000001"), "0.0000001");
+        assert_eq!(display("-1E-8"), "-0.00000001");
+        assert_eq!(
+            Value::number("1")
+                .mul(&Value::number("10"))
+                .unwrap()
+                .to_string(),
+            "10"
+        );
+        assert_eq!(
+            Value::number("5.00")
+                .mul(&Value::number("2"))
+                .unwrap()
+                .to_string(),
+            "10.00"
+        );
+    }
+
+    #[test]
+    fn test_decimal() {
+        use super::Value;
+        let decimal = DataType::decimal(6, 2).unwrap();
+        assert_eq!(DataType::from_str("Decimal(6, 2)").unwrap(), decimal);
+        assert_eq!(decimal.to_string(), "decimal(6,2)");
+        assert!(DataType::decimal(2, 3).is_err());
+        assert!(DataType::decimal(39, 2).is_err());
+        assert!(DataType::from_str("decimal(6)").is_err());
+
+        let round = |val: &str| Value::number(val).round_to_type(&decimal);
+        assert_eq!(round("5").unwrap().to_string(), "5.00");
+        assert_eq!(round("2.345").unwrap().to_string(), "2.35");
+        assert_eq!(round("-2.345").unwrap().to_string(), "-2.35");
+        assert_eq!(round("9999.994").unwrap().to_string(), "9999.99");
+        assert!(round("9999.995").is_err());
+        assert_eq!(
+            Value::get_value_from_string_val("1,234.5", &decimal)
+                .unwrap()
+                .to_string(),
+            "1234.50"
+        );
+        assert_eq!(
+            decimal.numeric_result(&DataType::decimal(10, 1).unwrap()),
+            Some(DataType::decimal(10, 2).unwrap())
+        );
+        assert_eq!(decimal.numeric_result(&DataType::Number), Some(decimal));
+        assert_eq!(decimal.numeric_result(&DataType::Text), None);
+    }
+
+    #[test]
+    fn test_date_diff() {
+        use super::Value::{self, Null, Text};
+        let diff = |unit: &str, start: &str, end: &str| {
+            Value::date(start)
+                .date_diff(&Text(unit.to_string()), &Value::date(end))
+                .unwrap()
+        };
+        assert_eq!(diff("day", "2023-02-27", "2023-03-01"), Value::number("2"));
+        assert_eq!(diff("days", "2024-02-27", "2024-03-01"), Value::number("3"));
+        assert_eq!(
+            diff("month", "2023-01-31", "2023-02-01"),
+            Value::number("1")
+        );
+        assert_eq!(
+            diff("Month", "2023-03-15", "2022-01-15"),
+            Value::number("-14")
+        );
+        assert_eq!(diff("year", "2023-12-31", "2024-01-01"), Value::number("1"));
+        assert_eq!(
+            Null.date_diff(&Text("day".to_string()), &Value::date("2023-01-01"))
+                .unwrap(),
+            Null
+        );
+        assert_eq!(
+            Value::date("2023-01-01")
+                .date_diff(&Text("week".to_string()), &Value::date("2023-01-08"))
+                .unwrap_err()
+                .to_string(),
+            "ZakuError: Unknown DATEDIFF unit: week"
+        );
+    }
+
+    #[test]
+    fn test_three_valued_logic() {
+        use super::Value::{Boolean, Null};
+        assert_eq!(Boolean(true).and(&Boolean(false)).unwrap(), Boolean(false));
+        assert_eq!(Null.and(&Boolean(false)).unwrap(), Boolean(false));
+        assert_eq!(Null.and(&Boolean(true)).unwrap(), Null);
+        assert_eq!(Boolean(false).or(&Boolean(true)).unwrap(), Boolean(true));
+        assert_eq!(Null.or(&Boolean(true)).unwrap(), Boolean(true));
+        assert_eq!(Null.or(&Boolean(false)).unwrap(), Null);
+        assert_eq!(Boolean(true).not().unwrap(), Boolean(false));
+        assert_eq!(Null.not().unwrap(), Null);
+    }
+
+    #[test]
+    fn test_cast() {
+        use super::Value::{self, Boolean, Null, Text};
+        let text = |s: &str| Text(s.to_string());
+        let date = Value::date("2023-01-02");
+        let datatypes = [
+            DataType::Text,
+            DataType::Number,
+            DataType::Boolean,
+            DataType::Date,
+        ];
+
+        for datatype in datatypes.iter() {
+            assert_eq!(Null.cast(datatype).unwrap(), Null);
+        }
+
+        assert_eq!(text("abc").cast(&DataType::Text).unwrap(), text("abc"));
+        assert_eq!(
+            text("1,000.5").cast(&DataType::Number).unwrap(),
+            Value::number("1000.5")
+        );
+        assert_eq!(
+            text("true").cast(&DataType::Boolean).unwrap(),
+            Boolean(true)
+        );
+        assert_eq!(text("2023-01-02").cast(&DataType::Date).unwrap(), date);
+        assert_eq!(text("").cast(&DataType::Number).unwrap(), Null);
+
+        assert_eq!(
+            Value::number("1.5").cast(&DataType::Text).unwrap(),
+            text("1.5")
+        );
+        assert_eq!(
+            Value::number("1.5").cast(&DataType::Number).unwrap(),
+            Value::number("1.5")
+        );
+        assert_eq!(
+            Value::number("0").cast(&DataType::Boolean).unwrap(),
+            Boolean(false)
+        );
+        assert_eq!(
+            Value::number("-2").cast(&DataType::Boolean).unwrap(),
+            Boolean(true)
+        );
+
+        assert_eq!(Boolean(false).cast(&DataType::Text).unwrap(), text("false"));
+        assert_eq!(
+            Boolean(true).cast(&DataType::Number).unwrap(),
+            Value::number("1")
+        );
+        assert_eq!(
+            Boolean(false).cast(&DataType::Number).unwrap(),
+            Value::number("0")
+        );
+        assert_eq!(
+            Boolean(true).cast(&DataType::Boolean).unwrap(),
+            Boolean(true)
+        );
+
+        assert_eq!(date.cast(&DataType::Text).unwrap(), text("2023-01-02"));
+        assert_eq!(date.cast(&DataType::Date).unwrap(), date);
+    }
+
+    #[test]
+    fn test_cast_errors() {
+        use super::Value::{self, Boolean, Text};
+        let cases = [
+            (
+                Text("abc".to_string()),
+                DataType::Number,
+                "Expected number, got 'abc'",
+            ),
+            (
+                Text("yes".to_string()),
+                DataType::Boolean,
+                "Expected boolean, got 'yes'",
+            ),
+            (
+                Text("01/02/2023".to_string()),
+                DataType::Date,
+                "Expected date, got '01/02/2023'",
+            ),
+            (Value::number("1"), DataType::Date, "Cannot cast 1 to date"),
+            (Boolean(true), DataType::Date, "Cannot cast true to date"),
+            (
+                Value::date("2023-01-02"),
+                DataType::Number,
+                "Cannot cast 2023-01-02 to number",
+            ),
+            (
+                Value::date("2023-01-02"),
+                DataType::Boolean,
+                "Cannot cast 2023-01-02 to boolean",
+            ),
+        ];
+        for (value, datatype, message) in cases {
+            assert_eq!(
+                value.cast(&datatype).unwrap_err().to_string(),
+                format!("ZakuError: {message}")
+            );
+        }
+    }
+
+    // NaN and infinity are never produced or read, computations that would give them error
+    #[test]
+    fn test_non_finite_numbers() {
+        use super::Value;
+        let err = |res: Result<Value, crate::ZakuError>| res.unwrap_err().to_string();
+        assert_eq!(
+            err(Value::number("-8").pow(&Value::number("0.5"))),
+            "ZakuError: Exponentiation result is not a finite number"
+        );
+        assert_eq!(
+            err(Value::number("0").pow(&Value::number("-1"))),
+            "ZakuError: Exponentiation result is not a finite number"
+        );
+        assert_eq!(
+            err(Value::number("0.0").div(&Value::number("0.0"))),
+            "ZakuError: Division by zero"
+        );
+        for val in ["NaN", "nan", "inf", "-Infinity"] {
+            assert_eq!(DataType::get_type_from_string_val(val), DataType::Text);
+            assert!(Value::get_value_from_string_val(val, &DataType::Number).is_err());
+        }
+    }
+
+    #[test]
+    fn test_can_cast_to() {
+        assert!(DataType::Number.can_cast_to(&DataType::Text));
+        assert!(DataType::Text.can_cast_to(&DataType::Date));
+        assert!(DataType::Boolean.can_cast_to(&DataType::Number));
+        assert!(DataType::Date.can_cast_to(&DataType::Date));
+        assert!(!DataType::Date.can_cast_to(&DataType::Number));
+        assert!(!DataType::Number.can_cast_to(&DataType::Date));
+    }
 }
@@ -1,26 +1,37 @@
 use std::{sync::Arc, vec};
 
+use bigdecimal::BigDecimal;
+
 use crate::{
+    catalog::Catalog,
+    config::ExecutionConfig,
     datasources::datasink::Datasink,
     datatypes::{
-        column_vector::{ColumnVector, Vectors},
+        column_vector::{ColumnVector, Vector, Vectors},
         record_batch::RecordBatch,
         schema::{Field, Schema},
         types::{DataType, Value},
     },
     error::ZakuError,
     logical_plans::{dataframe::Dataframe, logical_plan::LogicalPlan},
+    optimizer::rule::Optimizer,
     sql::{self, stmt::Stmt},
 };
 
-async fn execute_select(df: Dataframe) -> Result<Datasink, ZakuError> {
+async fn execute_select(
+    df: Dataframe,
+    config: &Arc<ExecutionConfig>,
+) -> Result<Datasink, ZakuError> {
     let plan = df.logical_plan();
     let schema = plan.schema();
-    Ok(Datasink::new(schema, plan.to_physical_plan()?))
+    Ok(Datasink::new(schema, plan.to_physical_plan(config)?))
 }
 
-async fn execute_explain(df: Dataframe) -> Result<Datasink, ZakuError> {
-    let plan = df.logical_plan().to_physical_plan()?;
+async fn execute_explain(
+    df: Dataframe,
+    config: &Arc<ExecutionConfig>,
+) -> Result<Datasink, ZakuError> {
+    let plan = df.logical_plan().to_physical_plan(config)?;
     let plan_str = format!("{}", plan);
     let col = vec![Arc::new(Vectors::ColumnVector(ColumnVector::new(
         DataType::Text,
@@ -29,22 +40,161 @@ async fn execute_explain(df: Dataframe) -> Result<Datasink, ZakuError> {
     let schema = Schema::new(vec![Field::new("Query Plan".to_string(), DataType::Text)]);
     let explain_df =
         Dataframe::from_memory(schema.clone(), vec![RecordBatch::new(schema.clone(), col)])?;
-    execute_select(explain_df).await
+    execute_select(explain_df, config).await
 }
 
-async fn execute_copy(df: Dataframe, path: &String) -> Result<Datasink, ZakuError> {
+async fn execute_copy(
+    df: Dataframe,
+    path: &String,
+    delimiter: u8,
+    config: &Arc<ExecutionConfig>,
+) -> Result<Datasink, ZakuError> {
     let plan = df.logical_plan();
     let schema = plan.schema();
-    let ds = Datasink::new(schema, plan.to_physical_plan()?);
-    ds.to_csv(path).await?;
+    let ds = Datasink::new(schema, plan.to_physical_plan(config)?);
+    ds.to_csv(path, delimiter).await?;
     Ok(ds)
 }
 
-pub async fn execute(sql: &str, df: Dataframe) -> Result<Datasink, ZakuError> {
-    let select_df = sql::parser::parse(sql, df)?;
-    match select_df {
-        Stmt::Select(df) => execute_select(df).await,
-        Stmt::Explain(df) => execute_explain(df).await,
-        Stmt::CopyTo(df, path) => execute_copy(df, &path).await,
+// Materializes the query into an in-memory table registered under the name,
+// returning the number of rows created
+async fn execute_create_table(
+    df: Dataframe,
+    name: &str,
+    catalog: &mut Catalog,
+    config: &Arc<ExecutionConfig>,
+) -> Result<Datasink, ZakuError> {
+    let plan = df.logical_plan();
+    let schema = plan.schema();
+    let data = Datasink::new(schema.clone(), plan.to_physical_plan(config)?)
+        .materialize()
+        .await?;
+    let row_count: usize = data.iter().map(|rb| rb.row_count()).sum();
+    catalog.register(name, Dataframe::from_memory(schema, data)?);
+
+    let col = vec![Arc::new(Vectors::ColumnVector(ColumnVector::new(
+        DataType::Number,
+        vec![Value::Number(BigDecimal::from(row_count as u64))],
+    )))];
+    let schema = Schema::new(vec![Field::new("rows".to_string(), DataType::Number)]);
+    let summary_df =
+        Dataframe::from_memory(schema.clone(), vec![RecordBatch::new(schema.clone(), col)])?;
+    execute_select(summary_df, config).await
+}
+
+// Runs each scalar subquery once, so that its value can be planned as a literal
+async fn evaluate_subqueries(
+    df: &Dataframe,
+    config: &Arc<ExecutionConfig>,
+) -> Result<(), ZakuError> {
+    for subquery in df.logical_plan().subqueries() {
+        let plan = subquery.plan();
+        let ds = Datasink::new(plan.schema(), plan.to_physical_plan(config)?);
+        let values: Vec<Value> = ds
+            .materialize()
+            .await?
+            .iter()
+            .flat_map(|rb| rb.columns()[0].iter().cloned().collect::<Vec<Value>>())
+            .collect();
+        if values.len() != 1 {
+            return Err(ZakuError::new(
+                format!(
+                    "Scalar subquery must return exactly one row, got {}",
+                    values.len()
+                )
+                .as_str(),
+            ));
+        }
+        subquery.set_value(values[0].clone())?;
     }
+    Ok(())
+}
+
+// Returns the optimized dataframe, and both plans if the config asks to debug them
+fn optimize(
+    df: Dataframe,
+    config: &ExecutionConfig,
+) -> Result<(Dataframe, Option<String>), ZakuError> {
+    let plan = config
+        .optimizer_rules()
+        .iter()
+        .fold(Optimizer::new(), |optimizer, rule| {
+            optimizer.with_rule(rule.clone())
+        })
+        .optimize(df.logical_plan())?;
+    let debug_plan = config.debug_plan().then(|| {
+        format!(
+            "Logical plan:\n{}\nOptimized logical plan:\n{}",
+            df.logical_plan(),
+            plan
+        )
+    });
+    Ok((Dataframe::new(Arc::new(plan)), debug_plan))
+}
+
+// Runs a dataframe built through its methods rather than from sql
+pub async fn execute_dataframe(
+    df: Dataframe,
+    config: ExecutionConfig,
+) -> Result<Datasink, ZakuError> {
+    config.validate()?;
+    let config = Arc::new(config);
+    evaluate_subqueries(&df, &config).await?;
+    let (df, debug_plan) = optimize(df, &config)?;
+    Ok(execute_select(df, &config)
+        .await?
+        .with_debug_plan(debug_plan))
+}
+
+pub async fn execute(sql: &str, df: Dataframe) -> Result<Datasink, ZakuError> {
+    execute_with_config(sql, df, ExecutionConfig::default()).await
+}
+
+// Runs the query against the single table, so tables created by the query are discarded
+pub async fn execute_with_config(
+    sql: &str,
+    df: Dataframe,
+    config: ExecutionConfig,
+) -> Result<Datasink, ZakuError> {
+    execute_in_catalog(sql, &mut Catalog::from_dataframe(df), config).await
+}
+
+// Runs the query against the tables of the catalog, registering any table it creates
+pub async fn execute_in_catalog(
+    sql: &str,
+    catalog: &mut Catalog,
+    config: ExecutionConfig,
+) -> Result<Datasink, ZakuError> {
+    config.validate()?;
+    let config = Arc::new(config);
+    let (select_df, warnings) = sql::parser::parse(sql, catalog, config.max_expr_depth())?;
+    evaluate_subqueries(select_df.dataframe(), &config).await?;
+    let res = match select_df {
+        Stmt::Select(df) => {
+            let (df, debug_plan) = optimize(df, &config)?;
+            execute_select(df, &config)
+                .await?
+                .with_debug_plan(debug_plan)
+        }
+        Stmt::Explain(df) => {
+            let (df, debug_plan) = optimize(df, &config)?;
+            execute_explain(df, &config)
+                .await?
+                .with_debug_plan(debug_plan)
+        }
+        Stmt::CopyTo(df, path, delimiter) => {
+            let (df, debug_plan) = optimize(df, &config)?;
+            let delimiter = delimiter.unwrap_or(config.delimiter_out());
+            execute_copy(df, &path, delimiter, &config)
+                .await?
+                .with_debug_plan(debug_plan)
+        }
+        Stmt::CreateTable(name, df) => {
+            let (df, debug_plan) = optimize(df, &config)?;
+            execute_create_table(df, &name, catalog, &config)
+                .await?
+                .with_debug_plan(debug_plan)
+        }
+    };
+    Ok(res.with_warnings(warnings))
 }
@@ -2,16 +2,34 @@
 #![feature(proc_macro_hygiene)]
 #![feature(coroutines)]
 
+mod catalog;
+mod config;
 mod datasources;
 mod datatypes;
 mod error;
 mod execute;
 mod logical_plans;
+mod optimizer;
 mod physical_plans;
 mod sql;
 pub mod test_utils;
 
-pub use datasources::datasink::Datasink;
+pub use catalog::Catalog;
+pub use config::ExecutionConfig;
+pub use datasources::{
+    csv_options::{CsvOptions, ExtraFields},
+    datasink::Datasink,
+    output_summary::OutputSummary,
+};
+pub use datatypes::{
+    record_batch::{Collation, SortOptions},
+    schema::{Field, Schema},
+    types::DataType,
+};
 pub use error::ZakuError;
-pub use execute::execute;
-pub use logical_plans::dataframe::Dataframe;
+pub use execute::{execute, execute_dataframe, execute_in_catalog, execute_with_config};
+pub use logical_plans::{
+    dataframe::Dataframe,
+    logical_plan::{LogicalPlan, LogicalPlans},
+};
+pub use optimizer::rule::{Optimizer, OptimizerRule, OptimizerRules};
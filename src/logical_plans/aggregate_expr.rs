@@ -5,33 +5,97 @@ use super::{
     logical_plan::LogicalPlans,
 };
 use crate::{datatypes::schema::Field, ZakuError};
-use crate::{datatypes::types::DataType, physical_plans::accumulator::AggregateExpressions};
+use crate::{
+    datatypes::types::{DataType, MAX_DECIMAL_PRECISION},
+    physical_plans::accumulator::{nulls_str, AggregateExpressions},
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum AggregateExprs {
     Count(Box<LogicalExprs>),
+    CountDistinct(Box<LogicalExprs>),
     Sum(Box<LogicalExprs>),
     Avg(Box<LogicalExprs>),
     Min(Box<LogicalExprs>),
     Max(Box<LogicalExprs>),
+    ArrayAgg(Box<LogicalExprs>),
+    // the first or last value in the order rows arrive in, with whether nulls are skipped
+    FirstValue(Box<LogicalExprs>, bool),
+    LastValue(Box<LogicalExprs>, bool),
 }
 
+type AggregateConstructor = fn(Box<LogicalExprs>) -> AggregateExprs;
+
+// the aggregate functions recognised by name, shared by the parser and the planner
+const AGGREGATE_FUNCTIONS: [(&str, AggregateConstructor); 8] = [
+    ("count", AggregateExprs::Count),
+    ("sum", AggregateExprs::Sum),
+    ("avg", AggregateExprs::Avg),
+    ("min", AggregateExprs::Min),
+    ("max", AggregateExprs::Max),
+    ("array_agg", AggregateExprs::ArrayAgg),
+    ("first_value", |expr| {
+        AggregateExprs::FirstValue(expr, false)
+    }),
+    ("last_value", |expr| AggregateExprs::LastValue(expr, false)),
+];
+
 impl AggregateExprs {
+    fn constructor(func: &str) -> Option<AggregateConstructor> {
+        let func = func.to_lowercase();
+        AGGREGATE_FUNCTIONS
+            .iter()
+            .find(|(name, _)| *name == func)
+            .map(|(_, constructor)| *constructor)
+    }
+
+    pub fn is_aggregate(func: &str) -> bool {
+        AggregateExprs::constructor(func).is_some()
+    }
+
     pub fn from_str(func: &str, func_arg: LogicalExprs) -> Result<AggregateExprs, ZakuError> {
-        match func.to_lowercase().as_str() {
-            "count" => Ok(AggregateExprs::Count(Box::new(func_arg))),
-            "sum" => Ok(AggregateExprs::Sum(Box::new(func_arg))),
-            "avg" => Ok(AggregateExprs::Avg(Box::new(func_arg))),
-            "min" => Ok(AggregateExprs::Min(Box::new(func_arg))),
-            "max" => Ok(AggregateExprs::Max(Box::new(func_arg))),
-            _ => Err(ZakuError::new("Unknown aggregate function")),
+        AggregateExprs::constructor(func)
+            .map(|constructor| constructor(Box::new(func_arg)))
+            .ok_or_else(|| ZakuError::new("Unknown aggregate function"))
+    }
+
+    // only COUNT supports counting distinct values
+    pub fn distinct(self) -> Result<AggregateExprs, ZakuError> {
+        match self {
+            AggregateExprs::Count(expr) => Ok(AggregateExprs::CountDistinct(expr)),
+            _ => Err(ZakuError::new("DISTINCT is only supported in COUNT")),
+        }
+    }
+
+    // nulls are respected by default, as in the SQL standard
+    pub fn ignore_nulls(self, ignore_nulls: bool) -> Result<AggregateExprs, ZakuError> {
+        match self {
+            AggregateExprs::FirstValue(expr, _) => {
+                Ok(AggregateExprs::FirstValue(expr, ignore_nulls))
+            }
+            AggregateExprs::LastValue(expr, _) => Ok(AggregateExprs::LastValue(expr, ignore_nulls)),
+            _ => Err(ZakuError::new(
+                "IGNORE NULLS and RESPECT NULLS are only supported in FIRST_VALUE and LAST_VALUE",
+            )),
         }
     }
 
     pub fn to_field(&self, input: &LogicalPlans) -> Result<Field, ZakuError> {
         match self {
-            AggregateExprs::Count(_) => Ok(Field::new("count".to_string(), DataType::Number)),
-            AggregateExprs::Sum(_) => Ok(Field::new("sum".to_string(), DataType::Number)),
+            AggregateExprs::Count(_) | AggregateExprs::CountDistinct(_) => {
+                Ok(Field::new("count".to_string(), DataType::Number))
+            }
+            // sums of decimals keep their scale, with room for the largest precision
+            AggregateExprs::Sum(expr) => match expr.to_field(input)?.datatype() {
+                DataType::Decimal { scale, .. } => Ok(Field::new(
+                    "sum".to_string(),
+                    DataType::Decimal {
+                        precision: MAX_DECIMAL_PRECISION,
+                        scale: *scale,
+                    },
+                )),
+                _ => Ok(Field::new("sum".to_string(), DataType::Number)),
+            },
             AggregateExprs::Avg(_) => Ok(Field::new("avg".to_string(), DataType::Number)),
             AggregateExprs::Min(expr) => Ok(Field::new(
                 "min".to_string(),
@@ -41,16 +105,29 @@ impl AggregateExprs {
                 "max".to_string(),
                 *expr.to_field(input)?.datatype(),
             )),
+            AggregateExprs::ArrayAgg(_) => Ok(Field::new("array_agg".to_string(), DataType::List)),
+            AggregateExprs::FirstValue(expr, _) => Ok(Field::new(
+                "first_value".to_string(),
+                *expr.to_field(input)?.datatype(),
+            )),
+            AggregateExprs::LastValue(expr, _) => Ok(Field::new(
+                "last_value".to_string(),
+                *expr.to_field(input)?.datatype(),
+            )),
         }
     }
 
     pub fn input(&self) -> &LogicalExprs {
         match self {
             AggregateExprs::Count(expr) => expr,
+            AggregateExprs::CountDistinct(expr) => expr,
             AggregateExprs::Sum(expr) => expr,
             AggregateExprs::Avg(expr) => expr,
             AggregateExprs::Min(expr) => expr,
             AggregateExprs::Max(expr) => expr,
+            AggregateExprs::ArrayAgg(expr) => expr,
+            AggregateExprs::FirstValue(expr, _) => expr,
+            AggregateExprs::LastValue(expr, _) => expr,
         }
     }
 
@@ -62,6 +139,9 @@ impl AggregateExprs {
             AggregateExprs::Count(expr) => {
                 Ok(AggregateExpressions::Count(expr.to_physical_expr(plan)?))
             }
+            AggregateExprs::CountDistinct(expr) => Ok(AggregateExpressions::CountDistinct(
+                expr.to_physical_expr(plan)?,
+            )),
             AggregateExprs::Sum(expr) => {
                 Ok(AggregateExpressions::Sum(expr.to_physical_expr(plan)?))
             }
@@ -74,6 +154,17 @@ impl AggregateExprs {
             AggregateExprs::Max(expr) => {
                 Ok(AggregateExpressions::Max(expr.to_physical_expr(plan)?))
             }
+            AggregateExprs::ArrayAgg(expr) => {
+                Ok(AggregateExpressions::ArrayAgg(expr.to_physical_expr(plan)?))
+            }
+            AggregateExprs::FirstValue(expr, ignore_nulls) => Ok(AggregateExpressions::FirstValue(
+                expr.to_physical_expr(plan)?,
+                *ignore_nulls,
+            )),
+            AggregateExprs::LastValue(expr, ignore_nulls) => Ok(AggregateExpressions::LastValue(
+                expr.to_physical_expr(plan)?,
+                *ignore_nulls,
+            )),
         }
     }
 }
@@ -82,10 +173,18 @@ impl Display for AggregateExprs {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let _ = match self {
             AggregateExprs::Count(expr) => write!(f, "count({})", expr),
+            AggregateExprs::CountDistinct(expr) => write!(f, "count(distinct {})", expr),
             AggregateExprs::Sum(expr) => write!(f, "sum({})", expr),
             AggregateExprs::Avg(expr) => write!(f, "avg({})", expr),
             AggregateExprs::Min(expr) => write!(f, "min({})", expr),
             AggregateExprs::Max(expr) => write!(f, "max({})", expr),
+            AggregateExprs::ArrayAgg(expr) => write!(f, "array_agg({})", expr),
+            AggregateExprs::FirstValue(expr, ignore_nulls) => {
+                write!(f, "first_value({}){}", expr, nulls_str(*ignore_nulls))
+            }
+            AggregateExprs::LastValue(expr, ignore_nulls) => {
+                write!(f, "last_value({}){}", expr, nulls_str(*ignore_nulls))
+            }
         };
         Ok(())
     }
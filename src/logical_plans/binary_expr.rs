@@ -1,7 +1,10 @@
 use sqlparser::ast::BinaryOperator;
 
 use crate::{
-    datatypes::{schema::Field, types::DataType},
+    datatypes::{
+        schema::Field,
+        types::{text_addition_error, DataType},
+    },
     error::ZakuError,
     physical_plans::{self, physical_expr::PhysicalExprs},
     sql::operators::{BinaryOp, BooleanOp, MathOp},
@@ -22,6 +25,7 @@ pub enum BinaryExprs {
     Or(BooleanExpr),
     Eq(BooleanExpr),
     Neq(BooleanExpr),
+    NotDistinct(BooleanExpr),
     Gt(BooleanExpr),
     Gte(BooleanExpr),
     Lt(BooleanExpr),
@@ -31,6 +35,8 @@ pub enum BinaryExprs {
     Mul(MathExpr),
     Div(MathExpr),
     Mod(MathExpr),
+    Pow(MathExpr),
+    Concat(MathExpr),
 }
 
 impl BinaryExprs {
@@ -40,6 +46,12 @@ impl BinaryExprs {
             BinaryOperator::Or => Ok(BinaryExprs::Or(BooleanExpr::new(l, BooleanOp::Or, r))),
             BinaryOperator::Eq => Ok(BinaryExprs::Eq(BooleanExpr::new(l, BooleanOp::Eq, r))),
             BinaryOperator::NotEq => Ok(BinaryExprs::Neq(BooleanExpr::new(l, BooleanOp::Neq, r))),
+            // <=> is MySQL's spelling of IS NOT DISTINCT FROM
+            BinaryOperator::Spaceship => Ok(BinaryExprs::NotDistinct(BooleanExpr::new(
+                l,
+                BooleanOp::NotDistinct,
+                r,
+            ))),
             BinaryOperator::Gt => Ok(BinaryExprs::Gt(BooleanExpr::new(l, BooleanOp::Gt, r))),
             BinaryOperator::GtEq => Ok(BinaryExprs::Gte(BooleanExpr::new(l, BooleanOp::Gte, r))),
             BinaryOperator::Lt => Ok(BinaryExprs::Lt(BooleanExpr::new(l, BooleanOp::Lt, r))),
@@ -49,6 +61,14 @@ impl BinaryExprs {
             BinaryOperator::Multiply => Ok(BinaryExprs::Mul(MathExpr::new(l, MathOp::Mul, r))),
             BinaryOperator::Divide => Ok(BinaryExprs::Div(MathExpr::new(l, MathOp::Div, r))),
             BinaryOperator::Modulo => Ok(BinaryExprs::Mod(MathExpr::new(l, MathOp::Mod, r))),
+            // Generic dialects parse ^ as bitwise xor, which is not supported, so it is treated as
+            // exponentiation as in Postgres
+            BinaryOperator::PGExp | BinaryOperator::BitwiseXor => {
+                Ok(BinaryExprs::Pow(MathExpr::new(l, MathOp::Pow, r)))
+            }
+            BinaryOperator::StringConcat => {
+                Ok(BinaryExprs::Concat(MathExpr::new(l, MathOp::Concat, r)))
+            }
             _ => Err(ZakuError::new("Invalid operator")),
         }
     }
@@ -59,6 +79,7 @@ impl BinaryExprs {
             BinaryExprs::Or(expr) => expr.l.as_ref(),
             BinaryExprs::Eq(expr) => expr.l.as_ref(),
             BinaryExprs::Neq(expr) => expr.l.as_ref(),
+            BinaryExprs::NotDistinct(expr) => expr.l.as_ref(),
             BinaryExprs::Gt(expr) => expr.l.as_ref(),
             BinaryExprs::Gte(expr) => expr.l.as_ref(),
             BinaryExprs::Lt(expr) => expr.l.as_ref(),
@@ -68,6 +89,8 @@ impl BinaryExprs {
             BinaryExprs::Mul(expr) => expr.l.as_ref(),
             BinaryExprs::Div(expr) => expr.l.as_ref(),
             BinaryExprs::Mod(expr) => expr.l.as_ref(),
+            BinaryExprs::Pow(expr) => expr.l.as_ref(),
+            BinaryExprs::Concat(expr) => expr.l.as_ref(),
         }
     }
 
@@ -77,6 +100,7 @@ impl BinaryExprs {
             BinaryExprs::Or(expr) => expr.r.as_ref(),
             BinaryExprs::Eq(expr) => expr.r.as_ref(),
             BinaryExprs::Neq(expr) => expr.r.as_ref(),
+            BinaryExprs::NotDistinct(expr) => expr.r.as_ref(),
             BinaryExprs::Gt(expr) => expr.r.as_ref(),
             BinaryExprs::Gte(expr) => expr.r.as_ref(),
             BinaryExprs::Lt(expr) => expr.r.as_ref(),
@@ -86,6 +110,8 @@ impl BinaryExprs {
             BinaryExprs::Mul(expr) => expr.r.as_ref(),
             BinaryExprs::Div(expr) => expr.r.as_ref(),
             BinaryExprs::Mod(expr) => expr.r.as_ref(),
+            BinaryExprs::Pow(expr) => expr.r.as_ref(),
+            BinaryExprs::Concat(expr) => expr.r.as_ref(),
         }
     }
 
@@ -95,6 +121,7 @@ impl BinaryExprs {
             BinaryExprs::Or(_) => BinaryOperator::Or,
             BinaryExprs::Eq(_) => BinaryOperator::Eq,
             BinaryExprs::Neq(_) => BinaryOperator::NotEq,
+            BinaryExprs::NotDistinct(_) => BinaryOperator::Spaceship,
             BinaryExprs::Gt(_) => BinaryOperator::Gt,
             BinaryExprs::Gte(_) => BinaryOperator::GtEq,
             BinaryExprs::Lt(_) => BinaryOperator::Lt,
@@ -104,6 +131,8 @@ impl BinaryExprs {
             BinaryExprs::Mul(_) => BinaryOperator::Multiply,
             BinaryExprs::Div(_) => BinaryOperator::Divide,
             BinaryExprs::Mod(_) => BinaryOperator::Modulo,
+            BinaryExprs::Pow(_) => BinaryOperator::PGExp,
+            BinaryExprs::Concat(_) => BinaryOperator::StringConcat,
         }
     }
 }
@@ -115,6 +144,7 @@ impl BinaryExpr for BinaryExprs {
             BinaryExprs::Or(expr) => expr.to_string(),
             BinaryExprs::Eq(expr) => expr.to_string(),
             BinaryExprs::Neq(expr) => expr.to_string(),
+            BinaryExprs::NotDistinct(expr) => expr.to_string(),
             BinaryExprs::Gt(expr) => expr.to_string(),
             BinaryExprs::Gte(expr) => expr.to_string(),
             BinaryExprs::Lt(expr) => expr.to_string(),
@@ -124,6 +154,8 @@ impl BinaryExpr for BinaryExprs {
             BinaryExprs::Mul(expr) => expr.to_string(),
             BinaryExprs::Div(expr) => expr.to_string(),
             BinaryExprs::Mod(expr) => expr.to_string(),
+            BinaryExprs::Pow(expr) => expr.to_string(),
+            BinaryExprs::Concat(expr) => expr.to_string(),
         }
     }
 }
@@ -135,6 +167,7 @@ impl LogicalExpr for BinaryExprs {
             BinaryExprs::Or(expr) => expr.to_field(input),
             BinaryExprs::Eq(expr) => expr.to_field(input),
             BinaryExprs::Neq(expr) => expr.to_field(input),
+            BinaryExprs::NotDistinct(expr) => expr.to_field(input),
             BinaryExprs::Gt(expr) => expr.to_field(input),
             BinaryExprs::Gte(expr) => expr.to_field(input),
             BinaryExprs::Lt(expr) => expr.to_field(input),
@@ -144,6 +177,8 @@ impl LogicalExpr for BinaryExprs {
             BinaryExprs::Mul(expr) => expr.to_field(input),
             BinaryExprs::Div(expr) => expr.to_field(input),
             BinaryExprs::Mod(expr) => expr.to_field(input),
+            BinaryExprs::Pow(expr) => expr.to_field(input),
+            BinaryExprs::Concat(expr) => expr.to_field(input),
         }
     }
 
@@ -153,6 +188,7 @@ impl LogicalExpr for BinaryExprs {
             BinaryExprs::Or(expr) => expr.to_physical_expr(input),
             BinaryExprs::Eq(expr) => expr.to_physical_expr(input),
             BinaryExprs::Neq(expr) => expr.to_physical_expr(input),
+            BinaryExprs::NotDistinct(expr) => expr.to_physical_expr(input),
             BinaryExprs::Gt(expr) => expr.to_physical_expr(input),
             BinaryExprs::Gte(expr) => expr.to_physical_expr(input),
             BinaryExprs::Lt(expr) => expr.to_physical_expr(input),
@@ -162,6 +198,8 @@ impl LogicalExpr for BinaryExprs {
             BinaryExprs::Mul(expr) => expr.to_physical_expr(input),
             BinaryExprs::Div(expr) => expr.to_physical_expr(input),
             BinaryExprs::Mod(expr) => expr.to_physical_expr(input),
+            BinaryExprs::Pow(expr) => expr.to_physical_expr(input),
+            BinaryExprs::Concat(expr) => expr.to_physical_expr(input),
         }
     }
 }
@@ -230,10 +268,25 @@ impl BinaryExpr for MathExpr {
 impl LogicalExpr for MathExpr {
     fn to_field(&self, input: &LogicalPlans) -> Result<Field, ZakuError> {
         let datatype = get_datatype(&self.l, &self.r, input)?;
-        Ok(Field::new(self.op.name(), datatype))
+        match datatype {
+            DataType::Text if self.op == MathOp::Concat => Ok(Field::new(self.op.name(), datatype)),
+            datatype if datatype.is_numeric() && self.op != MathOp::Concat => {
+                Ok(Field::new(self.op.name(), datatype))
+            }
+            DataType::Text if self.op == MathOp::Add => Err(text_addition_error()),
+            _ => Err(ZakuError::new(
+                format!(
+                    "Operator {} is not supported for {} values",
+                    self.op.to_string(),
+                    datatype
+                )
+                .as_str(),
+            )),
+        }
     }
 
     fn to_physical_expr(&self, input: &LogicalPlans) -> Result<PhysicalExprs, ZakuError> {
+        self.to_field(input)?;
         let l = self.l.to_physical_expr(input)?;
         let r = self.r.to_physical_expr(input)?;
 
@@ -255,10 +308,12 @@ fn get_datatype(
     let err = Err(ZakuError::new("Datatypes do not match"));
 
     match l_datatype {
-        DataType::Number => match r_datatype {
-            DataType::Number => Ok(DataType::Number),
-            _ => err,
-        },
+        DataType::Number | DataType::Decimal { .. } => {
+            match l_datatype.numeric_result(r_datatype) {
+                Some(datatype) => Ok(datatype),
+                None => err,
+            }
+        }
         DataType::Text => match r_datatype {
             DataType::Text => Ok(DataType::Text),
             _ => err,
@@ -271,5 +326,6 @@ fn get_datatype(
             DataType::Date => Ok(DataType::Date),
             _ => err,
         },
+        DataType::List => err,
     }
 }
@@ -0,0 +1,129 @@
+use std::fmt::Display;
+
+use crate::{
+    datatypes::{schema::Field, types::DataType},
+    error::ZakuError,
+    physical_plans::{self, physical_expr::PhysicalExprs},
+};
+
+use super::{
+    logical_expr::{LogicalExpr, LogicalExprs},
+    logical_plan::LogicalPlans,
+};
+
+// CASE [operand] WHEN condition THEN result ... [ELSE result] END
+// Without an operand each condition is a predicate, otherwise it is a value compared with
+// the operand. Rows matching no condition take the ELSE result, or null without one
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseExpr {
+    operand: Option<Box<LogicalExprs>>,
+    when_then: Vec<(LogicalExprs, LogicalExprs)>,
+    else_expr: Option<Box<LogicalExprs>>,
+}
+
+impl CaseExpr {
+    pub fn new(
+        operand: Option<LogicalExprs>,
+        when_then: Vec<(LogicalExprs, LogicalExprs)>,
+        else_expr: Option<LogicalExprs>,
+    ) -> CaseExpr {
+        CaseExpr {
+            operand: operand.map(Box::new),
+            when_then,
+            else_expr: else_expr.map(Box::new),
+        }
+    }
+
+    pub fn operand(&self) -> Option<&LogicalExprs> {
+        self.operand.as_deref()
+    }
+
+    pub fn when_then(&self) -> &Vec<(LogicalExprs, LogicalExprs)> {
+        &self.when_then
+    }
+
+    pub fn else_expr(&self) -> Option<&LogicalExprs> {
+        self.else_expr.as_deref()
+    }
+
+    fn check_conditions(&self, input: &LogicalPlans) -> Result<(), ZakuError> {
+        let expected = match &self.operand {
+            Some(operand) => *operand.to_field(input)?.datatype(),
+            None => DataType::Boolean,
+        };
+        self.when_then.iter().try_for_each(|(when, _)| {
+            if !when
+                .to_field(input)?
+                .datatype()
+                .is_compatible_with(&expected)
+            {
+                return Err(ZakuError::new(
+                    format!("CASE conditions must be of type {}", expected).as_str(),
+                ));
+            }
+            Ok(())
+        })
+    }
+}
+
+impl LogicalExpr for CaseExpr {
+    // results of numeric types combine as in arithmetic, any other results must share a type
+    fn to_field(&self, input: &LogicalPlans) -> Result<Field, ZakuError> {
+        let datatype = self
+            .when_then
+            .iter()
+            .map(|(_, then)| then)
+            .chain(self.else_expr.as_deref())
+            .map(|e| Ok(*e.to_field(input)?.datatype()))
+            .reduce(|acc, datatype| {
+                let (acc, datatype) = (acc?, datatype?);
+                match acc.numeric_result(&datatype) {
+                    Some(numeric) => Ok(numeric),
+                    None if acc == datatype => Ok(acc),
+                    None => Err(ZakuError::new("CASE results must have the same type")),
+                }
+            })
+            .ok_or_else(|| ZakuError::new("CASE requires at least one WHEN"))??;
+        Ok(Field::new("case".to_string(), datatype))
+    }
+
+    fn to_physical_expr(&self, input: &LogicalPlans) -> Result<PhysicalExprs, ZakuError> {
+        self.check_conditions(input)?;
+        let datatype = *self.to_field(input)?.datatype();
+        let when_then = self
+            .when_then
+            .iter()
+            .map(|(when, then)| Ok((when.to_physical_expr(input)?, then.to_physical_expr(input)?)))
+            .collect::<Result<Vec<(PhysicalExprs, PhysicalExprs)>, ZakuError>>()?;
+        Ok(PhysicalExprs::CaseExpr(
+            physical_plans::case_expr::CaseExpr::new(
+                self.operand
+                    .as_ref()
+                    .map(|e| e.to_physical_expr(input))
+                    .transpose()?,
+                when_then,
+                self.else_expr
+                    .as_ref()
+                    .map(|e| e.to_physical_expr(input))
+                    .transpose()?,
+                datatype,
+            ),
+        ))
+    }
+}
+
+impl Display for CaseExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "case")?;
+        if let Some(operand) = &self.operand {
+            write!(f, " {}", operand)?;
+        }
+        for (when, then) in &self.when_then {
+            write!(f, " when {} then {}", when, then)?;
+        }
+        if let Some(else_expr) = &self.else_expr {
+            write!(f, " else {}", else_expr)?;
+        }
+        write!(f, " end")
+    }
+}
@@ -0,0 +1,64 @@
+use std::fmt::Display;
+
+use crate::{
+    datatypes::{schema::Field, types::DataType},
+    error::ZakuError,
+    physical_plans::{self, physical_expr::PhysicalExprs},
+};
+
+use super::{
+    logical_expr::{LogicalExpr, LogicalExprs},
+    logical_plan::LogicalPlans,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CastExpr {
+    expr: Box<LogicalExprs>,
+    datatype: DataType,
+}
+
+impl CastExpr {
+    pub fn new(expr: LogicalExprs, datatype: DataType) -> CastExpr {
+        CastExpr {
+            expr: Box::new(expr),
+            datatype,
+        }
+    }
+
+    pub fn expr(&self) -> &LogicalExprs {
+        &self.expr
+    }
+
+    pub fn datatype(&self) -> DataType {
+        self.datatype
+    }
+}
+
+impl LogicalExpr for CastExpr {
+    // the cast column keeps the name of its input, as in Postgres
+    fn to_field(&self, input: &LogicalPlans) -> Result<Field, ZakuError> {
+        let field = self.expr.to_field(input)?;
+        if !field.datatype().can_cast_to(&self.datatype) {
+            return Err(ZakuError::new(
+                format!("Cannot cast {} to {}", field.datatype(), self.datatype).as_str(),
+            ));
+        }
+        Ok(Field::new(field.name().clone(), self.datatype))
+    }
+
+    fn to_physical_expr(&self, input: &LogicalPlans) -> Result<PhysicalExprs, ZakuError> {
+        self.to_field(input)?;
+        Ok(PhysicalExprs::CastExpr(
+            physical_plans::cast_expr::CastExpr::new(
+                self.expr.to_physical_expr(input)?,
+                self.datatype,
+            ),
+        ))
+    }
+}
+
+impl Display for CastExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cast({} as {})", self.expr, self.datatype)
+    }
+}
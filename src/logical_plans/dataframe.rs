@@ -1,20 +1,36 @@
 use std::{
     fmt::{Display, Formatter},
+    io::Read,
     sync::Arc,
 };
 
+use bigdecimal::BigDecimal;
+
 use crate::{
-    datasources::datasource::{CSVDatasource, Datasources, MemDatasource},
-    datatypes::{record_batch::RecordBatch, schema::Schema},
+    datasources::{
+        csv_options::CsvOptions,
+        datasource::{CSVDatasource, Datasource, Datasources, MemDatasource},
+    },
+    datatypes::{
+        record_batch::{RecordBatch, SortOptions},
+        schema::Schema,
+    },
     error::ZakuError,
 };
 
 use super::{
     aggregate_expr::AggregateExprs,
-    logical_expr::LogicalExprs,
-    logical_plan::{Aggregate, Filter, Limit, LogicalPlan, LogicalPlans, Projection, Scan, Sort},
+    generator_expr::SplitRowsExpr,
+    logical_expr::{Column, LogicalExprs},
+    logical_plan::{
+        Aggregate, Filter, Generate, Limit, LogicalPlan, LogicalPlans, Offset, Projection, Scan,
+        Sort, Window,
+    },
 };
 
+// CUBE aggregates 2^n combinations of its expressions
+const MAX_CUBE_COLUMNS: usize = 8;
+
 #[derive(Debug, Clone)]
 pub struct Dataframe {
     plan: Arc<LogicalPlans>,
@@ -33,20 +49,77 @@ impl Dataframe {
         &self.plan
     }
 
+    pub fn estimated_rows(&self) -> usize {
+        self.plan.estimated_rows()
+    }
+
+    pub fn table_name(&self) -> Option<String> {
+        match self.plan.as_ref() {
+            LogicalPlans::Scan(scan) => scan.datasource.table_name(),
+            _ => None,
+        }
+    }
+
     pub fn from_memory(schema: Schema, data: Vec<RecordBatch>) -> Result<Dataframe, ZakuError> {
         let datasource = Datasources::Mem(MemDatasource::new(schema, data));
         Ok(Dataframe::new(Arc::new(LogicalPlans::Scan(Scan::new(
             datasource,
             Vec::new(),
-        )))))
+        )?))))
     }
 
     pub fn from_csv(filename: &str, delimiter: Option<u8>) -> Result<Dataframe, ZakuError> {
-        let datasource = Datasources::Csv(CSVDatasource::from_csv(filename, delimiter)?);
+        Dataframe::from_csv_with_options(filename, &CsvOptions::from_delimiter(delimiter))
+    }
+
+    pub fn from_csv_with_options(
+        filename: &str,
+        options: &CsvOptions,
+    ) -> Result<Dataframe, ZakuError> {
+        let datasource = Datasources::Csv(CSVDatasource::from_csv_with_options(filename, options)?);
         Ok(Dataframe::new(Arc::new(LogicalPlans::Scan(Scan::new(
             datasource,
             Vec::new(),
-        )))))
+        )?))))
+    }
+
+    pub fn from_csv_with_schema(
+        filename: &str,
+        schema: Schema,
+        delimiter: Option<u8>,
+    ) -> Result<Dataframe, ZakuError> {
+        let datasource = Datasources::Csv(CSVDatasource::from_csv_with_schema(
+            filename, schema, delimiter,
+        )?);
+        Ok(Dataframe::new(Arc::new(LogicalPlans::Scan(Scan::new(
+            datasource,
+            Vec::new(),
+        )?))))
+    }
+
+    // Reads csv data from a stream such as stdin, naming the table after name
+    pub fn from_csv_reader<R: Read>(
+        input: R,
+        name: &str,
+        options: &CsvOptions,
+    ) -> Result<Dataframe, ZakuError> {
+        let datasource = Datasources::Csv(CSVDatasource::from_reader(input, name, options)?);
+        Ok(Dataframe::new(Arc::new(LogicalPlans::Scan(Scan::new(
+            datasource,
+            Vec::new(),
+        )?))))
+    }
+
+    // Concatenates the files by column name, filling columns missing from a file with nulls
+    pub fn from_csv_files(
+        filenames: &[&str],
+        options: &CsvOptions,
+    ) -> Result<Dataframe, ZakuError> {
+        let datasource = Datasources::Csv(CSVDatasource::from_csv_files(filenames, options)?);
+        Ok(Dataframe::new(Arc::new(LogicalPlans::Scan(Scan::new(
+            datasource,
+            Vec::new(),
+        )?))))
     }
 
     pub fn projection(&self, expr: Vec<LogicalExprs>) -> Result<Dataframe, ZakuError> {
@@ -69,11 +142,38 @@ impl Dataframe {
         )?))))
     }
 
+    pub fn offset(&self, offset: usize) -> Result<Dataframe, ZakuError> {
+        Ok(Dataframe::new(Arc::new(LogicalPlans::Offset(Offset::new(
+            self.plan.clone(),
+            offset,
+        )))))
+    }
+
+    // returns the rows of the given zero-indexed page
+    pub fn page(&self, page: usize, size: usize) -> Result<Dataframe, ZakuError> {
+        if size == 0 {
+            return Err(ZakuError::new("Page size must be greater than 0"));
+        }
+        let offset = page
+            .checked_mul(size)
+            .ok_or_else(|| ZakuError::new("Page offset is too large"))?;
+        self.offset(offset)?.limit(size)
+    }
+
     pub fn sort(&self, sort_by: Vec<LogicalExprs>, asc: Vec<bool>) -> Result<Dataframe, ZakuError> {
+        self.sort_with_options(sort_by, asc.into_iter().map(SortOptions::new).collect())
+    }
+
+    // sorts with the null placement and collation of each key
+    pub fn sort_with_options(
+        &self,
+        sort_by: Vec<LogicalExprs>,
+        options: Vec<SortOptions>,
+    ) -> Result<Dataframe, ZakuError> {
         Ok(Dataframe::new(Arc::new(LogicalPlans::Sort(Sort::new(
             self.plan.clone(),
             sort_by,
-            asc,
+            options,
         )?))))
     }
 
@@ -86,6 +186,55 @@ impl Dataframe {
             Aggregate::new(self.plan.clone(), group_by, aggregates)?,
         ))))
     }
+
+    // Aggregates over every combination of the group by expressions, from all of them down to
+    // the grand total. Expressions left out of a combination are null in its rows
+    pub fn cube(
+        &self,
+        group_by: Vec<LogicalExprs>,
+        aggregates: Vec<AggregateExprs>,
+    ) -> Result<Dataframe, ZakuError> {
+        let n = group_by.len();
+        if n > MAX_CUBE_COLUMNS {
+            return Err(ZakuError::new(
+                format!("CUBE supports at most {MAX_CUBE_COLUMNS} expressions, got {n}").as_str(),
+            ));
+        }
+        let grouping_sets = (0..1usize << n)
+            .rev()
+            .map(|mask| (0..n).map(|i| mask & (1 << (n - 1 - i)) != 0).collect())
+            .collect();
+        Ok(Dataframe::new(Arc::new(LogicalPlans::Aggregate(
+            Aggregate::new(self.plan.clone(), group_by, aggregates)?
+                .with_grouping_sets(grouping_sets)?,
+        ))))
+    }
+
+    // each distinct value of the column with the number of rows holding it, most frequent first
+    // ties are ordered by value, so the result is stable
+    pub fn value_counts(&self, column: &str) -> Result<Dataframe, ZakuError> {
+        let column = self.schema().get_field(&column.to_string())?.name().clone();
+        let count =
+            AggregateExprs::Count(Box::new(LogicalExprs::LiteralNumber(BigDecimal::from(1))));
+        self.aggregate(vec![LogicalExprs::Column(Column::new(column))], vec![count])?
+            .sort(
+                vec![LogicalExprs::ColumnIndex(1), LogicalExprs::ColumnIndex(0)],
+                vec![false, true],
+            )
+    }
+
+    pub fn generate(&self, expr: SplitRowsExpr) -> Result<Dataframe, ZakuError> {
+        Ok(Dataframe::new(Arc::new(LogicalPlans::Generate(
+            Generate::new(self.plan.clone(), expr)?,
+        ))))
+    }
+
+    pub fn window(&self, window_expr: Vec<AggregateExprs>) -> Result<Dataframe, ZakuError> {
+        Ok(Dataframe::new(Arc::new(LogicalPlans::Window(Window::new(
+            self.plan.clone(),
+            window_expr,
+        )?))))
+    }
 }
 
 impl Display for Dataframe {
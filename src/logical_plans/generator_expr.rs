@@ -0,0 +1,52 @@
+use std::fmt::Display;
+
+use crate::{
+    datatypes::{schema::Field, types::DataType},
+    error::ZakuError,
+};
+
+use super::{
+    logical_expr::{LogicalExpr, LogicalExprs},
+    logical_plan::LogicalPlans,
+};
+
+// SPLIT_ROWS(text, delimiter) emits a row for each part of the text
+// It changes the number of rows, so it is computed by a generate plan rather than a projection
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitRowsExpr {
+    expr: Box<LogicalExprs>,
+    delimiter: Box<LogicalExprs>,
+}
+
+impl SplitRowsExpr {
+    pub fn new(expr: LogicalExprs, delimiter: LogicalExprs) -> SplitRowsExpr {
+        SplitRowsExpr {
+            expr: Box::new(expr),
+            delimiter: Box::new(delimiter),
+        }
+    }
+
+    pub fn expr(&self) -> &LogicalExprs {
+        &self.expr
+    }
+
+    pub fn delimiter(&self) -> &LogicalExprs {
+        &self.delimiter
+    }
+
+    pub fn to_field(&self, input: &LogicalPlans) -> Result<Field, ZakuError> {
+        let text = |e: &LogicalExprs| -> Result<bool, ZakuError> {
+            Ok(e.to_field(input)?.datatype() == &DataType::Text)
+        };
+        if !text(&self.expr)? || !text(&self.delimiter)? {
+            return Err(ZakuError::new("SPLIT_ROWS only supports text values"));
+        }
+        Ok(Field::new("split_rows".to_string(), DataType::Text))
+    }
+}
+
+impl Display for SplitRowsExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "split_rows({}, {})", self.expr, self.delimiter)
+    }
+}
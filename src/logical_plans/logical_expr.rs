@@ -4,7 +4,10 @@ use bigdecimal::BigDecimal;
 use chrono::NaiveDate;
 
 use crate::{
-    datatypes::{schema::Field, types::DataType},
+    datatypes::{
+        schema::{Field, Schema},
+        types::{DataType, Value},
+    },
     error::ZakuError,
     physical_plans::physical_expr::PhysicalExprs,
 };
@@ -13,7 +16,13 @@ use super::{
     aggregate_expr::AggregateExprs,
     binary_expr::BinaryExpr,
     binary_expr::BinaryExprs,
+    case_expr::CaseExpr,
+    cast_expr::CastExpr,
+    generator_expr::SplitRowsExpr,
     logical_plan::{LogicalPlan, LogicalPlans},
+    predicate_expr::{InListExpr, LikeExpr, NotExpr, QuantifiedExpr},
+    scalar_expr::ScalarFunctionExpr,
+    subquery_expr::ScalarSubqueryExpr,
 };
 
 pub trait LogicalExpr {
@@ -33,23 +42,211 @@ pub enum LogicalExprs {
     BinaryExpr(BinaryExprs),
     AggregateExpr(AggregateExprs),
     AliasExpr(AliasExpr),
+    WindowExpr(AggregateExprs),
+    Not(NotExpr),
+    InList(InListExpr),
+    Like(LikeExpr),
+    ScalarFunction(ScalarFunctionExpr),
+    SplitRows(SplitRowsExpr),
+    ScalarSubquery(ScalarSubqueryExpr),
+    Cast(CastExpr),
+    Quantified(QuantifiedExpr),
+    Case(CaseExpr),
 }
 
 impl LogicalExprs {
+    // wraps a predicate in NOT if it is negated
+    pub fn negate(expr: LogicalExprs, negated: bool) -> LogicalExprs {
+        if negated {
+            LogicalExprs::Not(NotExpr::new(expr))
+        } else {
+            expr
+        }
+    }
+
+    // direct sub-expressions, not descending into aggregate or window functions
+    pub fn children(&self) -> Vec<&LogicalExprs> {
+        match self {
+            LogicalExprs::AliasExpr(expr) => vec![expr.expr()],
+            LogicalExprs::BinaryExpr(expr) => vec![expr.get_l(), expr.get_r()],
+            LogicalExprs::Not(expr) => vec![expr.expr()],
+            LogicalExprs::InList(expr) => std::iter::once(expr.expr())
+                .chain(expr.list().iter())
+                .collect(),
+            LogicalExprs::Like(expr) => vec![expr.expr(), expr.pattern()],
+            LogicalExprs::ScalarFunction(expr) => expr.args().iter().collect(),
+            LogicalExprs::SplitRows(expr) => vec![expr.expr(), expr.delimiter()],
+            LogicalExprs::Cast(expr) => vec![expr.expr()],
+            LogicalExprs::Quantified(expr) => vec![expr.expr()],
+            LogicalExprs::Case(expr) => expr
+                .operand()
+                .into_iter()
+                .chain(
+                    expr.when_then()
+                        .iter()
+                        .flat_map(|(when, then)| [when, then]),
+                )
+                .chain(expr.else_expr())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    // rebuilds the expression with every child replaced by f(child)
+    pub fn map_children<F>(&self, f: F) -> Result<LogicalExprs, ZakuError>
+    where
+        F: Fn(&LogicalExprs) -> Result<LogicalExprs, ZakuError>,
+    {
+        match self {
+            LogicalExprs::AliasExpr(expr) => Ok(LogicalExprs::AliasExpr(AliasExpr::new(
+                f(expr.expr())?,
+                expr.alias().clone(),
+            ))),
+            LogicalExprs::BinaryExpr(expr) => Ok(LogicalExprs::BinaryExpr(BinaryExprs::new(
+                f(expr.get_l())?,
+                &expr.get_op(),
+                f(expr.get_r())?,
+            )?)),
+            LogicalExprs::Not(expr) => Ok(LogicalExprs::Not(NotExpr::new(f(expr.expr())?))),
+            LogicalExprs::InList(expr) => Ok(LogicalExprs::InList(InListExpr::new(
+                f(expr.expr())?,
+                expr.list()
+                    .iter()
+                    .map(&f)
+                    .collect::<Result<Vec<LogicalExprs>, ZakuError>>()?,
+            ))),
+            LogicalExprs::Like(expr) => Ok(LogicalExprs::Like(LikeExpr::new(
+                f(expr.expr())?,
+                f(expr.pattern())?,
+                expr.case_insensitive(),
+                expr.escape_char(),
+            ))),
+            LogicalExprs::ScalarFunction(expr) => {
+                Ok(LogicalExprs::ScalarFunction(ScalarFunctionExpr::new(
+                    expr.func(),
+                    expr.args()
+                        .iter()
+                        .map(&f)
+                        .collect::<Result<Vec<LogicalExprs>, ZakuError>>()?,
+                )))
+            }
+            LogicalExprs::SplitRows(expr) => Ok(LogicalExprs::SplitRows(SplitRowsExpr::new(
+                f(expr.expr())?,
+                f(expr.delimiter())?,
+            ))),
+            LogicalExprs::Cast(expr) => Ok(LogicalExprs::Cast(CastExpr::new(
+                f(expr.expr())?,
+                expr.datatype(),
+            ))),
+            LogicalExprs::Quantified(expr) => Ok(LogicalExprs::Quantified(QuantifiedExpr::new(
+                f(expr.expr())?,
+                expr.op(),
+                expr.list().clone(),
+                expr.all(),
+            ))),
+            LogicalExprs::Case(expr) => Ok(LogicalExprs::Case(CaseExpr::new(
+                expr.operand().map(&f).transpose()?,
+                expr.when_then()
+                    .iter()
+                    .map(|(when, then)| Ok((f(when)?, f(then)?)))
+                    .collect::<Result<Vec<(LogicalExprs, LogicalExprs)>, ZakuError>>()?,
+                expr.else_expr().map(&f).transpose()?,
+            ))),
+            _ => Ok(self.clone()),
+        }
+    }
+
+    // names of all columns referenced, including within aggregate and window functions
+    pub fn columns(&self) -> Vec<String> {
+        match self {
+            LogicalExprs::Column(column) => vec![column.name().clone()],
+            LogicalExprs::AggregateExpr(expr) | LogicalExprs::WindowExpr(expr) => {
+                expr.input().columns()
+            }
+            _ => self
+                .children()
+                .iter()
+                .flat_map(|expr| expr.columns())
+                .collect(),
+        }
+    }
+
+    // indexes of the input columns read, including within aggregate and window functions
+    pub fn column_indices(&self, schema: &Schema) -> Result<Vec<usize>, ZakuError> {
+        match self {
+            LogicalExprs::Column(column) => Ok(vec![schema.get_index(column.name())?]),
+            LogicalExprs::ColumnIndex(index) => Ok(vec![*index]),
+            LogicalExprs::AggregateExpr(expr) | LogicalExprs::WindowExpr(expr) => {
+                expr.input().column_indices(schema)
+            }
+            _ => Ok(self
+                .children()
+                .iter()
+                .map(|expr| expr.column_indices(schema))
+                .collect::<Result<Vec<Vec<usize>>, ZakuError>>()?
+                .concat()),
+        }
+    }
+
+    // the value of a literal, used where an expression must be a constant
+    pub fn as_literal(&self) -> Option<Value> {
+        match self {
+            LogicalExprs::LiteralText(value) => Some(Value::Text(value.clone())),
+            LogicalExprs::LiteralBoolean(value) => Some(Value::Boolean(*value)),
+            LogicalExprs::LiteralNumber(value) => Some(Value::Number(value.clone())),
+            LogicalExprs::LiteralDate(value) => Some(Value::Date(*value)),
+            _ => None,
+        }
+    }
+
     // extracts all nested aggregate functions
     pub fn as_aggregate(&self) -> Vec<AggregateExprs> {
         match self {
             LogicalExprs::AggregateExpr(expr) => vec![expr.clone()],
-            LogicalExprs::AliasExpr(expr) => expr.expr.as_aggregate(),
-            LogicalExprs::BinaryExpr(expr) => {
-                let mut exprs = vec![];
-                let mut l = expr.get_l().as_aggregate();
-                exprs.append(&mut l);
-                let mut r = expr.get_r().as_aggregate();
-                exprs.append(&mut r);
-                exprs
+            _ => self
+                .children()
+                .iter()
+                .flat_map(|expr| expr.as_aggregate())
+                .collect(),
+        }
+    }
+
+    // extracts all nested SPLIT_ROWS calls
+    pub fn as_generator(&self) -> Vec<SplitRowsExpr> {
+        match self {
+            LogicalExprs::SplitRows(expr) => vec![expr.clone()],
+            _ => self
+                .children()
+                .iter()
+                .flat_map(|expr| expr.as_generator())
+                .collect(),
+        }
+    }
+
+    // extracts all nested scalar subqueries, including within aggregate and window functions
+    pub fn as_subquery(&self) -> Vec<ScalarSubqueryExpr> {
+        match self {
+            LogicalExprs::ScalarSubquery(expr) => vec![expr.clone()],
+            LogicalExprs::AggregateExpr(expr) | LogicalExprs::WindowExpr(expr) => {
+                expr.input().as_subquery()
             }
-            _ => vec![],
+            _ => self
+                .children()
+                .iter()
+                .flat_map(|expr| expr.as_subquery())
+                .collect(),
+        }
+    }
+
+    // extracts all nested window functions
+    pub fn as_window(&self) -> Vec<AggregateExprs> {
+        match self {
+            LogicalExprs::WindowExpr(expr) => vec![expr.clone()],
+            _ => self
+                .children()
+                .iter()
+                .flat_map(|expr| expr.as_window())
+                .collect(),
         }
     }
 }
@@ -61,6 +258,10 @@ impl LogicalExpr for LogicalExprs {
             LogicalExprs::ColumnIndex(index) => {
                 Ok(input.schema().get_field_by_index(index)?.clone())
             }
+            // un-aliased literals are named after their value, as an empty name cannot be referenced
+            LogicalExprs::LiteralText(value) if value.is_empty() => {
+                Ok(Field::new("?column?".to_string(), DataType::Text))
+            }
             LogicalExprs::LiteralText(value) => Ok(Field::new(value.clone(), DataType::Text)),
             LogicalExprs::LiteralBoolean(value) => {
                 Ok(Field::new(value.to_string(), DataType::Boolean))
@@ -72,6 +273,16 @@ impl LogicalExpr for LogicalExprs {
             LogicalExprs::BinaryExpr(expr) => expr.to_field(input),
             LogicalExprs::AggregateExpr(expr) => expr.to_field(input),
             LogicalExprs::AliasExpr(expr) => expr.to_field(input),
+            LogicalExprs::WindowExpr(expr) => expr.to_field(input),
+            LogicalExprs::Not(expr) => expr.to_field(input),
+            LogicalExprs::InList(expr) => expr.to_field(input),
+            LogicalExprs::Like(expr) => expr.to_field(input),
+            LogicalExprs::ScalarFunction(expr) => expr.to_field(input),
+            LogicalExprs::SplitRows(expr) => expr.to_field(input),
+            LogicalExprs::ScalarSubquery(expr) => expr.to_field(input),
+            LogicalExprs::Cast(expr) => expr.to_field(input),
+            LogicalExprs::Quantified(expr) => expr.to_field(input),
+            LogicalExprs::Case(expr) => expr.to_field(input),
         }
     }
 
@@ -86,6 +297,20 @@ impl LogicalExpr for LogicalExprs {
             LogicalExprs::BinaryExpr(expr) => expr.to_physical_expr(input),
             LogicalExprs::AliasExpr(expr) => expr.to_physical_expr(input),
             LogicalExprs::AggregateExpr(expr) => expr.input().to_physical_expr(input),
+            LogicalExprs::WindowExpr(_) => Err(ZakuError::new(
+                "Window functions must be computed by a window plan",
+            )),
+            LogicalExprs::Not(expr) => expr.to_physical_expr(input),
+            LogicalExprs::InList(expr) => expr.to_physical_expr(input),
+            LogicalExprs::Like(expr) => expr.to_physical_expr(input),
+            LogicalExprs::ScalarFunction(expr) => expr.to_physical_expr(input),
+            LogicalExprs::SplitRows(_) => Err(ZakuError::new(
+                "SPLIT_ROWS must be computed by a generate plan",
+            )),
+            LogicalExprs::ScalarSubquery(expr) => expr.to_physical_expr(input),
+            LogicalExprs::Cast(expr) => expr.to_physical_expr(input),
+            LogicalExprs::Quantified(expr) => expr.to_physical_expr(input),
+            LogicalExprs::Case(expr) => expr.to_physical_expr(input),
         }
     }
 }
@@ -104,6 +329,16 @@ impl Display for LogicalExprs {
             LogicalExprs::BinaryExpr(expr) => expr.to_string(),
             LogicalExprs::AggregateExpr(expr) => expr.to_string(),
             LogicalExprs::AliasExpr(expr) => expr.to_string(),
+            LogicalExprs::WindowExpr(expr) => format!("{} over ()", expr),
+            LogicalExprs::Not(expr) => expr.to_string(),
+            LogicalExprs::InList(expr) => expr.to_string(),
+            LogicalExprs::Like(expr) => expr.to_string(),
+            LogicalExprs::ScalarFunction(expr) => expr.to_string(),
+            LogicalExprs::SplitRows(expr) => expr.to_string(),
+            LogicalExprs::ScalarSubquery(expr) => expr.to_string(),
+            LogicalExprs::Cast(expr) => expr.to_string(),
+            LogicalExprs::Quantified(expr) => expr.to_string(),
+            LogicalExprs::Case(expr) => expr.to_string(),
         };
         write!(f, "{}", string)
     }
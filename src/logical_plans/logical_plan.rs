@@ -3,22 +3,28 @@ use std::{fmt::Display, sync::Arc};
 use enum_dispatch::enum_dispatch;
 
 use crate::{
+    config::ExecutionConfig,
     datasources::datasource::{Datasource, Datasources},
-    datatypes::schema::{Field, Schema},
+    datatypes::{
+        record_batch::SortOptions,
+        schema::{Field, Schema},
+    },
     error::ZakuError,
     physical_plans::{
         accumulator::AggregateExpressions,
         physical_expr::PhysicalExprs,
         physical_plan::{
-            FilterExec, HashAggregateExec, LimitExec, PhysicalPlans, ProjectionExec, ScanExec,
-            SortExec,
+            grouping_sets_str, FilterExec, FilterProjectionExec, GenerateExec, HashAggregateExec,
+            LimitExec, OffsetExec, PhysicalPlans, ProjectionExec, ScanExec, SortExec, WindowExec,
         },
     },
 };
 
 use super::{
     aggregate_expr::AggregateExprs,
+    generator_expr::SplitRowsExpr,
     logical_expr::{LogicalExpr, LogicalExprs},
+    subquery_expr::ScalarSubqueryExpr,
 };
 
 #[enum_dispatch]
@@ -26,9 +32,17 @@ pub trait LogicalPlan {
     fn schema(&self) -> Schema;
     fn children(&self) -> Vec<Arc<LogicalPlans>>;
     fn to_string(&self) -> String;
-    fn to_physical_plan(&self) -> Result<PhysicalPlans, ZakuError>;
+    fn to_physical_plan(&self, config: &Arc<ExecutionConfig>) -> Result<PhysicalPlans, ZakuError>;
+    // a rough number of rows the plan outputs, for choosing between equivalent plans
+    fn estimated_rows(&self) -> usize;
 }
 
+// No column statistics are collected yet, so estimates do not use distinct counts or value
+// ranges. Instead, a filter is assumed to keep a third of its input, and a group by to
+// collapse every ten rows into a group
+const FILTER_SELECTIVITY_DIVISOR: usize = 3;
+const GROUPING_DIVISOR: usize = 10;
+
 #[derive(Debug, Clone)]
 #[enum_dispatch(LogicalPlan)]
 pub enum LogicalPlans {
@@ -36,11 +50,101 @@ pub enum LogicalPlans {
     Projection(Projection),
     Filter(Filter),
     Limit(Limit),
+    Offset(Offset),
     Aggregate(Aggregate),
     Sort(Sort),
+    Window(Window),
+    Generate(Generate),
 }
 
 impl LogicalPlans {
+    // expressions evaluated by this node, not including those of its children
+    pub fn expressions(&self) -> Vec<LogicalExprs> {
+        match self {
+            LogicalPlans::Projection(plan) => plan.expr.clone(),
+            LogicalPlans::Filter(plan) => vec![plan.expr.clone()],
+            LogicalPlans::Sort(plan) => plan.keys.clone(),
+            LogicalPlans::Aggregate(plan) => plan
+                .group_expr
+                .iter()
+                .cloned()
+                .chain(
+                    plan.aggregate_expr
+                        .iter()
+                        .map(|e| LogicalExprs::AggregateExpr(e.clone())),
+                )
+                .collect(),
+            LogicalPlans::Window(plan) => plan
+                .window_expr
+                .iter()
+                .map(|e| LogicalExprs::WindowExpr(e.clone()))
+                .collect(),
+            LogicalPlans::Generate(plan) => vec![LogicalExprs::SplitRows(plan.expr.clone())],
+            LogicalPlans::Scan(_) | LogicalPlans::Limit(_) | LogicalPlans::Offset(_) => vec![],
+        }
+    }
+
+    // scalar subqueries of this plan and its children
+    // Subqueries nested within a subquery come before it, so they are evaluated first
+    pub fn subqueries(&self) -> Vec<ScalarSubqueryExpr> {
+        let mut subqueries: Vec<ScalarSubqueryExpr> = self
+            .children()
+            .iter()
+            .flat_map(|child| child.subqueries())
+            .collect();
+        self.expressions()
+            .iter()
+            .flat_map(|expr| expr.as_subquery())
+            .for_each(|subquery| {
+                subqueries.extend(subquery.plan().subqueries());
+                subqueries.push(subquery);
+            });
+        subqueries
+    }
+
+    // rebuilds this node on top of new inputs, recomputing its schema
+    pub fn with_new_children(
+        &self,
+        children: Vec<Arc<LogicalPlans>>,
+    ) -> Result<LogicalPlans, ZakuError> {
+        let input = match self {
+            LogicalPlans::Scan(_) => return Ok(self.clone()),
+            _ => children
+                .into_iter()
+                .next()
+                .ok_or_else(|| ZakuError::new("Plan requires an input"))?,
+        };
+        match self {
+            LogicalPlans::Scan(_) => Ok(self.clone()),
+            LogicalPlans::Projection(plan) => Ok(LogicalPlans::Projection(Projection::new(
+                input,
+                plan.expr.clone(),
+            )?)),
+            LogicalPlans::Filter(plan) => {
+                Ok(LogicalPlans::Filter(Filter::new(input, plan.expr.clone())?))
+            }
+            LogicalPlans::Limit(plan) => Ok(LogicalPlans::Limit(Limit::new(input, plan.limit)?)),
+            LogicalPlans::Offset(plan) => Ok(LogicalPlans::Offset(Offset::new(input, plan.offset))),
+            LogicalPlans::Aggregate(plan) => Ok(LogicalPlans::Aggregate(
+                Aggregate::new(input, plan.group_expr.clone(), plan.aggregate_expr.clone())?
+                    .with_grouping_sets(plan.grouping_sets.clone())?,
+            )),
+            LogicalPlans::Sort(plan) => Ok(LogicalPlans::Sort(Sort::new(
+                input,
+                plan.keys.clone(),
+                plan.options.clone(),
+            )?)),
+            LogicalPlans::Window(plan) => Ok(LogicalPlans::Window(Window::new(
+                input,
+                plan.window_expr.clone(),
+            )?)),
+            LogicalPlans::Generate(plan) => Ok(LogicalPlans::Generate(Generate::new(
+                input,
+                plan.expr.clone(),
+            )?)),
+        }
+    }
+
     fn format(plan: &LogicalPlans, indent: usize) -> String {
         let mut s = String::new();
         (0..indent).for_each(|_| s.push_str("  "));
@@ -66,11 +170,12 @@ pub struct Scan {
 }
 
 impl Scan {
-    pub fn new(datasource: Datasources, projection: Vec<String>) -> Scan {
-        Scan {
+    pub fn new(datasource: Datasources, projection: Vec<String>) -> Result<Scan, ZakuError> {
+        datasource.schema().select(&projection)?;
+        Ok(Scan {
             datasource,
             projection,
-        }
+        })
     }
 }
 
@@ -78,7 +183,9 @@ impl LogicalPlan for Scan {
     fn schema(&self) -> Schema {
         let mut schema = self.datasource.schema().clone();
         if !self.projection.is_empty() {
-            schema = schema.select(&self.projection);
+            schema = schema
+                .select(&self.projection)
+                .expect("Scan projection is checked when the scan is created");
         }
         schema
     }
@@ -99,12 +206,20 @@ impl LogicalPlan for Scan {
         }
     }
 
-    fn to_physical_plan(&self) -> Result<PhysicalPlans, ZakuError> {
+    fn to_physical_plan(&self, _config: &Arc<ExecutionConfig>) -> Result<PhysicalPlans, ZakuError> {
         Ok(PhysicalPlans::Scan(ScanExec::new(
             self.datasource.clone(),
             self.projection.clone(),
         )))
     }
+
+    fn estimated_rows(&self) -> usize {
+        self.datasource
+            .get_data()
+            .iter()
+            .map(|rb| rb.row_count())
+            .sum()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -124,6 +239,38 @@ impl Projection {
             expr,
         })
     }
+
+    // a projection directly over a filter, and a limit over both, are executed in a single pass
+    fn to_fused_physical_plan(
+        &self,
+        filter: &Filter,
+        limit: Option<usize>,
+        config: &Arc<ExecutionConfig>,
+    ) -> Result<PhysicalPlans, ZakuError> {
+        let predicate = filter.expr.to_physical_expr(&filter.input)?;
+        let physical_expr = self
+            .expr
+            .iter()
+            .map(|e| e.to_physical_expr(&self.input))
+            .collect::<Result<Vec<PhysicalExprs>, ZakuError>>()?;
+        let input_schema = filter.schema();
+        let mut used_columns = self
+            .expr
+            .iter()
+            .map(|e| e.column_indices(&input_schema))
+            .collect::<Result<Vec<Vec<usize>>, ZakuError>>()?
+            .concat();
+        used_columns.sort();
+        used_columns.dedup();
+        Ok(PhysicalPlans::FilterProjection(FilterProjectionExec::new(
+            self.schema.clone(),
+            filter.input.to_physical_plan(config)?,
+            predicate,
+            physical_expr,
+            used_columns,
+            limit,
+        )))
+    }
 }
 
 impl LogicalPlan for Projection {
@@ -146,8 +293,11 @@ impl LogicalPlan for Projection {
         )
     }
 
-    fn to_physical_plan(&self) -> Result<PhysicalPlans, ZakuError> {
-        let physical_plan = self.input.to_physical_plan()?;
+    fn to_physical_plan(&self, config: &Arc<ExecutionConfig>) -> Result<PhysicalPlans, ZakuError> {
+        if let LogicalPlans::Filter(filter) = self.input.as_ref() {
+            return self.to_fused_physical_plan(filter, None, config);
+        }
+        let physical_plan = self.input.to_physical_plan(config)?;
         let projection_fields: Result<Vec<Field>, _> =
             self.expr.iter().map(|e| e.to_field(&self.input)).collect();
         let projection_schema = Schema::new(projection_fields?);
@@ -162,6 +312,10 @@ impl LogicalPlan for Projection {
             physical_expr?,
         )))
     }
+
+    fn estimated_rows(&self) -> usize {
+        self.input.estimated_rows()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -189,8 +343,8 @@ impl LogicalPlan for Filter {
         format!("Filter: {}", self.expr)
     }
 
-    fn to_physical_plan(&self) -> Result<PhysicalPlans, ZakuError> {
-        let physical_plan = self.input.to_physical_plan()?;
+    fn to_physical_plan(&self, config: &Arc<ExecutionConfig>) -> Result<PhysicalPlans, ZakuError> {
+        let physical_plan = self.input.to_physical_plan(config)?;
         let physical_expr = self.expr.to_physical_expr(&self.input)?;
         Ok(PhysicalPlans::Filter(FilterExec::new(
             self.schema(),
@@ -198,6 +352,12 @@ impl LogicalPlan for Filter {
             physical_expr,
         )))
     }
+
+    fn estimated_rows(&self) -> usize {
+        self.input
+            .estimated_rows()
+            .div_ceil(FILTER_SELECTIVITY_DIVISOR)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -225,14 +385,62 @@ impl LogicalPlan for Limit {
         format!("Limit: {}", self.limit)
     }
 
-    fn to_physical_plan(&self) -> Result<PhysicalPlans, ZakuError> {
-        let physical_plan = self.input.to_physical_plan()?;
+    fn to_physical_plan(&self, config: &Arc<ExecutionConfig>) -> Result<PhysicalPlans, ZakuError> {
+        if let LogicalPlans::Projection(projection) = self.input.as_ref() {
+            if let LogicalPlans::Filter(filter) = projection.input.as_ref() {
+                return projection.to_fused_physical_plan(filter, Some(self.limit), config);
+            }
+        }
+        let physical_plan = self.input.to_physical_plan(config)?;
         Ok(PhysicalPlans::Limit(LimitExec::new(
             self.schema(),
             physical_plan,
             self.limit,
         )))
     }
+
+    fn estimated_rows(&self) -> usize {
+        self.input.estimated_rows().min(self.limit)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Offset {
+    input: Arc<LogicalPlans>,
+    offset: usize,
+}
+
+impl Offset {
+    pub fn new(input: Arc<LogicalPlans>, offset: usize) -> Offset {
+        Offset { input, offset }
+    }
+}
+
+impl LogicalPlan for Offset {
+    fn schema(&self) -> Schema {
+        self.input.schema()
+    }
+
+    fn children(&self) -> Vec<Arc<LogicalPlans>> {
+        vec![self.input.clone()]
+    }
+
+    fn to_string(&self) -> String {
+        format!("Offset: {}", self.offset)
+    }
+
+    fn to_physical_plan(&self, config: &Arc<ExecutionConfig>) -> Result<PhysicalPlans, ZakuError> {
+        let physical_plan = self.input.to_physical_plan(config)?;
+        Ok(PhysicalPlans::Offset(OffsetExec::new(
+            self.schema(),
+            physical_plan,
+            self.offset,
+        )))
+    }
+
+    fn estimated_rows(&self) -> usize {
+        self.input.estimated_rows().saturating_sub(self.offset)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -241,6 +449,9 @@ pub struct Aggregate {
     input: Arc<LogicalPlans>,
     group_expr: Vec<LogicalExprs>,
     aggregate_expr: Vec<AggregateExprs>,
+    // which group expressions each set of groups is formed by, with one flag per expression
+    // The other group columns are null in the rows of that set
+    grouping_sets: Vec<Vec<bool>>,
 }
 
 impl Aggregate {
@@ -258,14 +469,41 @@ impl Aggregate {
             .map(|e| e.to_field(&input))
             .collect::<Result<Vec<Field>, _>>()?;
         group_fields.append(&mut aggregate_fields);
+        let grouping_sets = vec![vec![true; group_expr.len()]];
         Ok(Aggregate {
             schema: Schema::new(group_fields),
             input,
             group_expr,
             aggregate_expr,
+            grouping_sets,
         })
     }
 
+    pub fn with_grouping_sets(
+        mut self,
+        grouping_sets: Vec<Vec<bool>>,
+    ) -> Result<Aggregate, ZakuError> {
+        if grouping_sets.is_empty()
+            || grouping_sets
+                .iter()
+                .any(|set| set.len() != self.group_expr.len())
+        {
+            return Err(ZakuError::new(
+                "Grouping sets must have a flag for every group by expression",
+            ));
+        }
+        self.grouping_sets = grouping_sets;
+        Ok(self)
+    }
+
+    pub fn group_expr(&self) -> &Vec<LogicalExprs> {
+        &self.group_expr
+    }
+
+    pub fn aggregate_expr(&self) -> &Vec<AggregateExprs> {
+        &self.aggregate_expr
+    }
+
     fn group_expr_str(&self) -> String {
         if self.group_expr.is_empty() {
             "None".to_string()
@@ -301,15 +539,24 @@ impl LogicalPlan for Aggregate {
     }
 
     fn to_string(&self) -> String {
+        let grouping_sets = if self.grouping_sets.len() > 1 {
+            format!(
+                ", grouping sets={}",
+                grouping_sets_str(&self.group_expr, &self.grouping_sets)
+            )
+        } else {
+            "".to_string()
+        };
         format!(
-            "Aggregate: group by={}, aggregate={}",
+            "Aggregate: group by={}{}, aggregate={}",
             self.group_expr_str(),
+            grouping_sets,
             self.aggr_expr_str()
         )
     }
 
-    fn to_physical_plan(&self) -> Result<PhysicalPlans, ZakuError> {
-        let physical_plan = self.input.to_physical_plan()?;
+    fn to_physical_plan(&self, config: &Arc<ExecutionConfig>) -> Result<PhysicalPlans, ZakuError> {
+        let physical_plan = self.input.to_physical_plan(config)?;
         let physical_group_expr = self
             .group_expr
             .iter()
@@ -323,26 +570,47 @@ impl LogicalPlan for Aggregate {
         Ok(PhysicalPlans::HashAggregate(HashAggregateExec::new(
             physical_plan,
             physical_group_expr,
+            self.grouping_sets.clone(),
             physical_aggregate_expr,
             self.schema(),
+            config.clone(),
         )))
     }
+
+    // a set without group expressions always outputs a single row
+    fn estimated_rows(&self) -> usize {
+        let input_rows = self.input.estimated_rows();
+        self.grouping_sets
+            .iter()
+            .map(|set| match set.iter().any(|grouped| *grouped) {
+                true => input_rows.div_ceil(GROUPING_DIVISOR),
+                false => 1,
+            })
+            .sum()
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Sort {
     input: Arc<LogicalPlans>,
     keys: Vec<LogicalExprs>,
-    asc: Vec<bool>,
+    options: Vec<SortOptions>,
 }
 
 impl Sort {
     pub fn new(
         input: Arc<LogicalPlans>,
         keys: Vec<LogicalExprs>,
-        asc: Vec<bool>,
+        options: Vec<SortOptions>,
     ) -> Result<Sort, ZakuError> {
-        Ok(Sort { input, keys, asc })
+        // checked here, as the optimizer may narrow the input to the columns that exist
+        keys.iter()
+            .try_for_each(|key| key.to_field(&input).map(|_| ()))?;
+        Ok(Sort {
+            input,
+            keys,
+            options,
+        })
     }
 }
 
@@ -360,21 +628,15 @@ impl LogicalPlan for Sort {
             "Sort: keys={}",
             self.keys
                 .iter()
-                .enumerate()
-                .map(|(i, k)| {
-                    let mut asc = "asc";
-                    if !self.asc[i] {
-                        asc = "desc";
-                    }
-                    format!("{} {}", k, asc)
-                })
+                .zip(self.options.iter())
+                .map(|(k, options)| format!("{} {}", k, options))
                 .collect::<Vec<String>>()
                 .join(", ")
         )
     }
 
-    fn to_physical_plan(&self) -> Result<PhysicalPlans, ZakuError> {
-        let physical_plan = self.input.to_physical_plan()?;
+    fn to_physical_plan(&self, config: &Arc<ExecutionConfig>) -> Result<PhysicalPlans, ZakuError> {
+        let physical_plan = self.input.to_physical_plan(config)?;
         let keys: Result<Vec<PhysicalExprs>, ZakuError> = self
             .keys
             .iter()
@@ -384,7 +646,173 @@ impl LogicalPlan for Sort {
             self.schema(),
             physical_plan,
             keys?,
-            self.asc.clone(),
+            self.options.clone(),
+            config.clone(),
         )))
     }
+
+    fn estimated_rows(&self) -> usize {
+        self.input.estimated_rows()
+    }
+}
+
+// Computes aggregates over the whole input, appending each result as a column to every row
+#[derive(Debug, Clone)]
+pub struct Window {
+    schema: Schema,
+    input: Arc<LogicalPlans>,
+    window_expr: Vec<AggregateExprs>,
+}
+
+impl Window {
+    pub fn new(
+        input: Arc<LogicalPlans>,
+        window_expr: Vec<AggregateExprs>,
+    ) -> Result<Window, ZakuError> {
+        let mut fields = input.schema().fields().clone();
+        let mut window_fields = window_expr
+            .iter()
+            .map(|e| e.to_field(&input))
+            .collect::<Result<Vec<Field>, _>>()?;
+        fields.append(&mut window_fields);
+        Ok(Window {
+            schema: Schema::new(fields),
+            input,
+            window_expr,
+        })
+    }
+}
+
+impl LogicalPlan for Window {
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<Arc<LogicalPlans>> {
+        vec![self.input.clone()]
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "Window: window_expr={}",
+            self.window_expr
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+
+    fn to_physical_plan(&self, config: &Arc<ExecutionConfig>) -> Result<PhysicalPlans, ZakuError> {
+        let physical_plan = self.input.to_physical_plan(config)?;
+        let physical_window_expr = self
+            .window_expr
+            .iter()
+            .map(|e| e.to_physical_aggregate(&self.input))
+            .collect::<Result<Vec<AggregateExpressions>, _>>()?;
+        Ok(PhysicalPlans::Window(WindowExec::new(
+            self.schema(),
+            physical_plan,
+            physical_window_expr,
+        )))
+    }
+
+    fn estimated_rows(&self) -> usize {
+        self.input.estimated_rows()
+    }
+}
+
+// Expands every input row into a row per generated value, appended as the last column
+#[derive(Debug, Clone)]
+pub struct Generate {
+    schema: Schema,
+    input: Arc<LogicalPlans>,
+    expr: SplitRowsExpr,
+}
+
+impl Generate {
+    pub fn new(input: Arc<LogicalPlans>, expr: SplitRowsExpr) -> Result<Generate, ZakuError> {
+        let mut fields = input.schema().fields().clone();
+        fields.push(expr.to_field(&input)?);
+        Ok(Generate {
+            schema: Schema::new(fields),
+            input,
+            expr,
+        })
+    }
+}
+
+impl LogicalPlan for Generate {
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<Arc<LogicalPlans>> {
+        vec![self.input.clone()]
+    }
+
+    fn to_string(&self) -> String {
+        format!("Generate: {}", self.expr)
+    }
+
+    fn to_physical_plan(&self, config: &Arc<ExecutionConfig>) -> Result<PhysicalPlans, ZakuError> {
+        let physical_plan = self.input.to_physical_plan(config)?;
+        Ok(PhysicalPlans::Generate(GenerateExec::new(
+            self.schema(),
+            physical_plan,
+            self.expr.expr().to_physical_expr(&self.input)?,
+            self.expr.delimiter().to_physical_expr(&self.input)?,
+            config.clone(),
+        )))
+    }
+
+    // every input row generates at least one value
+    fn estimated_rows(&self) -> usize {
+        self.input.estimated_rows()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        catalog::Catalog, config::ExecutionConfig, datasources::csv_options::CsvOptions,
+        logical_plans::dataframe::Dataframe, sql::parser::parse,
+    };
+
+    // test.csv repeated six times, which makes 30 rows with five distinct products
+    fn estimate(sql: &str) -> usize {
+        let files = ["resources/test.csv"; 6];
+        let df = Dataframe::from_csv_files(&files, &CsvOptions::default()).unwrap();
+        let catalog = Catalog::from_dataframe(df);
+        let max_depth = ExecutionConfig::default().max_expr_depth();
+        let (stmt, _) = parse(sql, &catalog, max_depth).unwrap();
+        stmt.dataframe().estimated_rows()
+    }
+
+    #[test]
+    fn test_estimated_rows() {
+        assert_eq!(estimate("SELECT * FROM test"), 30);
+        assert_eq!(estimate("SELECT * FROM test WHERE price > 2"), 10);
+        assert_eq!(
+            estimate("SELECT * FROM test WHERE price > 2 AND id > 1"),
+            10
+        );
+        assert_eq!(
+            estimate("SELECT product_name, COUNT(*) FROM test GROUP BY product_name"),
+            3
+        );
+        assert_eq!(estimate("SELECT COUNT(*) FROM test"), 1);
+        assert_eq!(
+            estimate(
+                "SELECT product_name, COUNT(*) FROM test WHERE price > 2 GROUP BY product_name"
+            ),
+            1
+        );
+        // one estimate per grouping set, and one row for the grand total
+        assert_eq!(
+            estimate("SELECT product_name, COUNT(*) FROM test GROUP BY CUBE(product_name)"),
+            4
+        );
+        assert_eq!(estimate("SELECT * FROM test LIMIT 4"), 4);
+    }
 }
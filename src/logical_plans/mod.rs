@@ -1,5 +1,11 @@
 pub mod aggregate_expr;
 pub mod binary_expr;
+pub mod case_expr;
+pub mod cast_expr;
 pub mod dataframe;
+pub mod generator_expr;
 pub mod logical_expr;
 pub mod logical_plan;
+pub mod predicate_expr;
+pub mod scalar_expr;
+pub mod subquery_expr;
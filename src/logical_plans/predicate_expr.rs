@@ -0,0 +1,329 @@
+use std::fmt::Display;
+
+use crate::{
+    datatypes::{
+        schema::Field,
+        types::{DataType, Value},
+    },
+    error::ZakuError,
+    physical_plans::{self, physical_expr::PhysicalExprs},
+    sql::operators::{BinaryOp, BooleanOp},
+};
+
+use super::{
+    logical_expr::{LogicalExpr, LogicalExprs},
+    logical_plan::LogicalPlans,
+};
+
+fn expect_datatype(
+    expr: &LogicalExprs,
+    datatype: &DataType,
+    input: &LogicalPlans,
+    err: &str,
+) -> Result<(), ZakuError> {
+    if !expr
+        .to_field(input)?
+        .datatype()
+        .is_compatible_with(datatype)
+    {
+        return Err(ZakuError::new(err));
+    }
+    Ok(())
+}
+
+// Quoted strings parse as dates when they look like one and as text otherwise,
+// so in lists they are cast to the type of the expression they are compared with
+// Other list elements must already have that type
+fn coerce_list_literal(
+    expr: &LogicalExprs,
+    datatype: &DataType,
+    input: &LogicalPlans,
+) -> Result<Option<Value>, ZakuError> {
+    match expr.as_literal() {
+        Some(value @ (Value::Text(_) | Value::Date(_))) => Ok(Some(value.cast(datatype)?)),
+        _ => {
+            expect_datatype(expr, datatype, input, "Datatypes do not match")?;
+            Ok(None)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotExpr {
+    expr: Box<LogicalExprs>,
+}
+
+impl NotExpr {
+    pub fn new(expr: LogicalExprs) -> NotExpr {
+        NotExpr {
+            expr: Box::new(expr),
+        }
+    }
+
+    pub fn expr(&self) -> &LogicalExprs {
+        &self.expr
+    }
+}
+
+impl LogicalExpr for NotExpr {
+    fn to_field(&self, _input: &LogicalPlans) -> Result<Field, ZakuError> {
+        Ok(Field::new("not".to_string(), DataType::Boolean))
+    }
+
+    fn to_physical_expr(&self, input: &LogicalPlans) -> Result<PhysicalExprs, ZakuError> {
+        expect_datatype(
+            &self.expr,
+            &DataType::Boolean,
+            input,
+            "NOT only supports boolean expressions",
+        )?;
+        let expr = self.expr.to_physical_expr(input)?;
+        Ok(PhysicalExprs::NotExpr(
+            physical_plans::predicate_expr::NotExpr::new(Box::new(expr)),
+        ))
+    }
+}
+
+impl Display for NotExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NOT {}", self.expr)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InListExpr {
+    expr: Box<LogicalExprs>,
+    list: Vec<LogicalExprs>,
+}
+
+impl InListExpr {
+    pub fn new(expr: LogicalExprs, list: Vec<LogicalExprs>) -> InListExpr {
+        InListExpr {
+            expr: Box::new(expr),
+            list,
+        }
+    }
+
+    pub fn expr(&self) -> &LogicalExprs {
+        &self.expr
+    }
+
+    pub fn list(&self) -> &Vec<LogicalExprs> {
+        &self.list
+    }
+}
+
+impl LogicalExpr for InListExpr {
+    fn to_field(&self, _input: &LogicalPlans) -> Result<Field, ZakuError> {
+        Ok(Field::new("in".to_string(), DataType::Boolean))
+    }
+
+    fn to_physical_expr(&self, input: &LogicalPlans) -> Result<PhysicalExprs, ZakuError> {
+        let field = self.expr.to_field(input)?;
+        let expr = self.expr.to_physical_expr(input)?;
+        let list = self
+            .list
+            .iter()
+            .map(|e| match coerce_list_literal(e, field.datatype(), input)? {
+                Some(value) => Ok(PhysicalExprs::literal(value)),
+                None => e.to_physical_expr(input),
+            })
+            .collect::<Result<Vec<PhysicalExprs>, ZakuError>>()?;
+        Ok(PhysicalExprs::InListExpr(
+            physical_plans::predicate_expr::InListExpr::new(Box::new(expr), list),
+        ))
+    }
+}
+
+impl Display for InListExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let list = self
+            .list
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        write!(f, "{} IN ({})", self.expr, list)
+    }
+}
+
+// x op ANY (list) and x op ALL (list) compare x with every value of a literal list
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantifiedExpr {
+    expr: Box<LogicalExprs>,
+    op: BooleanOp,
+    list: Vec<LogicalExprs>,
+    all: bool,
+}
+
+impl QuantifiedExpr {
+    pub fn new(expr: LogicalExprs, op: BooleanOp, list: Vec<LogicalExprs>, all: bool) -> Self {
+        QuantifiedExpr {
+            expr: Box::new(expr),
+            op,
+            list,
+            all,
+        }
+    }
+
+    pub fn expr(&self) -> &LogicalExprs {
+        &self.expr
+    }
+
+    pub fn op(&self) -> BooleanOp {
+        self.op
+    }
+
+    pub fn list(&self) -> &Vec<LogicalExprs> {
+        &self.list
+    }
+
+    pub fn all(&self) -> bool {
+        self.all
+    }
+
+    fn quantifier(&self) -> &str {
+        if self.all {
+            "ALL"
+        } else {
+            "ANY"
+        }
+    }
+}
+
+impl LogicalExpr for QuantifiedExpr {
+    fn to_field(&self, _input: &LogicalPlans) -> Result<Field, ZakuError> {
+        Ok(Field::new(
+            self.quantifier().to_lowercase(),
+            DataType::Boolean,
+        ))
+    }
+
+    fn to_physical_expr(&self, input: &LogicalPlans) -> Result<PhysicalExprs, ZakuError> {
+        let field = self.expr.to_field(input)?;
+        let mut values: Vec<Value> = vec![];
+        for e in &self.list {
+            let value = match coerce_list_literal(e, field.datatype(), input)? {
+                Some(value) => value,
+                None => e.as_literal().ok_or_else(|| {
+                    ZakuError::new(
+                        format!("{} only supports lists of literals", self.quantifier()).as_str(),
+                    )
+                })?,
+            };
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        }
+        let expr = self.expr.to_physical_expr(input)?;
+        Ok(PhysicalExprs::QuantifiedExpr(
+            physical_plans::predicate_expr::QuantifiedExpr::new(
+                Box::new(expr),
+                self.op,
+                values,
+                self.all,
+            ),
+        ))
+    }
+}
+
+impl Display for QuantifiedExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let list = self
+            .list
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        write!(
+            f,
+            "{} {} {}([{}])",
+            self.expr,
+            self.op.to_string(),
+            self.quantifier(),
+            list
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LikeExpr {
+    expr: Box<LogicalExprs>,
+    pattern: Box<LogicalExprs>,
+    case_insensitive: bool,
+    escape_char: Option<char>,
+}
+
+impl LikeExpr {
+    pub fn new(
+        expr: LogicalExprs,
+        pattern: LogicalExprs,
+        case_insensitive: bool,
+        escape_char: Option<char>,
+    ) -> LikeExpr {
+        LikeExpr {
+            expr: Box::new(expr),
+            pattern: Box::new(pattern),
+            case_insensitive,
+            escape_char,
+        }
+    }
+
+    pub fn expr(&self) -> &LogicalExprs {
+        &self.expr
+    }
+
+    pub fn pattern(&self) -> &LogicalExprs {
+        &self.pattern
+    }
+
+    pub fn case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    pub fn escape_char(&self) -> Option<char> {
+        self.escape_char
+    }
+
+    fn name(&self) -> &str {
+        if self.case_insensitive {
+            "ilike"
+        } else {
+            "like"
+        }
+    }
+}
+
+impl LogicalExpr for LikeExpr {
+    fn to_field(&self, _input: &LogicalPlans) -> Result<Field, ZakuError> {
+        Ok(Field::new(self.name().to_string(), DataType::Boolean))
+    }
+
+    fn to_physical_expr(&self, input: &LogicalPlans) -> Result<PhysicalExprs, ZakuError> {
+        let err = format!("{} only supports text values", self.name().to_uppercase());
+        expect_datatype(&self.expr, &DataType::Text, input, &err)?;
+        expect_datatype(&self.pattern, &DataType::Text, input, &err)?;
+        let expr = self.expr.to_physical_expr(input)?;
+        let pattern = self.pattern.to_physical_expr(input)?;
+        Ok(PhysicalExprs::LikeExpr(
+            physical_plans::predicate_expr::LikeExpr::new(
+                Box::new(expr),
+                Box::new(pattern),
+                self.case_insensitive,
+                self.escape_char,
+            ),
+        ))
+    }
+}
+
+impl Display for LikeExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.expr,
+            self.name().to_uppercase(),
+            self.pattern
+        )
+    }
+}
@@ -0,0 +1,71 @@
+use std::fmt::Display;
+
+use crate::{
+    datatypes::{schema::Field, types::DataType},
+    error::ZakuError,
+    physical_plans::{self, physical_expr::PhysicalExprs},
+    sql::functions::ScalarFunction,
+};
+
+use super::{
+    logical_expr::{LogicalExpr, LogicalExprs},
+    logical_plan::LogicalPlans,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalarFunctionExpr {
+    func: ScalarFunction,
+    args: Vec<LogicalExprs>,
+}
+
+impl ScalarFunctionExpr {
+    pub fn new(func: ScalarFunction, args: Vec<LogicalExprs>) -> ScalarFunctionExpr {
+        ScalarFunctionExpr { func, args }
+    }
+
+    pub fn func(&self) -> ScalarFunction {
+        self.func
+    }
+
+    pub fn args(&self) -> &Vec<LogicalExprs> {
+        &self.args
+    }
+}
+
+impl LogicalExpr for ScalarFunctionExpr {
+    fn to_field(&self, input: &LogicalPlans) -> Result<Field, ZakuError> {
+        let arg_types = self
+            .args
+            .iter()
+            .map(|arg| Ok(*arg.to_field(input)?.datatype()))
+            .collect::<Result<Vec<DataType>, ZakuError>>()?;
+        Ok(Field::new(
+            self.func.name(),
+            self.func.return_type(&arg_types)?,
+        ))
+    }
+
+    fn to_physical_expr(&self, input: &LogicalPlans) -> Result<PhysicalExprs, ZakuError> {
+        let datatype = *self.to_field(input)?.datatype();
+        let args = self
+            .args
+            .iter()
+            .map(|arg| arg.to_physical_expr(input))
+            .collect::<Result<Vec<PhysicalExprs>, ZakuError>>()?;
+        Ok(PhysicalExprs::ScalarFunctionExpr(
+            physical_plans::scalar_expr::ScalarFunctionExpr::new(self.func, args, datatype),
+        ))
+    }
+}
+
+impl Display for ScalarFunctionExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let args = self
+            .args
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        write!(f, "{}({})", self.func.name(), args)
+    }
+}
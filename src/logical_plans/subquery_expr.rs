@@ -0,0 +1,74 @@
+use std::{
+    fmt::Display,
+    sync::{Arc, OnceLock},
+};
+
+use crate::{
+    datatypes::{schema::Field, types::Value},
+    error::ZakuError,
+    physical_plans::physical_expr::PhysicalExprs,
+};
+
+use super::{
+    dataframe::Dataframe,
+    logical_plan::{LogicalPlan, LogicalPlans},
+};
+
+// An uncorrelated subquery returning a single value, such as (SELECT MAX(price) FROM test)
+// It is executed once before the outer query is planned, and its value is planned as a literal
+#[derive(Debug, Clone)]
+pub struct ScalarSubqueryExpr {
+    plan: Arc<LogicalPlans>,
+    value: Arc<OnceLock<Value>>,
+}
+
+impl ScalarSubqueryExpr {
+    pub fn new(df: Dataframe) -> Result<ScalarSubqueryExpr, ZakuError> {
+        if df.schema().fields().len() != 1 {
+            return Err(ZakuError::new(
+                "Scalar subquery must return exactly one column",
+            ));
+        }
+        Ok(ScalarSubqueryExpr {
+            plan: Arc::new(df.logical_plan().clone()),
+            value: Arc::new(OnceLock::new()),
+        })
+    }
+
+    pub fn plan(&self) -> &LogicalPlans {
+        &self.plan
+    }
+
+    pub fn set_value(&self, value: Value) -> Result<(), ZakuError> {
+        self.value
+            .set(value)
+            .map_err(|_| ZakuError::new("Scalar subquery has already been evaluated"))
+    }
+
+    pub fn to_field(&self, _input: &LogicalPlans) -> Result<Field, ZakuError> {
+        Ok(self.plan.schema().get_field_by_index(&0)?.clone())
+    }
+
+    pub fn to_physical_expr(&self, _input: &LogicalPlans) -> Result<PhysicalExprs, ZakuError> {
+        self.value
+            .get()
+            .map(|value| PhysicalExprs::literal(value.clone()))
+            .ok_or_else(|| ZakuError::new("Scalar subquery has not been evaluated"))
+    }
+}
+
+// Copies of a subquery share the slot holding its value, so they are equal to each other only
+impl PartialEq for ScalarSubqueryExpr {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.value, &other.value)
+    }
+}
+
+impl Display for ScalarSubqueryExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.value.get() {
+            Some(value) => write!(f, "scalar_subquery({})", value),
+            None => write!(f, "scalar_subquery()"),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use crate::{
+    datasources::datasource::{Datasource, Datasources, MemDatasource},
+    datatypes::{
+        record_batch::{RecordBatch, BATCH_SIZE},
+        types::Value,
+    },
+    error::ZakuError,
+    logical_plans::{
+        aggregate_expr::AggregateExprs,
+        logical_plan::{Aggregate, LogicalPlan, LogicalPlans, Scan},
+    },
+};
+
+use super::rule::OptimizerRule;
+
+// Answers COUNT(*) over an unfiltered scan from the row counts of the loaded batches,
+// instead of running the rows through the generic aggregate
+pub struct CountRows {}
+
+impl CountRows {
+    fn counts_rows(aggregate: &Aggregate) -> bool {
+        aggregate.group_expr().is_empty()
+            && !aggregate.aggregate_expr().is_empty()
+            && aggregate.aggregate_expr().iter().all(|e| match e {
+                AggregateExprs::Count(expr) => expr.as_literal().is_some(),
+                _ => false,
+            })
+    }
+
+    fn count(aggregate: &Aggregate, scan: &Scan) -> Result<LogicalPlans, ZakuError> {
+        let rows: usize = scan
+            .datasource
+            .get_data()
+            .iter()
+            .map(|rb| rb.row_count())
+            .sum();
+        let schema = aggregate.schema();
+        let cols = schema
+            .fields()
+            .iter()
+            .map(|_| vec![Value::number(&rows.to_string())])
+            .collect();
+        let data = RecordBatch::to_record_batch(cols, &schema, BATCH_SIZE);
+        let datasource = Datasources::Mem(MemDatasource::new(schema, data));
+        Ok(LogicalPlans::Scan(Scan::new(datasource, Vec::new())?))
+    }
+}
+
+impl OptimizerRule for CountRows {
+    fn apply(&self, plan: LogicalPlans) -> Result<LogicalPlans, ZakuError> {
+        if let LogicalPlans::Aggregate(aggregate) = &plan {
+            if CountRows::counts_rows(aggregate) {
+                if let LogicalPlans::Scan(scan) = aggregate.children()[0].as_ref() {
+                    return CountRows::count(aggregate, scan);
+                }
+            }
+        }
+        let children = plan
+            .children()
+            .iter()
+            .map(|p| Ok(Arc::new(self.apply(p.as_ref().clone())?)))
+            .collect::<Result<Vec<Arc<LogicalPlans>>, ZakuError>>()?;
+        plan.with_new_children(children)
+    }
+}
@@ -0,0 +1,3 @@
+pub mod count_rows;
+pub mod projection_push_down;
+pub mod rule;
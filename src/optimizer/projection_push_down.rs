@@ -0,0 +1,98 @@
+use std::{collections::HashSet, sync::Arc};
+
+use crate::{
+    datasources::datasource::Datasource,
+    error::ZakuError,
+    logical_plans::logical_plan::{LogicalPlan, LogicalPlans, Scan},
+};
+
+use super::rule::OptimizerRule;
+
+// Restricts scans to the columns referenced by the rest of the plan
+pub struct ProjectionPushDown {}
+
+impl ProjectionPushDown {
+    // window and generated results are referenced by position after the columns of their input,
+    // and plans without a projection or aggregate output every scanned column
+    fn can_push_down(plan: &LogicalPlans) -> bool {
+        fn contains_positional(plan: &LogicalPlans) -> bool {
+            matches!(plan, LogicalPlans::Window(_) | LogicalPlans::Generate(_))
+                || plan.children().iter().any(|p| contains_positional(p))
+        }
+
+        fn narrows_output(plan: &LogicalPlans) -> bool {
+            match plan {
+                LogicalPlans::Projection(_) | LogicalPlans::Aggregate(_) => true,
+                LogicalPlans::Scan(_) | LogicalPlans::Window(_) | LogicalPlans::Generate(_) => {
+                    false
+                }
+                _ => plan.children().iter().all(|p| narrows_output(p)),
+            }
+        }
+
+        !contains_positional(plan) && narrows_output(plan)
+    }
+
+    fn collect_columns(plan: &LogicalPlans, columns: &mut HashSet<String>) {
+        plan.expressions()
+            .iter()
+            .flat_map(|e| e.columns())
+            .for_each(|c| {
+                columns.insert(c);
+            });
+        plan.children()
+            .iter()
+            .for_each(|p| ProjectionPushDown::collect_columns(p, columns));
+    }
+
+    fn push_down(
+        plan: &LogicalPlans,
+        columns: &HashSet<String>,
+    ) -> Result<LogicalPlans, ZakuError> {
+        match plan {
+            LogicalPlans::Scan(scan) => {
+                let mut projection: Vec<String> = scan
+                    .schema()
+                    .fields()
+                    .iter()
+                    .map(|f| f.name().clone())
+                    .filter(|name| columns.contains(name))
+                    .collect();
+                // an empty projection reads every column, so keep one to preserve the row count
+                if projection.is_empty() {
+                    projection = scan
+                        .datasource
+                        .schema()
+                        .fields()
+                        .iter()
+                        .take(1)
+                        .map(|f| f.name().clone())
+                        .collect();
+                }
+                Ok(LogicalPlans::Scan(Scan::new(
+                    scan.datasource.clone(),
+                    projection,
+                )?))
+            }
+            _ => {
+                let children = plan
+                    .children()
+                    .iter()
+                    .map(|p| Ok(Arc::new(ProjectionPushDown::push_down(p, columns)?)))
+                    .collect::<Result<Vec<Arc<LogicalPlans>>, ZakuError>>()?;
+                plan.with_new_children(children)
+            }
+        }
+    }
+}
+
+impl OptimizerRule for ProjectionPushDown {
+    fn apply(&self, plan: LogicalPlans) -> Result<LogicalPlans, ZakuError> {
+        if !ProjectionPushDown::can_push_down(&plan) {
+            return Ok(plan);
+        }
+        let mut columns = HashSet::new();
+        ProjectionPushDown::collect_columns(&plan, &mut columns);
+        ProjectionPushDown::push_down(&plan, &columns)
+    }
+}
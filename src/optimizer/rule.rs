@@ -0,0 +1,99 @@
+use std::{mem::discriminant, sync::Arc};
+
+use crate::{
+    datasources::datasource::{Datasource, Datasources},
+    error::ZakuError,
+    logical_plans::logical_plan::{LogicalPlan, LogicalPlans},
+};
+
+use super::{count_rows::CountRows, projection_push_down::ProjectionPushDown};
+
+// Upper bound on the passes over the rules, in case rules keep undoing each other
+const MAX_PASSES: usize = 10;
+
+// A rewrite of a logical plan into an equivalent, cheaper plan
+// Rules are applied to the root of the plan, and return it unchanged if they do not apply
+pub trait OptimizerRule {
+    fn apply(&self, plan: LogicalPlans) -> Result<LogicalPlans, ZakuError>;
+}
+
+pub type OptimizerRules = Vec<Arc<dyn OptimizerRule + Send + Sync>>;
+
+pub struct Optimizer {
+    rules: OptimizerRules,
+}
+
+impl Optimizer {
+    pub fn new() -> Optimizer {
+        Optimizer {
+            rules: vec![Arc::new(CountRows {}), Arc::new(ProjectionPushDown {})],
+        }
+    }
+
+    // runs after the built-in rules
+    pub fn with_rule(mut self, rule: Arc<dyn OptimizerRule + Send + Sync>) -> Optimizer {
+        self.rules.push(rule);
+        self
+    }
+
+    // applies the rules in order, repeating until a pass leaves the plan unchanged
+    // Rules that keep changing the plan, such as two rules undoing each other, are an error
+    pub fn optimize(&self, plan: &LogicalPlans) -> Result<LogicalPlans, ZakuError> {
+        let mut plan = plan.clone();
+        for _ in 0..MAX_PASSES {
+            let optimized = self
+                .rules
+                .iter()
+                .try_fold(plan.clone(), |plan, rule| rule.apply(plan))?;
+            if same_plan(&optimized, &plan) {
+                return Ok(optimized);
+            }
+            plan = optimized;
+        }
+        Err(ZakuError::new(
+            format!("Optimizer rules still change the plan after {MAX_PASSES} passes").as_str(),
+        ))
+    }
+}
+
+// Compares plans node by node without reading the rows of their sources.
+// Copies of a plan share the batches of its sources, so two scans are the same
+// if they read the same batches, and other nodes are the same if they print the same
+fn same_plan(left: &LogicalPlans, right: &LogicalPlans) -> bool {
+    let same_node = match (left, right) {
+        (LogicalPlans::Scan(l), LogicalPlans::Scan(r)) => {
+            l.projection == r.projection && same_source(&l.datasource, &r.datasource)
+        }
+        _ => {
+            discriminant(left) == discriminant(right)
+                && LogicalPlan::to_string(left) == LogicalPlan::to_string(right)
+                && left.schema() == right.schema()
+        }
+    };
+    let (left_children, right_children) = (left.children(), right.children());
+    same_node
+        && left_children.len() == right_children.len()
+        && left_children
+            .iter()
+            .zip(right_children.iter())
+            .all(|(l, r)| same_plan(l, r))
+}
+
+fn same_source(left: &Datasources, right: &Datasources) -> bool {
+    left.path() == right.path()
+        && left.schema() == right.schema()
+        && left.get_data().len() == right.get_data().len()
+        && left.get_data().iter().zip(right.get_data()).all(|(l, r)| {
+            l.columns().len() == r.columns().len()
+                && l.columns()
+                    .iter()
+                    .zip(r.columns())
+                    .all(|(l, r)| Arc::ptr_eq(l, r))
+        })
+}
+
+impl Default for Optimizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
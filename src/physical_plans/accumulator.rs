@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{collections::HashSet, fmt::Display};
 
 use enum_dispatch::enum_dispatch;
 
@@ -6,13 +6,26 @@ use crate::{datatypes::types::Value, ZakuError};
 
 use super::physical_expr::PhysicalExprs;
 
+// RESPECT NULLS is the default, so only IGNORE NULLS is printed
+pub(crate) fn nulls_str(ignore_nulls: bool) -> &'static str {
+    if ignore_nulls {
+        " ignore nulls"
+    } else {
+        ""
+    }
+}
+
 #[derive(Clone)]
 pub enum AggregateExpressions {
     Sum(PhysicalExprs),
     Count(PhysicalExprs),
+    CountDistinct(PhysicalExprs),
     Min(PhysicalExprs),
     Max(PhysicalExprs),
     Avg(PhysicalExprs),
+    ArrayAgg(PhysicalExprs),
+    FirstValue(PhysicalExprs, bool),
+    LastValue(PhysicalExprs, bool),
 }
 
 impl AggregateExpressions {
@@ -20,9 +33,13 @@ impl AggregateExpressions {
         let e = match self {
             AggregateExpressions::Sum(expr) => expr,
             AggregateExpressions::Count(expr) => expr,
+            AggregateExpressions::CountDistinct(expr) => expr,
             AggregateExpressions::Min(expr) => expr,
             AggregateExpressions::Max(expr) => expr,
             AggregateExpressions::Avg(expr) => expr,
+            AggregateExpressions::ArrayAgg(expr) => expr,
+            AggregateExpressions::FirstValue(expr, _) => expr,
+            AggregateExpressions::LastValue(expr, _) => expr,
         };
         e.clone()
     }
@@ -31,9 +48,19 @@ impl AggregateExpressions {
         match self {
             AggregateExpressions::Sum(_) => Accumulators::Sum(Sum::new()),
             AggregateExpressions::Count(_) => Accumulators::Count(Count::new()),
+            AggregateExpressions::CountDistinct(_) => {
+                Accumulators::CountDistinct(CountDistinct::new())
+            }
             AggregateExpressions::Min(_) => Accumulators::Min(Min::new()),
             AggregateExpressions::Max(_) => Accumulators::Max(Max::new()),
             AggregateExpressions::Avg(_) => Accumulators::Avg(Avg::new()),
+            AggregateExpressions::ArrayAgg(_) => Accumulators::ArrayAgg(ArrayAgg::new()),
+            AggregateExpressions::FirstValue(_, ignore_nulls) => {
+                Accumulators::FirstValue(FirstValue::new(*ignore_nulls))
+            }
+            AggregateExpressions::LastValue(_, ignore_nulls) => {
+                Accumulators::LastValue(LastValue::new(*ignore_nulls))
+            }
         }
     }
 }
@@ -43,9 +70,17 @@ impl Display for AggregateExpressions {
         match self {
             AggregateExpressions::Sum(e) => write!(f, "sum({})", e),
             AggregateExpressions::Count(e) => write!(f, "count({})", e),
+            AggregateExpressions::CountDistinct(e) => write!(f, "count(distinct {})", e),
             AggregateExpressions::Min(e) => write!(f, "min({})", e),
             AggregateExpressions::Max(e) => write!(f, "max({})", e),
             AggregateExpressions::Avg(e) => write!(f, "avg({})", e),
+            AggregateExpressions::ArrayAgg(e) => write!(f, "array_agg({})", e),
+            AggregateExpressions::FirstValue(e, ignore_nulls) => {
+                write!(f, "first_value({}){}", e, nulls_str(*ignore_nulls))
+            }
+            AggregateExpressions::LastValue(e, ignore_nulls) => {
+                write!(f, "last_value({}){}", e, nulls_str(*ignore_nulls))
+            }
         }
     }
 }
@@ -54,16 +89,20 @@ impl Display for AggregateExpressions {
 pub trait Accumulator {
     fn accumulate(&mut self, value: &Value) -> Result<(), ZakuError>;
 
-    fn get_value(&self) -> Value;
+    fn get_value(&self) -> Result<Value, ZakuError>;
 }
 
 #[enum_dispatch(Accumulator)]
 pub enum Accumulators {
     Sum(Sum),
     Count(Count),
+    CountDistinct(CountDistinct),
     Min(Min),
     Max(Max),
     Avg(Avg),
+    ArrayAgg(ArrayAgg),
+    FirstValue(FirstValue),
+    LastValue(LastValue),
 }
 
 pub struct Sum {
@@ -88,8 +127,8 @@ impl Accumulator for Sum {
         match &self.value {
             Some(v) => {
                 let new_value = match value {
-                    Value::Number(_) => Some(v.add(value)),
-                    Value::Null => Some(v.add(&Value::number("0"))),
+                    Value::Number(_) => Some(v.add(value)?),
+                    Value::Null => Some(v.add(&Value::number("0"))?),
                     _ => return err,
                 };
                 self.value = new_value;
@@ -105,10 +144,10 @@ impl Accumulator for Sum {
         Ok(())
     }
 
-    fn get_value(&self) -> Value {
+    fn get_value(&self) -> Result<Value, ZakuError> {
         match &self.value {
-            Some(v) => v.clone(),
-            None => Value::Null,
+            Some(v) => Ok(v.clone()),
+            None => Ok(Value::Null),
         }
     }
 }
@@ -135,8 +174,40 @@ impl Accumulator for Count {
         Ok(())
     }
 
-    fn get_value(&self) -> Value {
-        Value::number(self.value.to_string().as_str())
+    fn get_value(&self) -> Result<Value, ZakuError> {
+        Ok(Value::number(self.value.to_string().as_str()))
+    }
+}
+
+// Created per group, so each group counts its own distinct values
+pub struct CountDistinct {
+    values: HashSet<Value>,
+}
+
+impl CountDistinct {
+    pub fn new() -> CountDistinct {
+        CountDistinct {
+            values: HashSet::new(),
+        }
+    }
+}
+
+impl Default for CountDistinct {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Accumulator for CountDistinct {
+    fn accumulate(&mut self, value: &Value) -> Result<(), ZakuError> {
+        if value != &Value::Null && !self.values.contains(value) {
+            self.values.insert(value.clone());
+        }
+        Ok(())
+    }
+
+    fn get_value(&self) -> Result<Value, ZakuError> {
+        Ok(Value::number(self.values.len().to_string().as_str()))
     }
 }
 
@@ -160,8 +231,8 @@ impl Accumulator for Min {
     fn accumulate(&mut self, value: &Value) -> Result<(), ZakuError> {
         match &self.value {
             Some(v) => match v {
-                Value::Number(_) => self.value = Some(v.minimum(value)),
-                Value::Date(_) => self.value = Some(v.minimum(value)),
+                Value::Number(_) => self.value = Some(v.minimum(value)?),
+                Value::Date(_) => self.value = Some(v.minimum(value)?),
                 Value::Null => self.value = Some(value.clone()),
                 _ => return Err(ZakuError::new("MIN only supports numeric and date values")),
             },
@@ -172,10 +243,10 @@ impl Accumulator for Min {
         Ok(())
     }
 
-    fn get_value(&self) -> Value {
+    fn get_value(&self) -> Result<Value, ZakuError> {
         match &self.value {
-            Some(v) => v.clone(),
-            None => Value::Null,
+            Some(v) => Ok(v.clone()),
+            None => Ok(Value::Null),
         }
     }
 }
@@ -200,8 +271,8 @@ impl Accumulator for Max {
     fn accumulate(&mut self, value: &Value) -> Result<(), ZakuError> {
         match &self.value {
             Some(v) => match v {
-                Value::Number(_) => self.value = Some(v.maximum(value)),
-                Value::Date(_) => self.value = Some(v.minimum(value)),
+                Value::Number(_) => self.value = Some(v.maximum(value)?),
+                Value::Date(_) => self.value = Some(v.minimum(value)?),
                 Value::Null => self.value = Some(value.clone()),
                 _ => return Err(ZakuError::new("MAX only supports numeric values")),
             },
@@ -212,10 +283,10 @@ impl Accumulator for Max {
         Ok(())
     }
 
-    fn get_value(&self) -> Value {
+    fn get_value(&self) -> Result<Value, ZakuError> {
         match &self.value {
-            Some(v) => v.clone(),
-            None => Value::Null,
+            Some(v) => Ok(v.clone()),
+            None => Ok(Value::Null),
         }
     }
 }
@@ -246,8 +317,8 @@ impl Accumulator for Avg {
         match &self.sum {
             Some(v) => {
                 let new_value = match value {
-                    Value::Number(_) => Some(v.add(value)),
-                    Value::Null => Some(v.add(&Value::number("0"))),
+                    Value::Number(_) => Some(v.add(value)?),
+                    Value::Null => Some(v.add(&Value::number("0"))?),
                     _ => return err,
                 };
                 self.sum = new_value;
@@ -264,10 +335,95 @@ impl Accumulator for Avg {
         Ok(())
     }
 
-    fn get_value(&self) -> Value {
+    fn get_value(&self) -> Result<Value, ZakuError> {
         match &self.sum {
             Some(v) => v.div(&Value::number(self.count.to_string().as_str())),
-            None => Value::Null,
+            None => Ok(Value::Null),
         }
     }
 }
+
+// Keeps nulls and the order the values arrive in, and is null for a group without rows
+pub struct ArrayAgg {
+    values: Vec<Value>,
+}
+
+impl ArrayAgg {
+    pub fn new() -> ArrayAgg {
+        ArrayAgg { values: Vec::new() }
+    }
+}
+
+impl Default for ArrayAgg {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Accumulator for ArrayAgg {
+    fn accumulate(&mut self, value: &Value) -> Result<(), ZakuError> {
+        self.values.push(value.clone());
+        Ok(())
+    }
+
+    fn get_value(&self) -> Result<Value, ZakuError> {
+        if self.values.is_empty() {
+            return Ok(Value::Null);
+        }
+        Ok(Value::List(self.values.clone()))
+    }
+}
+
+pub struct FirstValue {
+    value: Option<Value>,
+    ignore_nulls: bool,
+}
+
+impl FirstValue {
+    pub fn new(ignore_nulls: bool) -> FirstValue {
+        FirstValue {
+            value: None,
+            ignore_nulls,
+        }
+    }
+}
+
+impl Accumulator for FirstValue {
+    fn accumulate(&mut self, value: &Value) -> Result<(), ZakuError> {
+        if self.value.is_none() && !(self.ignore_nulls && value == &Value::Null) {
+            self.value = Some(value.clone());
+        }
+        Ok(())
+    }
+
+    fn get_value(&self) -> Result<Value, ZakuError> {
+        Ok(self.value.clone().unwrap_or(Value::Null))
+    }
+}
+
+pub struct LastValue {
+    value: Option<Value>,
+    ignore_nulls: bool,
+}
+
+impl LastValue {
+    pub fn new(ignore_nulls: bool) -> LastValue {
+        LastValue {
+            value: None,
+            ignore_nulls,
+        }
+    }
+}
+
+impl Accumulator for LastValue {
+    fn accumulate(&mut self, value: &Value) -> Result<(), ZakuError> {
+        if !(self.ignore_nulls && value == &Value::Null) {
+            self.value = Some(value.clone());
+        }
+        Ok(())
+    }
+
+    fn get_value(&self) -> Result<Value, ZakuError> {
+        Ok(self.value.clone().unwrap_or(Value::Null))
+    }
+}
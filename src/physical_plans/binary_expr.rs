@@ -6,6 +6,7 @@ use crate::{
         record_batch::RecordBatch,
         types::{DataType, Value},
     },
+    error::ZakuError,
     sql::operators::{BinaryOp, BooleanOp, MathOp},
 };
 
@@ -23,17 +24,8 @@ impl BooleanExpr {
         Self { l, op, r }
     }
 
-    fn evaluate_row(&self, l: &Value, r: &Value) -> Value {
-        match self.op {
-            BooleanOp::And => l.and(r),
-            BooleanOp::Or => l.or(r),
-            BooleanOp::Eq => l.eq(r),
-            BooleanOp::Neq => l.neq(r),
-            BooleanOp::Gt => l.gt(r),
-            BooleanOp::Gte => l.gte(r),
-            BooleanOp::Lt => l.lt(r),
-            BooleanOp::Lte => l.lte(r),
-        }
+    fn evaluate_row(&self, l: &Value, r: &Value) -> Result<Value, ZakuError> {
+        self.op.evaluate(l, r)
     }
 }
 
@@ -46,10 +38,10 @@ impl Display for BooleanExpr {
 }
 
 impl PhysicalExpr for BooleanExpr {
-    fn evaluate(&self, record_batch: &RecordBatch) -> Arc<Vectors> {
+    fn evaluate(&self, record_batch: &RecordBatch) -> Result<Arc<Vectors>, ZakuError> {
         let row_num = record_batch.row_count();
-        let l = self.l.evaluate(record_batch);
-        let r = self.r.evaluate(record_batch);
+        let l = self.l.evaluate(record_batch)?;
+        let r = self.r.evaluate(record_batch)?;
 
         let vector: Vec<Value> = (0..row_num)
             .map(|i| {
@@ -57,11 +49,11 @@ impl PhysicalExpr for BooleanExpr {
                 let r_val = r.get_value(&i);
                 self.evaluate_row(l_val, r_val)
             })
-            .collect();
-        Arc::new(Vectors::ColumnVector(ColumnVector::new(
+            .collect::<Result<Vec<Value>, ZakuError>>()?;
+        Ok(Arc::new(Vectors::ColumnVector(ColumnVector::new(
             DataType::Boolean,
             vector,
-        )))
+        ))))
     }
 }
 
@@ -77,13 +69,15 @@ impl MathExpr {
         Self { l, op, r }
     }
 
-    fn evaluate_row(&self, l: &Value, r: &Value) -> Value {
+    fn evaluate_row(&self, l: &Value, r: &Value) -> Result<Value, ZakuError> {
         match self.op {
             MathOp::Add => l.add(r),
             MathOp::Sub => l.sub(r),
             MathOp::Mul => l.mul(r),
             MathOp::Div => l.div(r),
             MathOp::Mod => l.modulo(r),
+            MathOp::Pow => l.pow(r),
+            MathOp::Concat => l.concat(r),
         }
     }
 }
@@ -97,19 +91,25 @@ impl Display for MathExpr {
 }
 
 impl PhysicalExpr for MathExpr {
-    fn evaluate(&self, record_batch: &RecordBatch) -> Arc<Vectors> {
+    fn evaluate(&self, record_batch: &RecordBatch) -> Result<Arc<Vectors>, ZakuError> {
         let row_num = record_batch.row_count();
-        let l = self.l.evaluate(record_batch);
-        let r = self.r.evaluate(record_batch);
-        let datatype = l.get_type();
+        let l = self.l.evaluate(record_batch)?;
+        let r = self.r.evaluate(record_batch)?;
+        // results with a decimal operand are rounded to its scale
+        let datatype = l
+            .get_type()
+            .numeric_result(r.get_type())
+            .unwrap_or(*l.get_type());
 
         let vector: Vec<Value> = (0..row_num)
             .map(|i| {
                 let l_val = l.get_value(&i);
                 let r_val = r.get_value(&i);
-                self.evaluate_row(l_val, r_val)
+                self.evaluate_row(l_val, r_val)?.round_to_type(&datatype)
             })
-            .collect();
-        Arc::new(Vectors::ColumnVector(ColumnVector::new(*datatype, vector)))
+            .collect::<Result<Vec<Value>, ZakuError>>()?;
+        Ok(Arc::new(Vectors::ColumnVector(ColumnVector::new(
+            datatype, vector,
+        ))))
     }
 }
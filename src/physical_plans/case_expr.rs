@@ -0,0 +1,133 @@
+use std::{fmt::Display, sync::Arc};
+
+use crate::{
+    datatypes::{
+        column_vector::{ColumnVector, Vector, Vectors},
+        record_batch::RecordBatch,
+        types::{DataType, Value},
+    },
+    error::ZakuError,
+};
+
+use super::physical_expr::{PhysicalExpr, PhysicalExprs};
+
+// Each row takes the result of the first condition that is true for it.
+// A branch is only evaluated on the rows no earlier condition matched, so that
+// CASE WHEN quantity = 0 THEN 0 ELSE price / quantity END never divides by zero
+#[derive(Clone)]
+pub struct CaseExpr {
+    operand: Option<Box<PhysicalExprs>>,
+    when_then: Vec<(PhysicalExprs, PhysicalExprs)>,
+    else_expr: Option<Box<PhysicalExprs>>,
+    datatype: DataType,
+}
+
+impl CaseExpr {
+    pub fn new(
+        operand: Option<PhysicalExprs>,
+        when_then: Vec<(PhysicalExprs, PhysicalExprs)>,
+        else_expr: Option<PhysicalExprs>,
+        datatype: DataType,
+    ) -> CaseExpr {
+        CaseExpr {
+            operand: operand.map(Box::new),
+            when_then,
+            else_expr: else_expr.map(Box::new),
+            datatype,
+        }
+    }
+}
+
+impl Display for CaseExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "case")?;
+        if let Some(operand) = &self.operand {
+            write!(f, " {}", operand)?;
+        }
+        for (when, then) in &self.when_then {
+            write!(f, " when {} then {}", when, then)?;
+        }
+        if let Some(else_expr) = &self.else_expr {
+            write!(f, " else {}", else_expr)?;
+        }
+        write!(f, " end")
+    }
+}
+
+impl PhysicalExpr for CaseExpr {
+    fn evaluate(&self, record_batch: &RecordBatch) -> Result<Arc<Vectors>, ZakuError> {
+        let operand = self
+            .operand
+            .as_ref()
+            .map(|e| e.evaluate(record_batch))
+            .transpose()?;
+        let mut values = vec![Value::Null; record_batch.row_count()];
+        let mut remaining: Vec<usize> = (0..record_batch.row_count()).collect();
+
+        for (when, then) in &self.when_then {
+            if remaining.is_empty() {
+                break;
+            }
+            let conditions = when.evaluate(&take_rows(record_batch, &remaining))?;
+            let mut matched = vec![];
+            let mut unmatched = vec![];
+            for (i, row) in remaining.into_iter().enumerate() {
+                let condition = match &operand {
+                    Some(operand) => operand.get_value(&row).eq(conditions.get_value(&i))?,
+                    None => conditions.get_value(&i).clone(),
+                };
+                if condition == Value::Boolean(true) {
+                    matched.push(row);
+                } else {
+                    unmatched.push(row);
+                }
+            }
+            self.scatter(then, record_batch, &matched, &mut values)?;
+            remaining = unmatched;
+        }
+        if let Some(else_expr) = &self.else_expr {
+            self.scatter(else_expr, record_batch, &remaining, &mut values)?;
+        }
+        Ok(Arc::new(Vectors::ColumnVector(ColumnVector::new(
+            self.datatype,
+            values,
+        ))))
+    }
+}
+
+impl CaseExpr {
+    // Evaluates expr on the given rows only, writing each result back to the position of its row
+    fn scatter(
+        &self,
+        expr: &PhysicalExprs,
+        record_batch: &RecordBatch,
+        rows: &[usize],
+        values: &mut [Value],
+    ) -> Result<(), ZakuError> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let results = expr.evaluate(&take_rows(record_batch, rows))?;
+        rows.iter().enumerate().try_for_each(|(i, row)| {
+            values[*row] = results.get_value(&i).round_to_type(&self.datatype)?;
+            Ok(())
+        })
+    }
+}
+
+// rows are in ascending order, so taking all of them leaves the batch as it is
+fn take_rows(record_batch: &RecordBatch, rows: &[usize]) -> RecordBatch {
+    if rows.len() == record_batch.row_count() {
+        return record_batch.clone();
+    }
+    let cols = record_batch
+        .iter()
+        .map(|c| {
+            Arc::new(Vectors::ColumnVector(ColumnVector::new(
+                *c.get_type(),
+                rows.iter().map(|i| c.get_value(i).clone()).collect(),
+            )))
+        })
+        .collect();
+    RecordBatch::new(record_batch.schema().clone(), cols)
+}
@@ -0,0 +1,48 @@
+use std::{fmt::Display, sync::Arc};
+
+use crate::{
+    datatypes::{
+        column_vector::{ColumnVector, Vector, Vectors},
+        record_batch::RecordBatch,
+        types::{DataType, Value},
+    },
+    error::ZakuError,
+};
+
+use super::physical_expr::{PhysicalExpr, PhysicalExprs};
+
+#[derive(Clone)]
+pub struct CastExpr {
+    expr: Box<PhysicalExprs>,
+    datatype: DataType,
+}
+
+impl CastExpr {
+    pub fn new(expr: PhysicalExprs, datatype: DataType) -> CastExpr {
+        CastExpr {
+            expr: Box::new(expr),
+            datatype,
+        }
+    }
+}
+
+impl Display for CastExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cast({} as {})", self.expr, self.datatype)
+    }
+}
+
+impl PhysicalExpr for CastExpr {
+    fn evaluate(&self, record_batch: &RecordBatch) -> Result<Arc<Vectors>, ZakuError> {
+        let values = self
+            .expr
+            .evaluate(record_batch)?
+            .iter()
+            .map(|value| value.cast(&self.datatype))
+            .collect::<Result<Vec<Value>, ZakuError>>()?;
+        Ok(Arc::new(Vectors::ColumnVector(ColumnVector::new(
+            self.datatype,
+            values,
+        ))))
+    }
+}
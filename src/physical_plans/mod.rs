@@ -1,4 +1,9 @@
 pub mod accumulator;
 pub mod binary_expr;
+pub mod case_expr;
+pub mod cast_expr;
 pub mod physical_expr;
 pub mod physical_plan;
+pub mod predicate_expr;
+pub mod scalar_expr;
+pub mod spill;
@@ -3,16 +3,25 @@ use std::{fmt::Display, sync::Arc};
 use bigdecimal::BigDecimal;
 use chrono::NaiveDate;
 
-use crate::datatypes::{
-    column_vector::{LiteralVector, Vectors},
-    record_batch::RecordBatch,
-    types::{DataType, Value},
+use crate::{
+    datatypes::{
+        column_vector::{LiteralVector, Vectors},
+        record_batch::RecordBatch,
+        types::{DataType, Value},
+    },
+    error::ZakuError,
 };
 
-use super::binary_expr::{BooleanExpr, MathExpr};
+use super::{
+    binary_expr::{BooleanExpr, MathExpr},
+    case_expr::CaseExpr,
+    cast_expr::CastExpr,
+    predicate_expr::{InListExpr, LikeExpr, NotExpr, QuantifiedExpr},
+    scalar_expr::ScalarFunctionExpr,
+};
 
 pub trait PhysicalExpr {
-    fn evaluate(&self, batch: &RecordBatch) -> Arc<Vectors>;
+    fn evaluate(&self, batch: &RecordBatch) -> Result<Arc<Vectors>, ZakuError>;
 }
 
 #[derive(Clone)]
@@ -22,27 +31,60 @@ pub enum PhysicalExprs {
     LiteralBoolean(bool),
     LiteralNumber(BigDecimal),
     LiteralDate(NaiveDate),
+    LiteralList(Vec<Value>),
+    LiteralNull,
     BooleanExpr(BooleanExpr),
     MathExpr(MathExpr),
+    NotExpr(NotExpr),
+    InListExpr(InListExpr),
+    LikeExpr(LikeExpr),
+    ScalarFunctionExpr(ScalarFunctionExpr),
+    CastExpr(CastExpr),
+    QuantifiedExpr(QuantifiedExpr),
+    CaseExpr(CaseExpr),
+}
+
+impl PhysicalExprs {
+    pub fn literal(value: Value) -> PhysicalExprs {
+        match value {
+            Value::Text(value) => PhysicalExprs::LiteralText(value),
+            Value::Boolean(value) => PhysicalExprs::LiteralBoolean(value),
+            Value::Number(value) => PhysicalExprs::LiteralNumber(value),
+            Value::Date(value) => PhysicalExprs::LiteralDate(value),
+            Value::List(values) => PhysicalExprs::LiteralList(values),
+            Value::Null => PhysicalExprs::LiteralNull,
+        }
+    }
 }
 
 impl PhysicalExpr for PhysicalExprs {
-    fn evaluate(&self, batch: &RecordBatch) -> Arc<Vectors> {
+    fn evaluate(&self, batch: &RecordBatch) -> Result<Arc<Vectors>, ZakuError> {
         let size = batch.row_count();
         match self {
-            PhysicalExprs::Column(index) => batch
-                .get(index)
-                .expect("Expected column to be in record batch"),
+            PhysicalExprs::Column(index) => batch.get(index),
             PhysicalExprs::LiteralText(value) => {
-                create_literal(Value::Text(value.to_string()), size)
+                Ok(create_literal(Value::Text(value.to_string()), size))
+            }
+            PhysicalExprs::LiteralBoolean(value) => {
+                Ok(create_literal(Value::Boolean(*value), size))
             }
-            PhysicalExprs::LiteralBoolean(value) => create_literal(Value::Boolean(*value), size),
             PhysicalExprs::LiteralNumber(value) => {
-                create_literal(Value::Number(value.clone()), size)
+                Ok(create_literal(Value::Number(value.clone()), size))
+            }
+            PhysicalExprs::LiteralDate(value) => Ok(create_literal(Value::Date(*value), size)),
+            PhysicalExprs::LiteralList(values) => {
+                Ok(create_literal(Value::List(values.clone()), size))
             }
-            PhysicalExprs::LiteralDate(value) => create_literal(Value::Date(*value), size),
+            PhysicalExprs::LiteralNull => Ok(create_literal(Value::Null, size)),
             PhysicalExprs::BooleanExpr(expr) => expr.evaluate(batch),
             PhysicalExprs::MathExpr(expr) => expr.evaluate(batch),
+            PhysicalExprs::NotExpr(expr) => expr.evaluate(batch),
+            PhysicalExprs::InListExpr(expr) => expr.evaluate(batch),
+            PhysicalExprs::LikeExpr(expr) => expr.evaluate(batch),
+            PhysicalExprs::ScalarFunctionExpr(expr) => expr.evaluate(batch),
+            PhysicalExprs::CastExpr(expr) => expr.evaluate(batch),
+            PhysicalExprs::QuantifiedExpr(expr) => expr.evaluate(batch),
+            PhysicalExprs::CaseExpr(expr) => expr.evaluate(batch),
         }
     }
 }
@@ -55,8 +97,17 @@ impl Display for PhysicalExprs {
             PhysicalExprs::LiteralBoolean(value) => write!(f, "{}", value),
             PhysicalExprs::LiteralNumber(value) => write!(f, "{}", value),
             PhysicalExprs::LiteralDate(value) => write!(f, "{}", value),
+            PhysicalExprs::LiteralList(values) => write!(f, "{}", Value::List(values.clone())),
+            PhysicalExprs::LiteralNull => write!(f, "null"),
             PhysicalExprs::BooleanExpr(expr) => write!(f, "{}", expr),
             PhysicalExprs::MathExpr(expr) => write!(f, "{}", expr),
+            PhysicalExprs::NotExpr(expr) => write!(f, "{}", expr),
+            PhysicalExprs::InListExpr(expr) => write!(f, "{}", expr),
+            PhysicalExprs::LikeExpr(expr) => write!(f, "{}", expr),
+            PhysicalExprs::ScalarFunctionExpr(expr) => write!(f, "{}", expr),
+            PhysicalExprs::CastExpr(expr) => write!(f, "{}", expr),
+            PhysicalExprs::QuantifiedExpr(expr) => write!(f, "{}", expr),
+            PhysicalExprs::CaseExpr(expr) => write!(f, "{}", expr),
         }
     }
 }
@@ -83,6 +134,11 @@ fn create_literal(val: Value, size: usize) -> Arc<Vectors> {
             val,
             size,
         ))),
+        Value::List(_) => Arc::new(Vectors::LiteralVector(LiteralVector::new(
+            DataType::List,
+            val,
+            size,
+        ))),
         Value::Null => Arc::new(Vectors::LiteralVector(LiteralVector::new(
             DataType::default(),
             val,
@@ -3,10 +3,12 @@ use futures_async_stream::try_stream;
 use std::{collections::HashMap, fmt::Display, sync::Arc};
 
 use crate::{
+    config::ExecutionConfig,
     datasources::datasource::{Datasource, Datasources},
     datatypes::{
-        column_vector::{ColumnVector, Vector, Vectors},
-        record_batch::RecordBatch,
+        column_vector::{ColumnVector, LiteralVector, Vector, Vectors},
+        hash::ValueHasher,
+        record_batch::{sort_indices, RecordBatch, SortOptions},
         schema::Schema,
         types::Value,
     },
@@ -17,8 +19,11 @@ use crate::{
 use super::{
     accumulator::AggregateExpressions,
     physical_expr::{PhysicalExpr, PhysicalExprs},
+    spill::SpillFiles,
 };
 
+type AggregatorMap = HashMap<Vec<Value>, Vec<Accumulators>, ValueHasher>;
+
 #[enum_dispatch]
 pub trait PhysicalPlan {
     fn schema(&self) -> Schema;
@@ -34,9 +39,13 @@ pub enum PhysicalPlans {
     Scan(ScanExec),
     Projection(ProjectionExec),
     Filter(FilterExec),
+    FilterProjection(FilterProjectionExec),
     Limit(LimitExec),
+    Offset(OffsetExec),
     HashAggregate(HashAggregateExec),
     Sort(SortExec),
+    Window(WindowExec),
+    Generate(GenerateExec),
 }
 
 impl PhysicalPlans {
@@ -46,9 +55,13 @@ impl PhysicalPlans {
             PhysicalPlans::Scan(exec) => exec.execute(),
             PhysicalPlans::Projection(exec) => exec.execute(),
             PhysicalPlans::Filter(exec) => exec.execute(),
+            PhysicalPlans::FilterProjection(exec) => exec.execute(),
             PhysicalPlans::Limit(exec) => exec.execute(),
+            PhysicalPlans::Offset(exec) => exec.execute(),
             PhysicalPlans::HashAggregate(exec) => exec.execute(),
             PhysicalPlans::Sort(exec) => exec.execute(),
+            PhysicalPlans::Window(exec) => exec.execute(),
+            PhysicalPlans::Generate(exec) => exec.execute(),
         };
         #[for_await]
         for res in stream {
@@ -90,15 +103,37 @@ impl ScanExec {
 
     #[try_stream(boxed, ok = RecordBatch, error = ZakuError)]
     pub async fn execute(&self) {
-        for rb in self.datasource.get_data() {
-            yield rb.clone()
+        if self.projection.is_empty() {
+            for rb in self.datasource.get_data() {
+                yield rb.clone()
+            }
+        } else {
+            let schema = self.schema();
+            let indices = self
+                .projection
+                .iter()
+                .map(|name| self.datasource.schema().get_index(name))
+                .collect::<Result<Vec<usize>, ZakuError>>()?;
+            for rb in self.datasource.get_data() {
+                let cols = indices
+                    .iter()
+                    .map(|i| rb.get(i))
+                    .collect::<Result<Vec<Arc<Vectors>>, ZakuError>>()?;
+                yield RecordBatch::new(schema.clone(), cols)
+            }
         }
     }
 }
 
 impl PhysicalPlan for ScanExec {
     fn schema(&self) -> Schema {
-        self.datasource.schema().select(&self.projection)
+        if self.projection.is_empty() {
+            return self.datasource.schema().clone();
+        }
+        self.datasource
+            .schema()
+            .select(&self.projection)
+            .expect("Scan projection is checked when the scan is created")
     }
 
     fn children(&self) -> Vec<PhysicalPlans> {
@@ -139,7 +174,11 @@ impl ProjectionExec {
         #[for_await]
         for rb in self.input.execute() {
             let rb = rb?;
-            let columns = self.expr.iter().map(|e| e.evaluate(&rb)).collect();
+            let columns = self
+                .expr
+                .iter()
+                .map(|e| e.evaluate(&rb))
+                .collect::<Result<Vec<Arc<Vectors>>, ZakuError>>()?;
             yield RecordBatch::new(self.schema.clone(), columns)
         }
     }
@@ -187,7 +226,7 @@ impl FilterExec {
         #[for_await]
         for res in self.input.execute() {
             let rb = res?;
-            let eval_col = self.expr.evaluate(&rb);
+            let eval_col = self.expr.evaluate(&rb)?;
             let cols = rb
                 .iter()
                 .map(|c| {
@@ -220,6 +259,129 @@ impl PhysicalPlan for FilterExec {
     }
 }
 
+// Fuses a projection, and optionally a limit, over a filter into a single pass
+// Only the surviving rows of the columns read by the projection are copied,
+// and no more input is read once the limit is reached
+#[derive(Clone)]
+pub struct FilterProjectionExec {
+    schema: Schema,
+    input: Box<PhysicalPlans>,
+    predicate: PhysicalExprs,
+    expr: Vec<PhysicalExprs>,
+    used_columns: Vec<usize>,
+    limit: Option<usize>,
+}
+
+impl FilterProjectionExec {
+    pub fn new(
+        schema: Schema,
+        input: PhysicalPlans,
+        predicate: PhysicalExprs,
+        expr: Vec<PhysicalExprs>,
+        used_columns: Vec<usize>,
+        limit: Option<usize>,
+    ) -> FilterProjectionExec {
+        FilterProjectionExec {
+            schema,
+            input: Box::new(input),
+            predicate,
+            expr,
+            used_columns,
+            limit,
+        }
+    }
+
+    #[try_stream(boxed, ok = RecordBatch, error = ZakuError)]
+    pub async fn execute(&self) {
+        let mut remaining = self.limit;
+        if remaining == Some(0) {
+            let cols = self
+                .schema
+                .fields()
+                .iter()
+                .map(|f| {
+                    Arc::new(Vectors::ColumnVector(ColumnVector::new(
+                        *f.datatype(),
+                        vec![],
+                    )))
+                })
+                .collect();
+            yield RecordBatch::new(self.schema.clone(), cols);
+            return Ok(());
+        }
+
+        #[for_await]
+        for res in self.input.execute() {
+            let rb = res?;
+            let eval_col = self.predicate.evaluate(&rb)?;
+            let mut indices: Vec<usize> = (0..rb.row_count())
+                .filter(|i| eval_col.get_value(i) == &Value::Boolean(true))
+                .collect();
+            if let Some(remaining) = remaining.as_mut() {
+                indices.truncate(*remaining);
+                *remaining -= indices.len();
+            }
+
+            // columns only read by the predicate are replaced by placeholders to keep the indexes
+            let cols = rb
+                .iter()
+                .enumerate()
+                .map(|(i, c)| {
+                    if self.used_columns.contains(&i) {
+                        Arc::new(Vectors::ColumnVector(ColumnVector::new(
+                            *c.get_type(),
+                            indices.iter().map(|j| c.get_value(j).clone()).collect(),
+                        )))
+                    } else {
+                        Arc::new(Vectors::LiteralVector(LiteralVector::new(
+                            *c.get_type(),
+                            Value::Null,
+                            indices.len(),
+                        )))
+                    }
+                })
+                .collect();
+            let filtered = RecordBatch::new(rb.schema().clone(), cols);
+            let columns = self
+                .expr
+                .iter()
+                .map(|e| e.evaluate(&filtered))
+                .collect::<Result<Vec<Arc<Vectors>>, ZakuError>>()?;
+            yield RecordBatch::new(self.schema.clone(), columns);
+
+            if remaining == Some(0) {
+                break;
+            }
+        }
+    }
+}
+
+impl PhysicalPlan for FilterProjectionExec {
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<PhysicalPlans> {
+        vec![*self.input.clone()]
+    }
+
+    fn to_string(&self) -> String {
+        let expr = self
+            .expr
+            .iter()
+            .map(|e| format!("{}", e))
+            .collect::<Vec<String>>()
+            .join(", ");
+        match self.limit {
+            Some(limit) => format!(
+                "FilterProjection: {} | {} | Limit: {}",
+                self.predicate, expr, limit
+            ),
+            None => format!("FilterProjection: {} | {}", self.predicate, expr),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct LimitExec {
     schema: Schema,
@@ -295,12 +457,70 @@ impl PhysicalPlan for LimitExec {
     }
 }
 
+#[derive(Clone)]
+pub struct OffsetExec {
+    schema: Schema,
+    input: Box<PhysicalPlans>,
+    offset: usize,
+}
+
+impl OffsetExec {
+    pub fn new(schema: Schema, input: PhysicalPlans, offset: usize) -> OffsetExec {
+        OffsetExec {
+            schema,
+            input: Box::new(input),
+            offset,
+        }
+    }
+
+    #[try_stream(boxed, ok = RecordBatch, error = ZakuError)]
+    pub async fn execute(&self) {
+        let mut counter = self.offset;
+        #[for_await]
+        for res in self.input.execute() {
+            let rb = res?;
+            if counter >= rb.row_count() {
+                counter -= rb.row_count();
+                continue;
+            }
+            let skip = counter;
+            counter = 0;
+            let cols: Vec<Arc<Vectors>> = rb
+                .iter()
+                .map(|c| {
+                    Arc::new(Vectors::ColumnVector(ColumnVector::new(
+                        *c.get_type(),
+                        c.iter().skip(skip).cloned().collect(),
+                    )))
+                })
+                .collect();
+
+            yield RecordBatch::new(self.schema.clone(), cols)
+        }
+    }
+}
+
+impl PhysicalPlan for OffsetExec {
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<PhysicalPlans> {
+        vec![*self.input.clone()]
+    }
+
+    fn to_string(&self) -> String {
+        format!("Offset: {}", self.offset)
+    }
+}
+
 #[derive(Clone)]
 pub struct SortExec {
     schema: Schema,
     input: Box<PhysicalPlans>,
     sort_keys: Vec<PhysicalExprs>,
-    asc: Vec<bool>,
+    options: Vec<SortOptions>,
+    config: Arc<ExecutionConfig>,
 }
 
 impl SortExec {
@@ -308,61 +528,42 @@ impl SortExec {
         schema: Schema,
         input: PhysicalPlans,
         sort_keys: Vec<PhysicalExprs>,
-        asc: Vec<bool>,
+        options: Vec<SortOptions>,
+        config: Arc<ExecutionConfig>,
     ) -> SortExec {
         SortExec {
             schema,
             input: Box::new(input),
             sort_keys,
-            asc,
+            options,
+            config,
         }
     }
 
     #[try_stream(boxed, ok = RecordBatch, error = ZakuError)]
     pub async fn execute(&self) {
-        let sort_keys_idx = self
-            .sort_keys
-            .iter()
-            .flat_map(|e| match e {
-                PhysicalExprs::Column(i) => Ok(*i),
-                _ => Err(ZakuError::new("Sort keys must be column indexes")),
-            })
-            .collect::<Vec<usize>>();
-
-        // Aggregate and materialize all values
+        // Aggregate and materialize all values, along with the evaluated sort keys
         let mut cols: Vec<Vec<Value>> = self.schema().fields().iter().map(|_| vec![]).collect();
+        let mut keys: Vec<Vec<Value>> = self.sort_keys.iter().map(|_| vec![]).collect();
         #[for_await]
         for res in self.input.execute() {
-            let rb = res?.sort(&sort_keys_idx, &self.asc)?;
+            let rb = res?;
+            for (i, key) in self.sort_keys.iter().enumerate() {
+                keys[i].extend(key.evaluate(&rb)?.iter().cloned());
+            }
             for (i, col) in rb.columns().iter().enumerate() {
-                col.iter().for_each(|v| {
-                    cols[i].push(v.clone());
-                });
+                cols[i].extend(col.iter().cloned());
             }
         }
 
-        // Sort all values
-        let rev_asc: Vec<&bool> = self.asc.iter().rev().collect();
-        sort_keys_idx.iter().rev().enumerate().for_each(|(i, k)| {
-            let mut new_sorted_cols = vec![];
-            let mut indices: Vec<usize> = (0..cols[*k].len()).collect();
-            indices.sort_by_key(|&i| cols[*k][i].clone());
-
-            if !rev_asc[i] {
-                indices.reverse();
-            }
-
-            cols.iter().for_each(|col| {
-                let mut reorder: Vec<Value> = Vec::with_capacity(col.len());
-                indices.iter().for_each(|i| {
-                    reorder.push(col[*i].clone());
-                });
-                new_sorted_cols.push(reorder);
-            });
-            cols = new_sorted_cols;
-        });
+        // Sort all values, rows with tied sort keys keep their input order
+        let indices = sort_indices(&keys, &self.options);
+        let cols = cols
+            .iter()
+            .map(|col| indices.iter().map(|i| col[*i].clone()).collect())
+            .collect();
 
-        for rb in RecordBatch::to_record_batch(cols, &self.schema()) {
+        for rb in RecordBatch::to_record_batch(cols, &self.schema(), self.config.batch_size()) {
             yield rb
         }
     }
@@ -382,93 +583,193 @@ impl PhysicalPlan for SortExec {
             "Sort: keys={}",
             self.sort_keys
                 .iter()
-                .enumerate()
-                .map(|(i, k)| {
-                    let mut asc = "asc";
-                    if !self.asc[i] {
-                        asc = "desc";
-                    }
-                    format!("{} {}", k, asc)
-                })
+                .zip(self.options.iter())
+                .map(|(k, options)| format!("{} {}", k, options))
                 .collect::<Vec<String>>()
                 .join(", ")
         )
     }
 }
 
+// Lists the group expressions of each grouping set, such as (a, b), (a), ()
+// Shared by the logical and physical aggregate plans
+pub fn grouping_sets_str<E: Display>(group_expr: &[E], grouping_sets: &[Vec<bool>]) -> String {
+    grouping_sets
+        .iter()
+        .map(|set| {
+            let exprs = group_expr
+                .iter()
+                .zip(set.iter())
+                .filter(|(_, grouped)| **grouped)
+                .map(|(e, _)| e.to_string())
+                .collect::<Vec<String>>()
+                .join(", ");
+            format!("({})", exprs)
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
 #[derive(Clone)]
 pub struct HashAggregateExec {
     input: Box<PhysicalPlans>,
     group_expr: Vec<PhysicalExprs>,
+    grouping_sets: Vec<Vec<bool>>,
     aggr_expr: Vec<AggregateExpressions>,
     schema: Schema,
+    config: Arc<ExecutionConfig>,
 }
 
 impl HashAggregateExec {
     pub fn new(
         input: PhysicalPlans,
         group_expr: Vec<PhysicalExprs>,
+        grouping_sets: Vec<Vec<bool>>,
         aggr_expr: Vec<AggregateExpressions>,
         schema: Schema,
+        config: Arc<ExecutionConfig>,
     ) -> HashAggregateExec {
         HashAggregateExec {
             input: Box::new(input),
             group_expr,
+            grouping_sets,
             aggr_expr,
             schema,
+            config,
         }
     }
 
+    fn create_accumulators(&self) -> Vec<Accumulators> {
+        self.aggr_expr
+            .iter()
+            .map(|e| e.create_accumulator())
+            .collect()
+    }
+
+    // Groups are keyed by the index of their grouping set followed by the group values,
+    // so that the nulls of columns outside a set do not merge with null values in the data.
+    // Once the spill threshold is reached, rows of groups not yet in memory are written to
+    // spill files, and each spill partition is aggregated after the groups kept in memory
     #[try_stream(boxed, ok = RecordBatch, error = ZakuError)]
     pub async fn execute(&self) {
-        let mut aggregator_map: HashMap<Vec<Value>, Vec<Accumulators>> = HashMap::new();
+        let hasher = ValueHasher::new(self.config.deterministic_hash());
+        let mut aggregator_map: AggregatorMap = HashMap::with_hasher(hasher.clone());
+        let mut spill: Option<SpillFiles> = None;
+
+        // a set without group columns, as without GROUP BY, always has a single group,
+        // even when the input has no rows, so COUNT gives 0 and the other aggregates give null
+        for (set_idx, set) in self.grouping_sets.iter().enumerate() {
+            if set.iter().all(|grouped| !grouped) {
+                let key = std::iter::once(Value::number(&set_idx.to_string()))
+                    .chain(set.iter().map(|_| Value::Null))
+                    .collect();
+                aggregator_map.insert(key, self.create_accumulators());
+            }
+        }
+
         #[for_await]
         for res in self.input.execute() {
             let rb = res?;
-            let group_keys: Vec<Arc<Vectors>> =
-                self.group_expr.iter().map(|e| e.evaluate(&rb)).collect();
-            let aggr_input: Vec<Arc<Vectors>> = self
+            let group_keys = self
+                .group_expr
+                .iter()
+                .map(|e| e.evaluate(&rb))
+                .collect::<Result<Vec<Arc<Vectors>>, ZakuError>>()?;
+            let aggr_input = self
                 .aggr_expr
                 .iter()
                 .map(|e| e.input_expr().evaluate(&rb))
-                .collect();
+                .collect::<Result<Vec<Arc<Vectors>>, ZakuError>>()?;
 
-            (0..rb.row_count()).try_for_each(|i| {
-                let row_key: Vec<Value> = group_keys
-                    .iter()
-                    .map(|key| key.get_value(&i).clone())
-                    .collect();
+            for (set_idx, set) in self.grouping_sets.iter().enumerate() {
+                for i in 0..rb.row_count() {
+                    let row_key: Vec<Value> = std::iter::once(Value::number(&set_idx.to_string()))
+                        .chain(group_keys.iter().zip(set.iter()).map(|(key, grouped)| {
+                            if *grouped {
+                                key.get_value(&i).clone()
+                            } else {
+                                Value::Null
+                            }
+                        }))
+                        .collect();
 
-                let accumulators = aggregator_map.entry(row_key).or_insert_with(|| {
-                    self.aggr_expr
-                        .iter()
-                        .map(|e| e.create_accumulator())
-                        .collect()
-                });
+                    let in_memory = aggregator_map.contains_key(&row_key);
+                    if !in_memory
+                        && spill.is_none()
+                        && self
+                            .config
+                            .spill_threshold()
+                            .is_some_and(|threshold| aggregator_map.len() >= threshold)
+                    {
+                        spill = Some(SpillFiles::create(self.config.spill_dir(), hasher.clone())?);
+                    }
+                    match spill.as_mut() {
+                        Some(files) if !in_memory => {
+                            let inputs: Vec<&Value> =
+                                aggr_input.iter().map(|v| v.get_value(&i)).collect();
+                            files.write(&row_key, &inputs)?;
+                        }
+                        _ => {
+                            aggregator_map
+                                .entry(row_key)
+                                .or_insert_with(|| self.create_accumulators())
+                                .iter_mut()
+                                .zip(aggr_input.iter())
+                                .try_for_each(|(a, v)| a.accumulate(v.get_value(&i)))?;
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(files) = spill.as_mut() {
+            files.finish()?;
+        }
+        for rb in self.to_record_batches(aggregator_map)? {
+            yield rb
+        }
 
-                accumulators
-                    .iter_mut()
-                    .zip(aggr_input.iter())
-                    .try_for_each(|(a, v)| a.accumulate(v.get_value(&i)))
-            })?;
+        if let Some(files) = spill {
+            for partition in 0..files.paths().len() {
+                let mut partition_map: AggregatorMap = HashMap::with_hasher(hasher.clone());
+                for row in files.reader(partition)? {
+                    let (key, inputs) = row?;
+                    partition_map
+                        .entry(key)
+                        .or_insert_with(|| self.create_accumulators())
+                        .iter_mut()
+                        .zip(inputs.iter())
+                        .try_for_each(|(a, v)| a.accumulate(v))?;
+                }
+                for rb in self.to_record_batches(partition_map)? {
+                    yield rb
+                }
+            }
         }
+    }
 
+    fn to_record_batches(
+        &self,
+        aggregator_map: AggregatorMap,
+    ) -> Result<Vec<RecordBatch>, ZakuError> {
         let mut columns: Vec<Vec<Value>> =
             self.schema().fields().iter().map(|_| Vec::new()).collect();
-        aggregator_map.into_iter().for_each(|(k, v)| {
+        for (k, v) in aggregator_map.into_iter() {
             let mut i = 0;
-            k.into_iter().for_each(|key| {
+            k.into_iter().skip(1).for_each(|key| {
                 columns[i].push(key);
                 i += 1;
             });
-            v.into_iter().for_each(|a| {
-                columns[i].push(a.get_value());
+            for a in v.into_iter() {
+                columns[i].push(a.get_value()?);
                 i += 1;
-            });
-        });
-        for rb in RecordBatch::to_record_batch(columns, &self.schema) {
-            yield rb
+            }
         }
+        Ok(RecordBatch::to_record_batch(
+            columns,
+            &self.schema,
+            self.config.batch_size(),
+        ))
     }
 
     fn group_expr_str(&self) -> String {
@@ -506,10 +807,363 @@ impl PhysicalPlan for HashAggregateExec {
     }
 
     fn to_string(&self) -> String {
+        let grouping_sets = if self.grouping_sets.len() > 1 {
+            format!(
+                ", grouping_sets={}",
+                grouping_sets_str(&self.group_expr, &self.grouping_sets)
+            )
+        } else {
+            "".to_string()
+        };
         format!(
-            "HashAggregate: group_expr={}, aggr_expr={}",
+            "HashAggregate: group_expr={}{}, aggr_expr={}",
             self.group_expr_str(),
+            grouping_sets,
             self.aggr_expr_str()
         )
     }
 }
+
+#[derive(Clone)]
+pub struct WindowExec {
+    schema: Schema,
+    input: Box<PhysicalPlans>,
+    window_expr: Vec<AggregateExpressions>,
+}
+
+impl WindowExec {
+    pub fn new(
+        schema: Schema,
+        input: PhysicalPlans,
+        window_expr: Vec<AggregateExpressions>,
+    ) -> WindowExec {
+        WindowExec {
+            schema,
+            input: Box::new(input),
+            window_expr,
+        }
+    }
+
+    #[try_stream(boxed, ok = RecordBatch, error = ZakuError)]
+    pub async fn execute(&self) {
+        // Materialize the input, as the window aggregates span every row
+        let mut batches = vec![];
+        let mut accumulators: Vec<Accumulators> = self
+            .window_expr
+            .iter()
+            .map(|e| e.create_accumulator())
+            .collect();
+        #[for_await]
+        for res in self.input.execute() {
+            let rb = res?;
+            for (accumulator, expr) in accumulators.iter_mut().zip(self.window_expr.iter()) {
+                let input = expr.input_expr().evaluate(&rb)?;
+                input.iter().try_for_each(|v| accumulator.accumulate(v))?;
+            }
+            batches.push(rb);
+        }
+
+        let values = accumulators
+            .iter()
+            .map(|a| a.get_value())
+            .collect::<Result<Vec<Value>, ZakuError>>()?;
+        let input_len = self.schema.fields().len() - values.len();
+        for rb in batches {
+            let mut cols = rb.columns().clone();
+            for (i, value) in values.iter().enumerate() {
+                let datatype = self.schema.get_datatype_from_index(&(input_len + i))?;
+                cols.push(Arc::new(Vectors::LiteralVector(LiteralVector::new(
+                    *datatype,
+                    value.clone(),
+                    rb.row_count(),
+                ))));
+            }
+            yield RecordBatch::new(self.schema.clone(), cols)
+        }
+    }
+}
+
+impl PhysicalPlan for WindowExec {
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<PhysicalPlans> {
+        vec![*self.input.clone()]
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "Window: window_expr={}",
+            self.window_expr
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct GenerateExec {
+    schema: Schema,
+    input: Box<PhysicalPlans>,
+    expr: PhysicalExprs,
+    delimiter: PhysicalExprs,
+    config: Arc<ExecutionConfig>,
+}
+
+impl GenerateExec {
+    pub fn new(
+        schema: Schema,
+        input: PhysicalPlans,
+        expr: PhysicalExprs,
+        delimiter: PhysicalExprs,
+        config: Arc<ExecutionConfig>,
+    ) -> GenerateExec {
+        GenerateExec {
+            schema,
+            input: Box::new(input),
+            expr,
+            delimiter,
+            config,
+        }
+    }
+
+    // null text generates no rows
+    fn split(text: &Value, delimiter: &Value) -> Result<Vec<Value>, ZakuError> {
+        match (text, delimiter) {
+            (Value::Null, _) => Ok(vec![]),
+            (Value::Text(text), Value::Text(delimiter)) if delimiter.is_empty() => {
+                Ok(vec![Value::Text(text.clone())])
+            }
+            (Value::Text(text), Value::Text(delimiter)) => Ok(text
+                .split(delimiter.as_str())
+                .map(|part| Value::Text(part.to_string()))
+                .collect()),
+            (Value::Text(_), Value::Null) => Ok(vec![Value::Null]),
+            _ => Err(ZakuError::new("SPLIT_ROWS only supports text values")),
+        }
+    }
+
+    #[try_stream(boxed, ok = RecordBatch, error = ZakuError)]
+    pub async fn execute(&self) {
+        #[for_await]
+        for res in self.input.execute() {
+            let rb = res?;
+            let texts = self.expr.evaluate(&rb)?;
+            let delimiters = self.delimiter.evaluate(&rb)?;
+            let mut cols: Vec<Vec<Value>> = self.schema.fields().iter().map(|_| vec![]).collect();
+            for i in 0..rb.row_count() {
+                let parts = GenerateExec::split(texts.get_value(&i), delimiters.get_value(&i))?;
+                for part in parts {
+                    rb.columns()
+                        .iter()
+                        .enumerate()
+                        .for_each(|(j, col)| cols[j].push(col.get_value(&i).clone()));
+                    cols[rb.column_count()].push(part);
+                }
+            }
+            for batch in RecordBatch::to_record_batch(cols, &self.schema, self.config.batch_size())
+            {
+                yield batch
+            }
+        }
+    }
+}
+
+impl PhysicalPlan for GenerateExec {
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<PhysicalPlans> {
+        vec![*self.input.clone()]
+    }
+
+    fn to_string(&self) -> String {
+        format!("Generate: split_rows({}, {})", self.expr, self.delimiter)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::Path, str::FromStr, sync::Arc};
+
+    use bigdecimal::BigDecimal;
+    use futures_async_stream::for_await;
+
+    use super::{
+        FilterExec, FilterProjectionExec, HashAggregateExec, LimitExec, PhysicalPlan,
+        PhysicalPlans, ProjectionExec,
+    };
+    use crate::{
+        config::ExecutionConfig,
+        datasources::datasource::{Datasources, MemDatasource},
+        datatypes::{
+            column_vector::Vector,
+            record_batch::RecordBatch,
+            schema::Schema,
+            types::{DataType, Value},
+        },
+        physical_plans::{
+            accumulator::AggregateExpressions, binary_expr::BooleanExpr,
+            physical_expr::PhysicalExprs,
+        },
+        sql::operators::BooleanOp,
+    };
+
+    fn number(n: i32) -> Value {
+        Value::Number(BigDecimal::from_str(&n.to_string()).unwrap())
+    }
+
+    // two batches of (id, flag, value), where flag is only read by the predicate
+    fn scan() -> PhysicalPlans {
+        scan_columns(vec![])
+    }
+
+    fn scan_columns(projection: Vec<&str>) -> PhysicalPlans {
+        let schema = Schema::from_pairs(&[
+            ("id", DataType::Number),
+            ("flag", DataType::Number),
+            ("value", DataType::Text),
+        ]);
+        let batch = |ids: Vec<i32>| {
+            let cols = vec![
+                ids.iter().map(|i| number(*i)).collect(),
+                ids.iter().map(|i| number(i % 3)).collect(),
+                ids.iter().map(|i| Value::Text(format!("v{}", i))).collect(),
+            ];
+            RecordBatch::new(schema.clone(), RecordBatch::make_arc_cols(cols, &schema))
+        };
+        let data = vec![batch(vec![1, 2, 3, 4, 5]), batch(vec![6, 7, 8, 9])];
+        let datasource = Datasources::Mem(MemDatasource::new(schema.clone(), data));
+        let projection = projection.into_iter().map(String::from).collect();
+        PhysicalPlans::Scan(super::ScanExec::new(datasource, projection))
+    }
+
+    async fn rows(plan: PhysicalPlans) -> Vec<Vec<Value>> {
+        let mut rows = vec![];
+        #[for_await]
+        for rb in plan.execute() {
+            let rb = rb.unwrap();
+            (0..rb.row_count()).for_each(|i| {
+                rows.push(rb.iter().map(|c| c.get_value(&i).clone()).collect());
+            });
+        }
+        rows
+    }
+
+    #[tokio::test]
+    async fn test_scan_projects_columns() {
+        let plan = scan_columns(vec!["value", "id"]);
+        let expected = Schema::from_pairs(&[("value", DataType::Text), ("id", DataType::Number)]);
+        assert_eq!(plan.schema(), expected);
+
+        #[for_await]
+        for rb in plan.execute() {
+            let rb = rb.unwrap();
+            assert_eq!(rb.schema(), &expected);
+            assert_eq!(rb.iter().count(), 2);
+        }
+        assert_eq!(
+            rows(scan_columns(vec!["value", "id"])).await[5],
+            vec![Value::Text("v6".to_string()), number(6)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_filter_projection_matches_unfused() {
+        let predicate = PhysicalExprs::BooleanExpr(BooleanExpr::new(
+            Box::new(PhysicalExprs::Column(1)),
+            BooleanOp::Gt,
+            Box::new(PhysicalExprs::LiteralNumber(BigDecimal::from(0))),
+        ));
+        let expr = vec![PhysicalExprs::Column(2), PhysicalExprs::Column(0)];
+        let schema = Schema::from_pairs(&[("value", DataType::Text), ("id", DataType::Number)]);
+        let input_schema = scan().schema();
+
+        for limit in [None, Some(0), Some(1), Some(5), Some(6), Some(100)] {
+            let fused = PhysicalPlans::FilterProjection(FilterProjectionExec::new(
+                schema.clone(),
+                scan(),
+                predicate.clone(),
+                expr.clone(),
+                vec![0, 2],
+                limit,
+            ));
+            let filter = FilterExec::new(input_schema.clone(), scan(), predicate.clone());
+            let mut unfused = PhysicalPlans::Projection(ProjectionExec::new(
+                schema.clone(),
+                PhysicalPlans::Filter(filter),
+                expr.clone(),
+            ));
+            if let Some(limit) = limit {
+                unfused = PhysicalPlans::Limit(LimitExec::new(schema.clone(), unfused, limit));
+            }
+            assert_eq!(rows(fused).await, rows(unfused).await, "limit {:?}", limit);
+        }
+    }
+
+    // SUM(id) grouped by flag, with one group kept in memory and the other two spilled.
+    // Returns the spill files, read when the in-memory group is emitted, and the result rows
+    async fn spilled_aggregate(dir: &Path) -> (Vec<Vec<u8>>, Vec<Vec<Value>>) {
+        fs::create_dir_all(dir).unwrap();
+        let config = ExecutionConfig::new()
+            .with_batch_size(1)
+            .with_deterministic_hash(true)
+            .with_spill_threshold(Some(1))
+            .with_spill_dir(dir.to_path_buf());
+        let plan = PhysicalPlans::HashAggregate(HashAggregateExec::new(
+            scan(),
+            vec![PhysicalExprs::Column(1)],
+            vec![vec![true]],
+            vec![AggregateExpressions::Sum(PhysicalExprs::Column(0))],
+            Schema::from_pairs(&[("flag", DataType::Number), ("sum", DataType::Number)]),
+            Arc::new(config),
+        ));
+
+        let mut spilled = vec![];
+        let mut rows = vec![];
+        #[for_await]
+        for rb in plan.execute() {
+            let rb = rb.unwrap();
+            if rows.is_empty() {
+                let mut paths: Vec<_> = fs::read_dir(dir)
+                    .unwrap()
+                    .map(|entry| entry.unwrap().path())
+                    .collect();
+                paths.sort();
+                spilled = paths.iter().map(|path| fs::read(path).unwrap()).collect();
+            }
+            (0..rb.row_count()).for_each(|i| {
+                rows.push(rb.iter().map(|c| c.get_value(&i).clone()).collect());
+            });
+        }
+        assert_eq!(fs::read_dir(dir).unwrap().count(), 0);
+        fs::remove_dir(dir).unwrap();
+        rows.sort();
+        (spilled, rows)
+    }
+
+    #[tokio::test]
+    async fn test_hash_aggregate_spills_deterministically() {
+        let dir = std::env::temp_dir().join(format!("zaku-spill-test-{}", std::process::id()));
+        let (first_spill, first_rows) = spilled_aggregate(&dir.join("first")).await;
+        let (second_spill, second_rows) = spilled_aggregate(&dir.join("second")).await;
+        fs::remove_dir(&dir).unwrap();
+
+        assert_eq!(first_spill.len(), 4);
+        assert!(first_spill.iter().any(|partition| !partition.is_empty()));
+        assert_eq!(first_spill, second_spill);
+        assert_eq!(
+            first_rows,
+            vec![
+                vec![number(0), number(18)],
+                vec![number(1), number(12)],
+                vec![number(2), number(15)],
+            ]
+        );
+        assert_eq!(first_rows, second_rows);
+    }
+}
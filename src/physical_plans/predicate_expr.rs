@@ -0,0 +1,292 @@
+use std::{fmt::Display, sync::Arc};
+
+use crate::{
+    datatypes::{
+        column_vector::{ColumnVector, Vector, Vectors},
+        record_batch::RecordBatch,
+        types::{DataType, Value},
+    },
+    error::ZakuError,
+    sql::operators::{BinaryOp, BooleanOp},
+};
+
+use super::physical_expr::{PhysicalExpr, PhysicalExprs};
+
+fn to_boolean_vector(values: Vec<Value>) -> Arc<Vectors> {
+    Arc::new(Vectors::ColumnVector(ColumnVector::new(
+        DataType::Boolean,
+        values,
+    )))
+}
+
+#[derive(Clone)]
+pub struct NotExpr {
+    expr: Box<PhysicalExprs>,
+}
+
+impl NotExpr {
+    pub fn new(expr: Box<PhysicalExprs>) -> Self {
+        Self { expr }
+    }
+}
+
+impl Display for NotExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NOT {}", self.expr)
+    }
+}
+
+impl PhysicalExpr for NotExpr {
+    fn evaluate(&self, record_batch: &RecordBatch) -> Result<Arc<Vectors>, ZakuError> {
+        let row_num = record_batch.row_count();
+        let vector = self.expr.evaluate(record_batch)?;
+        let values = (0..row_num)
+            .map(|i| vector.get_value(&i).not())
+            .collect::<Result<Vec<Value>, ZakuError>>()?;
+        Ok(to_boolean_vector(values))
+    }
+}
+
+#[derive(Clone)]
+pub struct InListExpr {
+    expr: Box<PhysicalExprs>,
+    list: Vec<PhysicalExprs>,
+}
+
+impl InListExpr {
+    pub fn new(expr: Box<PhysicalExprs>, list: Vec<PhysicalExprs>) -> Self {
+        Self { expr, list }
+    }
+
+    // x IN (a, b) behaves as x = a OR x = b
+    fn evaluate_row(&self, val: &Value, list: &[Value]) -> Result<Value, ZakuError> {
+        list.iter()
+            .try_fold(Value::Boolean(false), |acc, e| acc.or(&val.eq(e)?))
+    }
+}
+
+impl Display for InListExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let list = self
+            .list
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        write!(f, "{} IN ({})", self.expr, list)
+    }
+}
+
+impl PhysicalExpr for InListExpr {
+    fn evaluate(&self, record_batch: &RecordBatch) -> Result<Arc<Vectors>, ZakuError> {
+        let row_num = record_batch.row_count();
+        let vector = self.expr.evaluate(record_batch)?;
+        let list = self
+            .list
+            .iter()
+            .map(|e| e.evaluate(record_batch))
+            .collect::<Result<Vec<Arc<Vectors>>, ZakuError>>()?;
+        let values = (0..row_num)
+            .map(|i| {
+                let row: Vec<Value> = list.iter().map(|v| v.get_value(&i).clone()).collect();
+                self.evaluate_row(vector.get_value(&i), &row)
+            })
+            .collect::<Result<Vec<Value>, ZakuError>>()?;
+        Ok(to_boolean_vector(values))
+    }
+}
+
+// The list is a set of distinct literal values, bound once when planning
+#[derive(Clone)]
+pub struct QuantifiedExpr {
+    expr: Box<PhysicalExprs>,
+    op: BooleanOp,
+    values: Vec<Value>,
+    all: bool,
+}
+
+impl QuantifiedExpr {
+    pub fn new(expr: Box<PhysicalExprs>, op: BooleanOp, values: Vec<Value>, all: bool) -> Self {
+        Self {
+            expr,
+            op,
+            values,
+            all,
+        }
+    }
+
+    // x op ANY (a, b) behaves as x op a OR x op b, and ALL joins the comparisons with AND
+    fn evaluate_row(&self, val: &Value) -> Result<Value, ZakuError> {
+        self.values
+            .iter()
+            .try_fold(Value::Boolean(self.all), |acc, e| {
+                let cmp = self.op.evaluate(val, e)?;
+                if self.all {
+                    acc.and(&cmp)
+                } else {
+                    acc.or(&cmp)
+                }
+            })
+    }
+}
+
+impl Display for QuantifiedExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let values = self
+            .values
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        let quantifier = if self.all { "ALL" } else { "ANY" };
+        write!(
+            f,
+            "{} {} {}([{}])",
+            self.expr,
+            self.op.to_string(),
+            quantifier,
+            values
+        )
+    }
+}
+
+impl PhysicalExpr for QuantifiedExpr {
+    fn evaluate(&self, record_batch: &RecordBatch) -> Result<Arc<Vectors>, ZakuError> {
+        let vector = self.expr.evaluate(record_batch)?;
+        let values = vector
+            .iter()
+            .map(|val| self.evaluate_row(val))
+            .collect::<Result<Vec<Value>, ZakuError>>()?;
+        Ok(to_boolean_vector(values))
+    }
+}
+
+#[derive(Clone)]
+pub struct LikeExpr {
+    expr: Box<PhysicalExprs>,
+    pattern: Box<PhysicalExprs>,
+    case_insensitive: bool,
+    escape_char: Option<char>,
+}
+
+impl LikeExpr {
+    pub fn new(
+        expr: Box<PhysicalExprs>,
+        pattern: Box<PhysicalExprs>,
+        case_insensitive: bool,
+        escape_char: Option<char>,
+    ) -> Self {
+        Self {
+            expr,
+            pattern,
+            case_insensitive,
+            escape_char,
+        }
+    }
+
+    fn evaluate_row(&self, val: &Value, pattern: &Value) -> Result<Value, ZakuError> {
+        match (val, pattern) {
+            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+            (Value::Text(val), Value::Text(pattern)) => {
+                let matched = if self.case_insensitive {
+                    like_match(
+                        &val.to_lowercase(),
+                        &pattern.to_lowercase(),
+                        self.escape_char,
+                    )
+                } else {
+                    like_match(val, pattern, self.escape_char)
+                };
+                Ok(Value::Boolean(matched?))
+            }
+            _ => Err(ZakuError::new("Type mismatch")),
+        }
+    }
+}
+
+impl Display for LikeExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = if self.case_insensitive {
+            "ILIKE"
+        } else {
+            "LIKE"
+        };
+        write!(f, "{} {} {}", self.expr, op, self.pattern)
+    }
+}
+
+impl PhysicalExpr for LikeExpr {
+    fn evaluate(&self, record_batch: &RecordBatch) -> Result<Arc<Vectors>, ZakuError> {
+        let row_num = record_batch.row_count();
+        let vector = self.expr.evaluate(record_batch)?;
+        let pattern = self.pattern.evaluate(record_batch)?;
+        let values = (0..row_num)
+            .map(|i| self.evaluate_row(vector.get_value(&i), pattern.get_value(&i)))
+            .collect::<Result<Vec<Value>, ZakuError>>()?;
+        Ok(to_boolean_vector(values))
+    }
+}
+
+enum LikeToken {
+    Char(char),
+    AnyChar,
+    AnyString,
+}
+
+fn tokenize_pattern(pattern: &str, escape_char: Option<char>) -> Result<Vec<LikeToken>, ZakuError> {
+    let mut tokens = vec![];
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if Some(c) == escape_char => LikeToken::Char(chars.next().ok_or_else(|| {
+                ZakuError::new("LIKE pattern must not end with escape character")
+            })?),
+            '%' => LikeToken::AnyString,
+            '_' => LikeToken::AnyChar,
+            c => LikeToken::Char(c),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn like_match(val: &str, pattern: &str, escape_char: Option<char>) -> Result<bool, ZakuError> {
+    let tokens = tokenize_pattern(pattern, escape_char)?;
+    let chars: Vec<char> = val.chars().collect();
+
+    // matches[j] is true if the tokens seen so far match the first j chars
+    let mut matches = vec![false; chars.len() + 1];
+    matches[0] = true;
+    for token in tokens.iter() {
+        let mut next = vec![false; chars.len() + 1];
+        for j in 0..=chars.len() {
+            next[j] = match token {
+                LikeToken::AnyString => matches[j] || (j > 0 && next[j - 1]),
+                LikeToken::AnyChar => j > 0 && matches[j - 1],
+                LikeToken::Char(c) => j > 0 && matches[j - 1] && chars[j - 1] == *c,
+            };
+        }
+        matches = next;
+    }
+    Ok(matches[chars.len()])
+}
+
+#[cfg(test)]
+mod test {
+    use super::like_match;
+
+    #[test]
+    fn test_like_match() {
+        assert!(like_match("Alice", "A%", None).unwrap());
+        assert!(like_match("Alice", "%ic%", None).unwrap());
+        assert!(like_match("Alice", "_lic_", None).unwrap());
+        assert!(!like_match("Alice", "_lic", None).unwrap());
+        assert!(!like_match("Alice", "a%", None).unwrap());
+        assert!(like_match("", "%", None).unwrap());
+        assert!(like_match("50%", "50!%", Some('!')).unwrap());
+        assert!(!like_match("500", "50!%", Some('!')).unwrap());
+        assert!(like_match("a_b", "a\\_b", Some('\\')).unwrap());
+        assert!(!like_match("axb", "a\\_b", Some('\\')).unwrap());
+        assert!(like_match("a", "a!", Some('!')).is_err());
+    }
+}
@@ -0,0 +1,64 @@
+use std::{fmt::Display, sync::Arc};
+
+use crate::{
+    datatypes::{
+        column_vector::{ColumnVector, Vector, Vectors},
+        record_batch::RecordBatch,
+        types::{DataType, Value},
+    },
+    error::ZakuError,
+    sql::functions::ScalarFunction,
+};
+
+use super::physical_expr::{PhysicalExpr, PhysicalExprs};
+
+#[derive(Clone)]
+pub struct ScalarFunctionExpr {
+    func: ScalarFunction,
+    args: Vec<PhysicalExprs>,
+    datatype: DataType,
+}
+
+impl ScalarFunctionExpr {
+    pub fn new(func: ScalarFunction, args: Vec<PhysicalExprs>, datatype: DataType) -> Self {
+        Self {
+            func,
+            args,
+            datatype,
+        }
+    }
+}
+
+impl Display for ScalarFunctionExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let args = self
+            .args
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        write!(f, "{}({})", self.func.name(), args)
+    }
+}
+
+impl PhysicalExpr for ScalarFunctionExpr {
+    fn evaluate(&self, record_batch: &RecordBatch) -> Result<Arc<Vectors>, ZakuError> {
+        let row_num = record_batch.row_count();
+        let args = self
+            .args
+            .iter()
+            .map(|arg| arg.evaluate(record_batch))
+            .collect::<Result<Vec<Arc<Vectors>>, ZakuError>>()?;
+
+        let vector: Vec<Value> = (0..row_num)
+            .map(|i| {
+                let row: Vec<&Value> = args.iter().map(|arg| arg.get_value(&i)).collect();
+                self.func.evaluate(&row)
+            })
+            .collect::<Result<Vec<Value>, ZakuError>>()?;
+        Ok(Arc::new(Vectors::ColumnVector(ColumnVector::new(
+            self.datatype,
+            vector,
+        ))))
+    }
+}
@@ -0,0 +1,216 @@
+use std::{
+    fs::{self, File},
+    hash::BuildHasher,
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use bigdecimal::BigDecimal;
+
+use crate::{
+    datatypes::{
+        hash::ValueHasher,
+        types::{parse_iso_date_from_str, Value},
+    },
+    error::ZakuError,
+};
+
+pub const SPILL_PARTITIONS: usize = 4;
+
+// distinguishes the spill files of aggregations running in the same process
+static SPILL_ID: AtomicUsize = AtomicUsize::new(0);
+
+// Rows of a hash aggregation that did not fit in memory, written to disk.
+// Each row goes to the partition picked by the hash of its group key, so all rows of a group
+// share a partition and every partition can be aggregated on its own.
+// The files are removed when the spill is dropped
+pub struct SpillFiles {
+    paths: Vec<PathBuf>,
+    writers: Vec<BufWriter<File>>,
+    hasher: ValueHasher,
+}
+
+impl SpillFiles {
+    pub fn create(dir: &Path, hasher: ValueHasher) -> Result<SpillFiles, ZakuError> {
+        let id = SPILL_ID.fetch_add(1, Ordering::Relaxed);
+        let paths: Vec<PathBuf> = (0..SPILL_PARTITIONS)
+            .map(|p| dir.join(format!("zaku-spill-{}-{id}-{p}", std::process::id())))
+            .collect();
+        let mut spill = SpillFiles {
+            paths: vec![],
+            writers: vec![],
+            hasher,
+        };
+        // paths are added as their files are created, so a failure removes only those files
+        for path in paths {
+            let file = File::create(&path)?;
+            spill.paths.push(path);
+            spill.writers.push(BufWriter::new(file));
+        }
+        Ok(spill)
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    pub fn write(&mut self, key: &[Value], inputs: &[&Value]) -> Result<(), ZakuError> {
+        let partition = (self.hasher.hash_one(key) % SPILL_PARTITIONS as u64) as usize;
+        let writer = &mut self.writers[partition];
+        write_len(writer, key.len())?;
+        key.iter().try_for_each(|v| write_value(writer, v))?;
+        write_len(writer, inputs.len())?;
+        inputs.iter().try_for_each(|v| write_value(writer, v))
+    }
+
+    // flushes the written rows, so that the partitions can be read back
+    pub fn finish(&mut self) -> Result<(), ZakuError> {
+        self.writers.iter_mut().try_for_each(|w| Ok(w.flush()?))
+    }
+
+    pub fn reader(&self, partition: usize) -> Result<SpillReader, ZakuError> {
+        let file = File::open(&self.paths[partition])?;
+        Ok(SpillReader {
+            reader: BufReader::new(file),
+        })
+    }
+}
+
+impl Drop for SpillFiles {
+    fn drop(&mut self) {
+        self.paths.iter().for_each(|path| {
+            let _ = fs::remove_file(path);
+        });
+    }
+}
+
+// Reads back the (group key, aggregate inputs) rows of a spill partition in the order written
+pub struct SpillReader {
+    reader: BufReader<File>,
+}
+
+impl Iterator for SpillReader {
+    type Item = Result<(Vec<Value>, Vec<Value>), ZakuError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key_len = match read_len(&mut self.reader) {
+            Ok(len) => len,
+            Err(ZakuError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => return None,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(read_values(&mut self.reader, key_len).and_then(|key| {
+            let inputs_len = read_len(&mut self.reader)?;
+            Ok((key, read_values(&mut self.reader, inputs_len)?))
+        }))
+    }
+}
+
+fn write_len(writer: &mut impl Write, len: usize) -> Result<(), ZakuError> {
+    Ok(writer.write_all(&(len as u64).to_le_bytes())?)
+}
+
+fn write_str(writer: &mut impl Write, s: &str) -> Result<(), ZakuError> {
+    write_len(writer, s.len())?;
+    Ok(writer.write_all(s.as_bytes())?)
+}
+
+// each value is a tag byte followed by its contents, with strings and lists prefixed by their length
+fn write_value(writer: &mut impl Write, value: &Value) -> Result<(), ZakuError> {
+    match value {
+        Value::Number(n) => {
+            writer.write_all(b"N")?;
+            write_str(writer, &n.to_string())
+        }
+        Value::Text(s) => {
+            writer.write_all(b"T")?;
+            write_str(writer, s)
+        }
+        Value::Boolean(b) => Ok(writer.write_all(if *b { b"B1" } else { b"B0" })?),
+        Value::Date(d) => {
+            writer.write_all(b"D")?;
+            write_str(writer, &d.to_string())
+        }
+        Value::List(values) => {
+            writer.write_all(b"L")?;
+            write_len(writer, values.len())?;
+            values.iter().try_for_each(|v| write_value(writer, v))
+        }
+        Value::Null => Ok(writer.write_all(b"0")?),
+    }
+}
+
+fn read_len(reader: &mut impl Read) -> Result<usize, ZakuError> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf) as usize)
+}
+
+fn read_str(reader: &mut impl Read) -> Result<String, ZakuError> {
+    let mut buf = vec![0; read_len(reader)?];
+    reader.read_exact(&mut buf)?;
+    String::from_utf8(buf).map_err(|_| ZakuError::new("Spill file holds invalid text"))
+}
+
+fn read_values(reader: &mut impl Read, len: usize) -> Result<Vec<Value>, ZakuError> {
+    (0..len).map(|_| read_value(reader)).collect()
+}
+
+fn read_value(reader: &mut impl Read) -> Result<Value, ZakuError> {
+    let mut tag = [0; 1];
+    reader.read_exact(&mut tag)?;
+    match &tag {
+        b"N" => Ok(Value::Number(BigDecimal::from_str(&read_str(reader)?)?)),
+        b"T" => Ok(Value::Text(read_str(reader)?)),
+        b"B" => {
+            reader.read_exact(&mut tag)?;
+            Ok(Value::Boolean(&tag == b"1"))
+        }
+        b"D" => Ok(Value::Date(parse_iso_date_from_str(&read_str(reader)?)?)),
+        b"L" => {
+            let len = read_len(reader)?;
+            Ok(Value::List(read_values(reader, len)?))
+        }
+        b"0" => Ok(Value::Null),
+        _ => Err(ZakuError::new("Spill file holds an unknown value")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, str::FromStr};
+
+    use bigdecimal::BigDecimal;
+    use chrono::NaiveDate;
+
+    use super::SpillFiles;
+    use crate::datatypes::{hash::ValueHasher, types::Value};
+
+    #[test]
+    fn test_spill_round_trip() {
+        let key = vec![Value::Text("shampoo".to_string()), Value::Null];
+        let inputs = [
+            Value::Number(BigDecimal::from_str("-15.50").unwrap()),
+            Value::Boolean(true),
+            Value::Date(NaiveDate::from_ymd_opt(2023, 12, 1).unwrap()),
+            Value::List(vec![Value::Text("a".to_string()), Value::Null]),
+        ];
+        let mut spill = SpillFiles::create(&std::env::temp_dir(), ValueHasher::new(true)).unwrap();
+        spill
+            .write(&key, &inputs.iter().collect::<Vec<_>>())
+            .unwrap();
+        spill.write(&key, &[]).unwrap();
+        spill.finish().unwrap();
+
+        let rows = (0..spill.paths().len())
+            .flat_map(|p| spill.reader(p).unwrap())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows, vec![(key.clone(), inputs.to_vec()), (key, vec![])]);
+
+        let paths = spill.paths().to_vec();
+        drop(spill);
+        assert!(paths.iter().all(|path| fs::metadata(path).is_err()));
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{
+    datatypes::types::{DataType, Value},
+    error::ZakuError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScalarFunction {
+    Ascii,
+    Chr,
+    // TRIM([BOTH | LEADING | TRAILING] chars FROM text), with the characters as the second argument
+    Trim,
+    LTrim,
+    RTrim,
+    // DATEDIFF(unit, start, end) for day, month and year units
+    DateDiff,
+    // AGE(end, start) as years, months and days, since there is no interval type
+    Age,
+}
+
+impl ScalarFunction {
+    pub fn from_name(name: &str) -> Option<ScalarFunction> {
+        match name.to_lowercase().as_str() {
+            "ascii" => Some(ScalarFunction::Ascii),
+            "chr" => Some(ScalarFunction::Chr),
+            "btrim" => Some(ScalarFunction::Trim),
+            "ltrim" => Some(ScalarFunction::LTrim),
+            "rtrim" => Some(ScalarFunction::RTrim),
+            "datediff" => Some(ScalarFunction::DateDiff),
+            "age" => Some(ScalarFunction::Age),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            ScalarFunction::Ascii => "ascii".to_string(),
+            ScalarFunction::Chr => "chr".to_string(),
+            ScalarFunction::Trim => "trim".to_string(),
+            ScalarFunction::LTrim => "ltrim".to_string(),
+            ScalarFunction::RTrim => "rtrim".to_string(),
+            ScalarFunction::DateDiff => "datediff".to_string(),
+            ScalarFunction::Age => "age".to_string(),
+        }
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arg_types().len()
+    }
+
+    fn arg_types(&self) -> Vec<DataType> {
+        match self {
+            ScalarFunction::Ascii => vec![DataType::Text],
+            ScalarFunction::Chr => vec![DataType::Number],
+            ScalarFunction::Trim | ScalarFunction::LTrim | ScalarFunction::RTrim => {
+                vec![DataType::Text, DataType::Text]
+            }
+            ScalarFunction::DateDiff => vec![DataType::Text, DataType::Date, DataType::Date],
+            ScalarFunction::Age => vec![DataType::Date, DataType::Date],
+        }
+    }
+
+    // checks the argument types and returns the type of the result
+    pub fn return_type(&self, args: &[DataType]) -> Result<DataType, ZakuError> {
+        let expected = self.arg_types();
+        if args.len() != expected.len()
+            || args
+                .iter()
+                .zip(expected.iter())
+                .any(|(arg, expected)| !arg.is_compatible_with(expected))
+        {
+            return Err(ZakuError::new(
+                format!("Invalid argument types for {}", self.name().to_uppercase()).as_str(),
+            ));
+        }
+        match self {
+            ScalarFunction::Ascii => Ok(DataType::Number),
+            ScalarFunction::Chr => Ok(DataType::Text),
+            ScalarFunction::Trim | ScalarFunction::LTrim | ScalarFunction::RTrim => {
+                Ok(DataType::Text)
+            }
+            ScalarFunction::DateDiff => Ok(DataType::Number),
+            ScalarFunction::Age => Ok(DataType::Text),
+        }
+    }
+
+    pub fn evaluate(&self, args: &[&Value]) -> Result<Value, ZakuError> {
+        match self {
+            ScalarFunction::Ascii => args[0].ascii(),
+            ScalarFunction::Chr => args[0].chr(),
+            ScalarFunction::Trim => args[0].trim(args[1], true, true),
+            ScalarFunction::LTrim => args[0].trim(args[1], true, false),
+            ScalarFunction::RTrim => args[0].trim(args[1], false, true),
+            ScalarFunction::DateDiff => args[1].date_diff(args[0], args[2]),
+            ScalarFunction::Age => args[0].age(args[1]),
+        }
+    }
+}
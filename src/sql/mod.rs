@@ -1,3 +1,4 @@
+pub mod functions;
 pub mod operators;
 pub mod parser;
 pub mod stmt;
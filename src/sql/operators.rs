@@ -1,3 +1,7 @@
+use sqlparser::ast::BinaryOperator;
+
+use crate::{datatypes::types::Value, error::ZakuError};
+
 pub trait BinaryOp {
     fn name(&self) -> String;
 
@@ -10,12 +14,42 @@ pub enum BooleanOp {
     Or,
     Eq,
     Neq,
+    NotDistinct,
     Gt,
     Gte,
     Lt,
     Lte,
 }
 
+impl BooleanOp {
+    // the comparison operators that can be quantified with ANY and ALL
+    pub fn comparison(op: &BinaryOperator) -> Result<BooleanOp, ZakuError> {
+        match op {
+            BinaryOperator::Eq => Ok(BooleanOp::Eq),
+            BinaryOperator::NotEq => Ok(BooleanOp::Neq),
+            BinaryOperator::Gt => Ok(BooleanOp::Gt),
+            BinaryOperator::GtEq => Ok(BooleanOp::Gte),
+            BinaryOperator::Lt => Ok(BooleanOp::Lt),
+            BinaryOperator::LtEq => Ok(BooleanOp::Lte),
+            _ => Err(ZakuError::new("Expected a comparison operator")),
+        }
+    }
+
+    pub fn evaluate(&self, l: &Value, r: &Value) -> Result<Value, ZakuError> {
+        match self {
+            BooleanOp::And => l.and(r),
+            BooleanOp::Or => l.or(r),
+            BooleanOp::Eq => l.eq(r),
+            BooleanOp::Neq => l.neq(r),
+            BooleanOp::NotDistinct => l.not_distinct(r),
+            BooleanOp::Gt => l.gt(r),
+            BooleanOp::Gte => l.gte(r),
+            BooleanOp::Lt => l.lt(r),
+            BooleanOp::Lte => l.lte(r),
+        }
+    }
+}
+
 impl BinaryOp for BooleanOp {
     fn name(&self) -> String {
         match self {
@@ -23,6 +57,7 @@ impl BinaryOp for BooleanOp {
             BooleanOp::Or => "or".to_string(),
             BooleanOp::Eq => "eq".to_string(),
             BooleanOp::Neq => "neq".to_string(),
+            BooleanOp::NotDistinct => "not_distinct".to_string(),
             BooleanOp::Gt => "gt".to_string(),
             BooleanOp::Gte => "gte".to_string(),
             BooleanOp::Lt => "lt".to_string(),
@@ -36,6 +71,7 @@ impl BinaryOp for BooleanOp {
             BooleanOp::Or => "OR".to_string(),
             BooleanOp::Eq => "=".to_string(),
             BooleanOp::Neq => "<>".to_string(),
+            BooleanOp::NotDistinct => "IS NOT DISTINCT FROM".to_string(),
             BooleanOp::Gt => ">".to_string(),
             BooleanOp::Gte => ">=".to_string(),
             BooleanOp::Lt => "<".to_string(),
@@ -51,6 +87,8 @@ pub enum MathOp {
     Mul,
     Div,
     Mod,
+    Pow,
+    Concat,
 }
 
 impl BinaryOp for MathOp {
@@ -61,6 +99,8 @@ impl BinaryOp for MathOp {
             MathOp::Mul => "mul".to_string(),
             MathOp::Div => "div".to_string(),
             MathOp::Mod => "mod".to_string(),
+            MathOp::Pow => "pow".to_string(),
+            MathOp::Concat => "concat".to_string(),
         }
     }
 
@@ -71,6 +111,8 @@ impl BinaryOp for MathOp {
             MathOp::Mul => "*".to_string(),
             MathOp::Div => "/".to_string(),
             MathOp::Mod => "%".to_string(),
+            MathOp::Pow => "^".to_string(),
+            MathOp::Concat => "||".to_string(),
         }
     }
 }
@@ -1,85 +1,295 @@
-use std::{ops::Deref, str::FromStr};
+use std::{cell::Cell, collections::HashMap, ops::Deref, str::FromStr};
 
 use crate::{
-    datatypes::types::parse_iso_date_from_str,
+    catalog::Catalog,
+    datatypes::{
+        record_batch::{Collation, SortOptions},
+        schema::Schema,
+        types::{parse_iso_date_from_str, DataType},
+    },
     error::ZakuError,
     logical_plans::{
         aggregate_expr::AggregateExprs,
         binary_expr::BinaryExprs,
+        case_expr::CaseExpr,
+        cast_expr::CastExpr,
         dataframe::Dataframe,
+        generator_expr::SplitRowsExpr,
         logical_expr::{AliasExpr, Column, LogicalExprs},
+        predicate_expr::{InListExpr, LikeExpr, NotExpr, QuantifiedExpr},
+        scalar_expr::ScalarFunctionExpr,
+        subquery_expr::ScalarSubqueryExpr,
     },
+    sql::{functions::ScalarFunction, operators::BooleanOp},
 };
 use bigdecimal::BigDecimal;
 use sqlparser::{
     ast::Expr,
     ast::Select,
     ast::{
-        CopySource, CopyTarget, Function, FunctionArg, FunctionArgExpr, GroupByExpr, ObjectName,
-        OrderByExpr, Statement,
+        Array, ArrayAgg, BinaryOperator, CopyLegacyOption, CopyOption, CopySource, CopyTarget,
+        DataType as SqlDataType, ExactNumberInfo, Function, FunctionArg, FunctionArgExpr,
+        GroupByExpr, NullTreatment, ObjectName, OrderByExpr, Statement, TableFactor,
+        TableWithJoins, TrimWhereField, UnaryOperator, WindowType,
     },
     ast::{Query, SelectItem},
+    keywords::Keyword,
+    parser::ParserError,
+    tokenizer::{Token, TokenWithLocation, Tokenizer},
 };
 
 use super::stmt::{SelectStmt, Stmt};
 
-fn parse_select(query: &Query) -> Result<SelectStmt, ZakuError> {
-    let limit = match &query.limit {
-        Some(sqlparser::ast::Expr::Value(sqlparser::ast::Value::Number(num, _))) => Ok(Some(
-            num.parse::<usize>()
-                .unwrap_or_else(|_| panic!("{num} should be a number")),
-        )),
-        Some(_) => Err(ZakuError::new("Limit should be a positive number")),
-        _ => Ok(None),
+// Most tokens a single expression may have, as long chains of operators build expressions
+// deep enough to overflow the stack when they are dropped
+const MAX_EXPR_TOKENS: usize = 10_000;
+
+// Most expressions a projection may expand to once the aliases it refers to are replaced,
+// as each reference copies the aliased expression
+const MAX_ALIAS_EXPANSION: usize = 10_000;
+
+fn depth_error(max_depth: usize) -> ZakuError {
+    ZakuError::new(format!("Query is nested too deeply, the maximum depth is {max_depth}").as_str())
+}
+
+// State shared while parsing a statement, including its subqueries
+struct ParseContext<'a> {
+    catalog: &'a Catalog,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl<'a> ParseContext<'a> {
+    fn new(catalog: &'a Catalog, max_depth: usize) -> ParseContext<'a> {
+        ParseContext {
+            catalog,
+            depth: 0,
+            max_depth,
+        }
+    }
+
+    // the context for parsing one level deeper, failing past the maximum depth
+    fn nested(&self) -> Result<ParseContext<'a>, ZakuError> {
+        if self.depth >= self.max_depth {
+            return Err(depth_error(self.max_depth));
+        }
+        Ok(ParseContext {
+            catalog: self.catalog,
+            depth: self.depth + 1,
+            max_depth: self.max_depth,
+        })
+    }
+}
+
+// LIMIT and OFFSET take a literal count of rows
+fn parse_row_count(expr: &Expr, clause: &str) -> Result<usize, ZakuError> {
+    let invalid = || {
+        ZakuError::new(format!("{clause} should be a non-negative integer, got {expr}").as_str())
     };
+    match expr {
+        Expr::Value(sqlparser::ast::Value::Number(num, _)) => {
+            num.parse::<usize>().map_err(|_| invalid())
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn parse_select(query: &Query) -> Result<SelectStmt<'_>, ZakuError> {
+    let limit = query
+        .limit
+        .as_ref()
+        .map(|expr| parse_row_count(expr, "Limit"))
+        .transpose();
+
+    // sqlparser maps MySQL's LIMIT offset, count to the same offset and limit, and rejects it
+    // when combined with an explicit OFFSET
+    let offset = query
+        .offset
+        .as_ref()
+        .map(|offset| parse_row_count(&offset.value, "Offset"))
+        .transpose();
 
     let body = match &*query.body {
-        sqlparser::ast::SetExpr::Select(s) => Ok(s.clone()),
+        sqlparser::ast::SetExpr::Select(s) => Ok(s.as_ref()),
         _ => Err(ZakuError::new("Not a select query")),
     };
 
-    let order_by = query.order_by.clone();
+    Ok(SelectStmt::new(body?, limit?, offset?, &query.order_by))
+}
 
-    Ok(SelectStmt::new(body?, limit?, order_by))
+// Projections can refer to the aliases of earlier projections, as in SELECT a * 2 AS b, b + 1,
+// which are replaced by the expressions they name. Columns of the input take precedence
+// A lone * needs no projection, but alongside other expressions it expands to every input column
+// Also returns the aliases, for HAVING to refer to
+fn parse_projection(
+    select: &Select,
+    schema: &Schema,
+    ctx: &ParseContext,
+) -> Result<(Vec<LogicalExprs>, Aliases), ZakuError> {
+    let mut projections: Vec<LogicalExprs> = vec![];
+    let mut aliases = Aliases::default();
+    for item in &select.projection {
+        let expr = match item {
+            SelectItem::UnnamedExpr(expr) => parse_expr(expr, ctx)?,
+            SelectItem::ExprWithAlias { expr, alias } => {
+                LogicalExprs::AliasExpr(AliasExpr::new(parse_expr(expr, ctx)?, alias.value.clone()))
+            }
+            SelectItem::Wildcard(_) | SelectItem::QualifiedWildcard(..)
+                if select.projection.len() > 1 =>
+            {
+                projections.extend(
+                    schema
+                        .fields()
+                        .iter()
+                        .map(|field| LogicalExprs::Column(Column::new(field.name().clone()))),
+                );
+                continue;
+            }
+            _ => continue,
+        };
+        let (expr, size) = aliases.replace(&expr, schema)?;
+        if let LogicalExprs::AliasExpr(alias) = &expr {
+            aliases.add(alias, size - 1);
+        }
+        projections.push(expr);
+    }
+    Ok((projections, aliases))
 }
 
-fn parse_projection(select: &Select) -> Result<Vec<LogicalExprs>, ZakuError> {
-    let projections = select
-        .projection
-        .iter()
-        .filter(|item| {
-            matches!(
-                item,
-                SelectItem::UnnamedExpr(_) | SelectItem::ExprWithAlias { expr: _, alias: _ }
-            )
-        })
-        .map(|item| match item {
-            SelectItem::UnnamedExpr(expr) => parse_expr(expr),
-            SelectItem::ExprWithAlias { expr, alias } => parse_expr(expr)
-                .map(|e| LogicalExprs::AliasExpr(AliasExpr::new(e, alias.value.clone()))),
-            _ => panic!("Non unnamed expressions should have been filtered"),
-        })
-        .collect::<Result<Vec<LogicalExprs>, ZakuError>>()?;
+// The expressions of aliased projections, each resolved once, along with the number of
+// expressions they contain. A later alias of the same name replaces an earlier one
+#[derive(Default)]
+struct Aliases {
+    resolved: HashMap<String, (LogicalExprs, usize)>,
+}
 
-    Ok(projections)
+impl Aliases {
+    fn add(&mut self, alias: &AliasExpr, size: usize) {
+        self.resolved
+            .insert(alias.alias().clone(), (alias.expr().clone(), size));
+    }
+
+    // replaces the references to aliases, also returning the size of the result, which is
+    // checked before an alias is copied so that chains such as b + b AS c, c + c AS d
+    // cannot grow exponentially
+    fn replace(
+        &self,
+        expr: &LogicalExprs,
+        schema: &Schema,
+    ) -> Result<(LogicalExprs, usize), ZakuError> {
+        if let LogicalExprs::Column(col) = expr {
+            if let Some((aliased, size)) = self
+                .resolved
+                .get(col.name())
+                .filter(|_| schema.get_field(col.name()).is_err())
+            {
+                return Ok((aliased.clone(), *size));
+            }
+        }
+        let size = Cell::new(1);
+        let replaced = expr.map_children(|e| {
+            let (e, child_size) = self.replace(e, schema)?;
+            size.set(size.get() + child_size);
+            if size.get() > MAX_ALIAS_EXPANSION {
+                return Err(ZakuError::new(
+                    format!(
+                        "Aliases expand to more than {MAX_ALIAS_EXPANSION} expressions, \
+                         use a subquery instead"
+                    )
+                    .as_str(),
+                ));
+            }
+            Ok(e)
+        })?;
+        Ok((replaced, size.get()))
+    }
 }
 
-fn parse_group_by(expr: &GroupByExpr) -> Result<Vec<LogicalExprs>, ZakuError> {
+// GROUP BY ALL groups by every projection that does not contain an aggregate
+// Also returns whether the expressions are grouped as a CUBE
+fn parse_group_by(
+    expr: &GroupByExpr,
+    projections: &[LogicalExprs],
+    ctx: &ParseContext,
+) -> Result<(Vec<LogicalExprs>, bool), ZakuError> {
     match expr {
-        GroupByExpr::Expressions(exprs) => exprs.iter().map(parse_expr).collect(),
-        _ => Err(ZakuError::new("Unsupported group by expression")),
+        GroupByExpr::Expressions(exprs) => match exprs.as_slice() {
+            [Expr::Cube(sets)] => {
+                let exprs = sets
+                    .iter()
+                    .map(|set| match set.as_slice() {
+                        [expr] => parse_expr(expr, ctx),
+                        _ => Err(ZakuError::new(
+                            "CUBE only supports single expressions, not tuples",
+                        )),
+                    })
+                    .collect::<Result<Vec<LogicalExprs>, ZakuError>>()?;
+                Ok((exprs, true))
+            }
+            _ if exprs.iter().any(|e| matches!(e, Expr::Cube(_))) => {
+                Err(ZakuError::new("CUBE must be the only GROUP BY expression"))
+            }
+            _ => Ok((
+                exprs
+                    .iter()
+                    .map(|e| parse_expr(e, ctx))
+                    .collect::<Result<Vec<LogicalExprs>, ZakuError>>()?,
+                false,
+            )),
+        },
+        GroupByExpr::All => Ok((
+            projections
+                .iter()
+                .filter(|expr| expr.as_aggregate().is_empty())
+                .map(|expr| match expr {
+                    LogicalExprs::AliasExpr(alias) => alias.expr().clone(),
+                    _ => expr.clone(),
+                })
+                .collect(),
+            false,
+        )),
     }
 }
 
-fn parse_aggregate_function(func: &Function) -> Result<LogicalExprs, ZakuError> {
+fn arity_error(name: &str, expected: usize, got: usize) -> ZakuError {
+    let plural = if expected == 1 { "" } else { "s" };
+    ZakuError::new(
+        format!(
+            "{} expects {expected} argument{plural}, got {got}",
+            name.to_uppercase()
+        )
+        .as_str(),
+    )
+}
+
+fn check_arity(name: &str, expected: usize, got: usize) -> Result<(), ZakuError> {
+    if expected != got {
+        return Err(arity_error(name, expected, got));
+    }
+    Ok(())
+}
+
+fn parse_function(func: &Function, ctx: &ParseContext) -> Result<LogicalExprs, ZakuError> {
     let ObjectName(idents) = &func.name;
+    let name = &idents[0].value;
 
     let args = func
         .args
         .iter()
-        .map(|f| match f {
+        .enumerate()
+        .map(|(i, f)| match f {
             FunctionArg::Unnamed(expr) => match expr {
-                FunctionArgExpr::Expr(e) => parse_expr(e),
+                // the unit of DATEDIFF is usually written as a bare keyword, such as DATEDIFF(day, ...)
+                FunctionArgExpr::Expr(Expr::Identifier(unit))
+                    if i == 0 && name.to_lowercase() == "datediff" =>
+                {
+                    Ok(LogicalExprs::LiteralText(unit.value.clone()))
+                }
+                FunctionArgExpr::Expr(e) => parse_expr(e, ctx),
+                // COUNT(*) counts every row, which is the same as counting a non-null literal
+                FunctionArgExpr::Wildcard if name.to_lowercase() == "count" => {
+                    Ok(LogicalExprs::LiteralNumber(BigDecimal::from(1)))
+                }
                 _ => Err(ZakuError::new(
                     "Only column names in aggregate functions are supported",
                 )),
@@ -89,17 +299,207 @@ fn parse_aggregate_function(func: &Function) -> Result<LogicalExprs, ZakuError>
             }
         })
         .collect::<Result<Vec<LogicalExprs>, ZakuError>>()?;
-    Ok(LogicalExprs::AggregateExpr(AggregateExprs::from_str(
-        &idents[0].value,
-        args[0].clone(),
-    )?))
+
+    if func.distinct && !AggregateExprs::is_aggregate(name) {
+        return Err(ZakuError::new("DISTINCT is only supported in COUNT"));
+    }
+    if func.null_treatment.is_some() && !AggregateExprs::is_aggregate(name) {
+        return Err(ZakuError::new(
+            "IGNORE NULLS and RESPECT NULLS are only supported in FIRST_VALUE and LAST_VALUE",
+        ));
+    }
+
+    // POWER shares its implementation with the ^ operator
+    if ["power", "pow"].contains(&name.to_lowercase().as_str()) {
+        let [base, exp] =
+            <[LogicalExprs; 2]>::try_from(args).map_err(|args| arity_error(name, 2, args.len()))?;
+        return Ok(LogicalExprs::BinaryExpr(BinaryExprs::new(
+            base,
+            &BinaryOperator::PGExp,
+            exp,
+        )?));
+    }
+
+    if name.to_lowercase() == "split_rows" {
+        check_arity(name, 2, args.len())?;
+        let [expr, delimiter] = <[LogicalExprs; 2]>::try_from(args)
+            .map_err(|_| ZakuError::new("SPLIT_ROWS expects 2 arguments"))?;
+        return Ok(LogicalExprs::SplitRows(SplitRowsExpr::new(expr, delimiter)));
+    }
+
+    if let Some(scalar) = ScalarFunction::from_name(name) {
+        check_arity(name, scalar.arity(), args.len())?;
+        if func.over.is_some() {
+            return Err(ZakuError::new(
+                "OVER is only supported for aggregate functions",
+            ));
+        }
+        return Ok(LogicalExprs::ScalarFunction(ScalarFunctionExpr::new(
+            scalar, args,
+        )));
+    }
+
+    if !AggregateExprs::is_aggregate(name) {
+        return Err(ZakuError::new(format!("Unknown function: {name}").as_str()));
+    }
+    check_arity(name, 1, args.len())?;
+    let arg = args
+        .into_iter()
+        .next()
+        .ok_or_else(|| ZakuError::new("Aggregate functions require an argument"))?;
+    let mut aggregate = AggregateExprs::from_str(name, arg)?;
+    if func.distinct {
+        aggregate = aggregate.distinct()?;
+    }
+    if let Some(null_treatment) = &func.null_treatment {
+        aggregate = aggregate.ignore_nulls(null_treatment == &NullTreatment::IgnoreNulls)?;
+    }
+
+    match &func.over {
+        None => Ok(LogicalExprs::AggregateExpr(aggregate)),
+        Some(WindowType::WindowSpec(spec))
+            if spec.partition_by.is_empty()
+                && spec.order_by.is_empty()
+                && spec.window_frame.is_none() =>
+        {
+            Ok(LogicalExprs::WindowExpr(aggregate))
+        }
+        Some(_) => Err(ZakuError::new("Only empty OVER () windows are supported")),
+    }
 }
 
-fn parse_expr(expr: &Expr) -> Result<LogicalExprs, ZakuError> {
+// TRIM(text) and TRIM(text, chars) trim both ends
+// TRIM([BOTH | LEADING | TRAILING] [chars] FROM text) trims the given side, spaces by default
+fn parse_trim(
+    expr: &Expr,
+    trim_where: &Option<TrimWhereField>,
+    trim_what: &Option<Box<Expr>>,
+    trim_characters: &Option<Vec<Expr>>,
+    ctx: &ParseContext,
+) -> Result<LogicalExprs, ZakuError> {
+    let func = match trim_where {
+        None | Some(TrimWhereField::Both) => ScalarFunction::Trim,
+        Some(TrimWhereField::Leading) => ScalarFunction::LTrim,
+        Some(TrimWhereField::Trailing) => ScalarFunction::RTrim,
+    };
+    let chars = match (trim_what, trim_characters.as_deref()) {
+        (Some(what), _) => parse_expr(what, ctx)?,
+        (None, Some([chars])) => parse_expr(chars, ctx)?,
+        (None, Some(_)) => return Err(ZakuError::new("TRIM expects 2 arguments")),
+        (None, None) => LogicalExprs::LiteralText(" ".to_string()),
+    };
+    Ok(LogicalExprs::ScalarFunction(ScalarFunctionExpr::new(
+        func,
+        vec![parse_expr(expr, ctx)?, chars],
+    )))
+}
+
+fn parse_like(
+    expr: &Expr,
+    pattern: &Expr,
+    negated: bool,
+    case_insensitive: bool,
+    escape_char: Option<char>,
+    ctx: &ParseContext,
+) -> Result<LogicalExprs, ZakuError> {
+    let like = LikeExpr::new(
+        parse_expr(expr, ctx)?,
+        parse_expr(pattern, ctx)?,
+        case_insensitive,
+        escape_char,
+    );
+    Ok(LogicalExprs::negate(LogicalExprs::Like(like), negated))
+}
+
+// the list of ANY and ALL can be written as [1, 2], ARRAY[1, 2], (1, 2) or ANY(1, 2)
+fn parse_quantified(
+    left: &Expr,
+    op: &BinaryOperator,
+    right: &Expr,
+    all: bool,
+    ctx: &ParseContext,
+) -> Result<LogicalExprs, ZakuError> {
+    let list = match right {
+        Expr::Array(Array { elem, named: _ }) | Expr::Tuple(elem) => elem
+            .iter()
+            .map(|e| parse_expr(e, ctx))
+            .collect::<Result<Vec<LogicalExprs>, ZakuError>>(),
+        Expr::Nested(e) => Ok(vec![parse_expr(e, ctx)?]),
+        _ => Err(ZakuError::new("ANY and ALL only support list literals")),
+    }?;
+    Ok(LogicalExprs::Quantified(QuantifiedExpr::new(
+        parse_expr(left, ctx)?,
+        BooleanOp::comparison(op)?,
+        list,
+        all,
+    )))
+}
+
+fn parse_datatype(datatype: &SqlDataType) -> Result<DataType, ZakuError> {
+    match datatype {
+        SqlDataType::Boolean | SqlDataType::Bool => Ok(DataType::Boolean),
+        // a declared precision gives a fixed-scale decimal, with a scale of 0 if none is given
+        SqlDataType::Numeric(info) | SqlDataType::Decimal(info) | SqlDataType::Dec(info)
+            if *info != ExactNumberInfo::None =>
+        {
+            let (precision, scale) = match info {
+                ExactNumberInfo::PrecisionAndScale(precision, scale) => (*precision, *scale),
+                ExactNumberInfo::Precision(precision) => (*precision, 0),
+                ExactNumberInfo::None => unreachable!("Checked by the guard"),
+            };
+            let invalid = || ZakuError::new(format!("Unsupported datatype: {}", datatype).as_str());
+            DataType::decimal(
+                precision.try_into().map_err(|_| invalid())?,
+                scale.try_into().map_err(|_| invalid())?,
+            )
+        }
+        SqlDataType::Numeric(_)
+        | SqlDataType::Decimal(_)
+        | SqlDataType::Dec(_)
+        | SqlDataType::Int(_)
+        | SqlDataType::Integer(_)
+        | SqlDataType::SmallInt(_)
+        | SqlDataType::BigInt(_)
+        | SqlDataType::Float(_)
+        | SqlDataType::Real
+        | SqlDataType::Double
+        | SqlDataType::DoublePrecision => Ok(DataType::Number),
+        SqlDataType::Text
+        | SqlDataType::String(_)
+        | SqlDataType::Varchar(_)
+        | SqlDataType::Char(_) => Ok(DataType::Text),
+        SqlDataType::Date => Ok(DataType::Date),
+        // NUMBER is not a keyword in sqlparser
+        SqlDataType::Custom(name, _) => DataType::from_str(&name.to_string()),
+        _ => Err(ZakuError::new(
+            format!("Unsupported datatype: {}", datatype).as_str(),
+        )),
+    }
+}
+
+// ARRAY_AGG has its own syntax, so it is parsed apart from the other aggregate functions
+fn parse_array_agg(array_agg: &ArrayAgg, ctx: &ParseContext) -> Result<LogicalExprs, ZakuError> {
+    if array_agg.distinct {
+        return Err(ZakuError::new("DISTINCT is only supported in COUNT"));
+    }
+    if array_agg.order_by.is_some() || array_agg.limit.is_some() {
+        return Err(ZakuError::new(
+            "ORDER BY and LIMIT are not supported in ARRAY_AGG",
+        ));
+    }
+    let expr = parse_expr(&array_agg.expr, ctx)?;
+    Ok(LogicalExprs::AggregateExpr(AggregateExprs::ArrayAgg(
+        Box::new(expr),
+    )))
+}
+
+fn parse_expr(expr: &Expr, ctx: &ParseContext) -> Result<LogicalExprs, ZakuError> {
+    let ctx = &ctx.nested()?;
     match expr {
+        Expr::BinaryOp { .. } if has_pow(expr) => parse_pow_chain(expr, ctx),
         Expr::BinaryOp { left, op, right } => {
-            let l = parse_expr(left)?;
-            let r = parse_expr(right)?;
+            let l = parse_expr(left, ctx)?;
+            let r = parse_expr(right, ctx)?;
             Ok(LogicalExprs::BinaryExpr(BinaryExprs::new(l, op, r)?))
         }
         Expr::Identifier(ident) => Ok(LogicalExprs::Column(Column::new(ident.value.clone()))),
@@ -108,54 +508,284 @@ fn parse_expr(expr: &Expr) -> Result<LogicalExprs, ZakuError> {
             sqlparser::ast::Value::Number(n, _) => {
                 Ok(LogicalExprs::LiteralNumber(BigDecimal::from_str(n)?))
             }
-            sqlparser::ast::Value::SingleQuotedString(s) => match parse_iso_date_from_str(s) {
+            // the tokenizer has already decoded doubled quotes and the backslash escapes of E''
+            sqlparser::ast::Value::SingleQuotedString(s)
+            | sqlparser::ast::Value::EscapedStringLiteral(s) => match parse_iso_date_from_str(s) {
                 Ok(date) => Ok(LogicalExprs::LiteralDate(date)),
                 Err(_) => Ok(LogicalExprs::LiteralText(s.clone())),
             },
             _ => Err(ZakuError::new("Unsupported value")),
         },
-        Expr::Nested(expr) => parse_expr(expr),
-        Expr::Function(func) => parse_aggregate_function(func),
+        Expr::Nested(expr) => parse_expr(expr, ctx),
+        Expr::Function(func) => parse_function(func, ctx),
+        Expr::ArrayAgg(array_agg) => parse_array_agg(array_agg, ctx),
+        Expr::Case {
+            operand,
+            conditions,
+            results,
+            else_result,
+        } => {
+            let when_then = conditions
+                .iter()
+                .zip(results.iter())
+                .map(|(when, then)| Ok((parse_expr(when, ctx)?, parse_expr(then, ctx)?)))
+                .collect::<Result<Vec<(LogicalExprs, LogicalExprs)>, ZakuError>>()?;
+            Ok(LogicalExprs::Case(CaseExpr::new(
+                operand.as_ref().map(|e| parse_expr(e, ctx)).transpose()?,
+                when_then,
+                else_result
+                    .as_ref()
+                    .map(|e| parse_expr(e, ctx))
+                    .transpose()?,
+            )))
+        }
+        Expr::UnaryOp {
+            op: UnaryOperator::Not,
+            expr,
+        } => Ok(LogicalExprs::Not(NotExpr::new(parse_expr(expr, ctx)?))),
+        Expr::InList {
+            expr,
+            list,
+            negated,
+        } => {
+            let expr = parse_expr(expr, ctx)?;
+            let list = list
+                .iter()
+                .map(|e| parse_expr(e, ctx))
+                .collect::<Result<Vec<LogicalExprs>, ZakuError>>()?;
+            Ok(LogicalExprs::negate(
+                LogicalExprs::InList(InListExpr::new(expr, list)),
+                *negated,
+            ))
+        }
+        Expr::Between {
+            expr,
+            negated,
+            low,
+            high,
+        } => {
+            let expr = parse_expr(expr, ctx)?;
+            let gte = BinaryExprs::new(expr.clone(), &BinaryOperator::GtEq, parse_expr(low, ctx)?)?;
+            let lte = BinaryExprs::new(expr, &BinaryOperator::LtEq, parse_expr(high, ctx)?)?;
+            let between = BinaryExprs::new(
+                LogicalExprs::BinaryExpr(gte),
+                &BinaryOperator::And,
+                LogicalExprs::BinaryExpr(lte),
+            )?;
+            Ok(LogicalExprs::negate(
+                LogicalExprs::BinaryExpr(between),
+                *negated,
+            ))
+        }
+        Expr::Like {
+            negated,
+            expr,
+            pattern,
+            escape_char,
+        } => parse_like(expr, pattern, *negated, false, *escape_char, ctx),
+        Expr::ILike {
+            negated,
+            expr,
+            pattern,
+            escape_char,
+        } => parse_like(expr, pattern, *negated, true, *escape_char, ctx),
+        Expr::AnyOp {
+            left,
+            compare_op,
+            right,
+        } => parse_quantified(left, compare_op, right, false, ctx),
+        Expr::AllOp {
+            left,
+            compare_op,
+            right,
+        } => parse_quantified(left, compare_op, right, true, ctx),
+        Expr::Cast {
+            expr,
+            data_type,
+            format: _,
+        } => Ok(LogicalExprs::Cast(CastExpr::new(
+            parse_expr(expr, ctx)?,
+            parse_datatype(data_type)?,
+        ))),
+        Expr::IsNotDistinctFrom(l, r) => Ok(LogicalExprs::BinaryExpr(BinaryExprs::new(
+            parse_expr(l, ctx)?,
+            &BinaryOperator::Spaceship,
+            parse_expr(r, ctx)?,
+        )?)),
+        // the null-safe comparison is never null, so negating it is enough
+        Expr::IsDistinctFrom(l, r) => Ok(LogicalExprs::Not(NotExpr::new(
+            LogicalExprs::BinaryExpr(BinaryExprs::new(
+                parse_expr(l, ctx)?,
+                &BinaryOperator::Spaceship,
+                parse_expr(r, ctx)?,
+            )?),
+        ))),
+        Expr::Trim {
+            expr,
+            trim_where,
+            trim_what,
+            trim_characters,
+        } => parse_trim(expr, trim_where, trim_what, trim_characters, ctx),
+        Expr::Subquery(query) => {
+            let select = parse_select(query)?;
+            if select.body.from.is_empty() {
+                return parse_from_less_subquery(&select, ctx);
+            }
+            // deprecation warnings are only reported for the outer query
+            let subquery = create_df(&select, ctx, &mut vec![])?;
+            Ok(LogicalExprs::ScalarSubquery(ScalarSubqueryExpr::new(
+                subquery,
+            )?))
+        }
+        Expr::Exists { .. } | Expr::InSubquery { .. } => {
+            Err(ZakuError::new("Subqueries are not supported"))
+        }
         _ => Err(ZakuError::new("Unsupported expression")),
     }
 }
 
-fn parse_order_by(exprs: &[OrderByExpr]) -> Result<(Vec<LogicalExprs>, Vec<bool>), ZakuError> {
+// Precedence of the arithmetic operators, with ^ binding tighter than * and / as in Postgres
+fn arithmetic_precedence(op: &BinaryOperator) -> Option<u8> {
+    match op {
+        BinaryOperator::Plus | BinaryOperator::Minus => Some(1),
+        BinaryOperator::Multiply
+        | BinaryOperator::Divide
+        | BinaryOperator::Modulo
+        | BinaryOperator::StringConcat => Some(2),
+        BinaryOperator::PGExp | BinaryOperator::BitwiseXor => Some(3),
+        _ => None,
+    }
+}
+
+// The operands and operators of the chain of arithmetic operators at the root of expr
+fn flatten_arithmetic(expr: &Expr, operands: &mut Vec<Expr>, ops: &mut Vec<BinaryOperator>) {
+    match expr {
+        Expr::BinaryOp { left, op, right } if arithmetic_precedence(op).is_some() => {
+            flatten_arithmetic(left, operands, ops);
+            ops.push(op.clone());
+            flatten_arithmetic(right, operands, ops);
+        }
+        _ => operands.push(expr.clone()),
+    }
+}
+
+fn has_pow(expr: &Expr) -> bool {
+    let (mut operands, mut ops) = (vec![], vec![]);
+    flatten_arithmetic(expr, &mut operands, &mut ops);
+    ops.iter().any(|op| arithmetic_precedence(op) == Some(3))
+}
+
+// sqlparser gives ^ a lower precedence than + and -, so 2 * 3 ^ 2 is parsed as (2 * 3) ^ 2.
+// The chain is rebuilt by precedence climbing, keeping all operators left associative.
+// Parenthesized operands are not part of the chain, so (2 * 3) ^ 2 keeps its grouping
+fn parse_pow_chain(expr: &Expr, ctx: &ParseContext) -> Result<LogicalExprs, ZakuError> {
+    fn climb(
+        operands: &[Expr],
+        ops: &[BinaryOperator],
+        pos: &mut usize,
+        min: u8,
+        ctx: &ParseContext,
+    ) -> Result<LogicalExprs, ZakuError> {
+        let mut left = parse_expr(&operands[*pos], ctx)?;
+        while let Some(op) = ops
+            .get(*pos)
+            .filter(|op| arithmetic_precedence(op) >= Some(min))
+        {
+            let precedence = arithmetic_precedence(op).unwrap_or(min);
+            *pos += 1;
+            let right = climb(operands, ops, pos, precedence + 1, ctx)?;
+            left = LogicalExprs::BinaryExpr(BinaryExprs::new(left, op, right)?);
+        }
+        Ok(left)
+    }
+    let (mut operands, mut ops) = (vec![], vec![]);
+    flatten_arithmetic(expr, &mut operands, &mut ops);
+    climb(&operands, &ops, &mut 0, 1, ctx)
+}
+
+// COLLATE is only supported on the keys of ORDER BY, where it sets how the key is compared
+fn parse_order_by(
+    exprs: &[OrderByExpr],
+    ctx: &ParseContext,
+) -> Result<(Vec<LogicalExprs>, Vec<SortOptions>), ZakuError> {
     let mut order_by_exprs = vec![];
-    let mut asc = vec![];
-    let _ = exprs.iter().try_for_each(|expr| {
-        let logical_expr = parse_expr(&expr.expr)?;
-        order_by_exprs.push(logical_expr);
-        asc.push(expr.asc.unwrap_or(true));
+    let mut options = vec![];
+    exprs.iter().try_for_each(|expr| {
+        let mut sort_options = SortOptions::new(expr.asc.unwrap_or(true));
+        if let Some(nulls_first) = expr.nulls_first {
+            sort_options = sort_options.with_nulls_first(nulls_first);
+        }
+        let key = match &expr.expr {
+            Expr::Collate { expr, collation } => {
+                sort_options =
+                    sort_options.with_collation(Collation::from_str(&collation.to_string())?);
+                expr
+            }
+            key => key,
+        };
+        order_by_exprs.push(parse_expr(key, ctx)?);
+        options.push(sort_options);
         Ok::<(), ZakuError>(())
-    });
-    Ok((order_by_exprs, asc))
+    })?;
+    Ok((order_by_exprs, options))
 }
 
 fn retrieve_aggregate_col_idx(
-    group_by_size: usize,
+    group_by_exprs: &[LogicalExprs],
     expr: &LogicalExprs,
     aggregates: &Vec<AggregateExprs>,
-) -> LogicalExprs {
+) -> Result<LogicalExprs, ZakuError> {
+    let group_by_size = group_by_exprs.len();
+    // group keys computed from expressions are read from the aggregate output, as the
+    // columns they are computed from are not available after the aggregation
+    if let Some(idx) = group_by_exprs
+        .iter()
+        .position(|e| e == expr && !matches!(e, LogicalExprs::Column(_)))
+    {
+        return Ok(LogicalExprs::ColumnIndex(idx));
+    }
     match expr {
         LogicalExprs::AggregateExpr(expr) => {
             let idx = aggregates
                 .iter()
                 .position(|e| e == expr)
-                .expect("Aggregate expr should be found within aggregates");
-            LogicalExprs::ColumnIndex(idx + group_by_size)
-        }
-        LogicalExprs::AliasExpr(alias) => {
-            let aggr = retrieve_aggregate_col_idx(group_by_size, alias.expr(), aggregates);
-            LogicalExprs::AliasExpr(AliasExpr::new(aggr, alias.alias().clone()))
+                .ok_or(ZakuError::new("Aggregate expr not found within aggregates"))?;
+            Ok(LogicalExprs::ColumnIndex(idx + group_by_size))
         }
-        LogicalExprs::BinaryExpr(binary_expr) => {
-            let l = retrieve_aggregate_col_idx(group_by_size, binary_expr.get_l(), aggregates);
-            let r = retrieve_aggregate_col_idx(group_by_size, binary_expr.get_r(), aggregates);
-            LogicalExprs::BinaryExpr(BinaryExprs::new(l, &binary_expr.get_op(), r).unwrap())
+        _ => expr.map_children(|e| retrieve_aggregate_col_idx(group_by_exprs, e, aggregates)),
+    }
+}
+
+// Convert window functions to column indexes
+// The window plan appends its results after the columns of its input
+fn retrieve_window_col_idx(
+    input_size: usize,
+    expr: &LogicalExprs,
+    windows: &[AggregateExprs],
+) -> Result<LogicalExprs, ZakuError> {
+    match expr {
+        LogicalExprs::WindowExpr(expr) => {
+            let idx = windows
+                .iter()
+                .position(|e| e == expr)
+                .ok_or(ZakuError::new("Window expr not found within windows"))?;
+            Ok(LogicalExprs::ColumnIndex(idx + input_size))
         }
+        _ => expr.map_children(|e| retrieve_window_col_idx(input_size, e, windows)),
+    }
+}
 
-        _ => expr.clone(),
+// The generate plan appends the generated values after the columns of its input
+fn retrieve_generate_col_idx(
+    input_size: usize,
+    expr: &LogicalExprs,
+    generator: &SplitRowsExpr,
+) -> Result<LogicalExprs, ZakuError> {
+    match expr {
+        LogicalExprs::SplitRows(expr) if expr == generator => {
+            Ok(LogicalExprs::ColumnIndex(input_size))
+        }
+        _ => expr.map_children(|e| retrieve_generate_col_idx(input_size, e, generator)),
     }
 }
 
@@ -164,7 +794,7 @@ fn retrieve_aggregate_col_idx(
 // followed by the aggregate columns
 // As such, we need to offset the aggregate column indexes by the number of group by columns
 fn get_aggregate_indexes(
-    group_by_size: usize,
+    group_by_exprs: &[LogicalExprs],
     projections: Vec<LogicalExprs>,
     aggregates: &Vec<AggregateExprs>,
 ) -> Result<Vec<LogicalExprs>, ZakuError> {
@@ -173,36 +803,109 @@ fn get_aggregate_indexes(
             "Group by queries must have at least one projection",
         ));
     }
-    Ok(projections
+    projections
         .iter()
-        .map(|expr| retrieve_aggregate_col_idx(group_by_size, expr, aggregates))
-        .collect())
+        .map(|expr| retrieve_aggregate_col_idx(group_by_exprs, expr, aggregates))
+        .collect()
+}
+
+// The name of the table read in FROM, if any
+// A subquery without FROM is a single row, so (SELECT expr) is the same as expr
+fn parse_from_less_subquery(
+    select: &SelectStmt,
+    ctx: &ParseContext,
+) -> Result<LogicalExprs, ZakuError> {
+    let body = select.body;
+    let only_projection = body.selection.is_none()
+        && body.having.is_none()
+        && body.distinct.is_none()
+        && matches!(&body.group_by, GroupByExpr::Expressions(exprs) if exprs.is_empty())
+        && select.limit.is_none()
+        && select.offset.is_none()
+        && select.order_by.is_empty();
+    let expr = match body.projection.as_slice() {
+        [SelectItem::UnnamedExpr(expr)] | [SelectItem::ExprWithAlias { expr, .. }]
+            if only_projection =>
+        {
+            parse_expr(expr, ctx)?
+        }
+        _ => {
+            return Err(ZakuError::new(
+                "A subquery without FROM can only select a single expression",
+            ))
+        }
+    };
+    if !expr.as_aggregate().is_empty()
+        || !expr.as_window().is_empty()
+        || !expr.as_generator().is_empty()
+    {
+        return Err(ZakuError::new(
+            "A subquery without FROM cannot contain aggregate, window or generator functions",
+        ));
+    }
+    Ok(expr)
 }
 
-fn create_df(select: &SelectStmt, dataframe: Dataframe) -> Result<Dataframe, ZakuError> {
-    let mut df = dataframe;
+fn relation_name(from: &[TableWithJoins]) -> Option<String> {
+    match from.first().map(|table| &table.relation)? {
+        TableFactor::Table {
+            name: ObjectName(idents),
+            ..
+        } => idents.last().map(|ident| ident.value.clone()),
+        _ => None,
+    }
+}
+
+fn create_df(
+    select: &SelectStmt,
+    ctx: &ParseContext,
+    warnings: &mut Vec<String>,
+) -> Result<Dataframe, ZakuError> {
+    let (mut df, warning) = ctx
+        .catalog
+        .resolve(relation_name(&select.body.from).as_deref())?;
+    warnings.extend(warning);
 
     // parse where clause
-    if let Some(selection) = select.body.selection.as_ref().map(parse_expr) {
-        if !selection?.as_aggregate().is_empty() {
+    if let Some(selection) = select.body.selection.as_ref().map(|e| parse_expr(e, ctx)) {
+        let selection = selection?;
+        if !selection.as_aggregate().is_empty() {
             return Err(ZakuError::new(
                 "WHERE clause cannot contain aggregate functions",
             ));
         }
+        if !selection.as_window().is_empty() {
+            return Err(ZakuError::new(
+                "WHERE clause cannot contain window functions",
+            ));
+        }
+        if !selection.as_generator().is_empty() {
+            return Err(ZakuError::new("WHERE clause cannot contain SPLIT_ROWS"));
+        }
     }
-    let selection = select.body.selection.as_ref().map(parse_expr);
+    let selection = select.body.selection.as_ref().map(|e| parse_expr(e, ctx));
     if let Some(selection) = selection {
         df = df.filter(selection?)?;
     }
 
     // handle GROUP BY + HAVING and aggregates
-    let projections = parse_projection(&select.body)?;
+    let (projections, aliases) = parse_projection(select.body, &df.schema(), ctx)?;
     let mut aggregates: Vec<AggregateExprs> = projections
         .iter()
         .flat_map(|expr| expr.as_aggregate())
         .collect();
-    if let Some(have) = select.body.having.as_ref().map(parse_expr) {
-        let have_aggregates: Vec<AggregateExprs> = have?
+    // HAVING can refer to the aliases of projections, with input columns taking precedence
+    let having = select
+        .body
+        .having
+        .as_ref()
+        .map(|e| {
+            let (having, _) = aliases.replace(&parse_expr(e, ctx)?, &df.schema())?;
+            Ok::<LogicalExprs, ZakuError>(having)
+        })
+        .transpose()?;
+    if let Some(have) = &having {
+        let have_aggregates: Vec<AggregateExprs> = have
             .as_aggregate()
             .iter()
             .filter(|expr| !aggregates.contains(expr))
@@ -211,42 +914,135 @@ fn create_df(select: &SelectStmt, dataframe: Dataframe) -> Result<Dataframe, Zak
         aggregates.extend(have_aggregates);
     }
 
-    let group_by_exprs = parse_group_by(&select.body.group_by)?;
+    let (group_by_exprs, is_cube) = parse_group_by(&select.body.group_by, &projections, ctx)?;
+    let (order_by_exprs, sort_options) = parse_order_by(select.order_by, ctx)?;
+
+    let mut windows: Vec<AggregateExprs> = vec![];
+    projections
+        .iter()
+        .chain(order_by_exprs.iter())
+        .flat_map(|expr| expr.as_window())
+        .for_each(|expr| {
+            if !windows.contains(&expr) {
+                windows.push(expr);
+            }
+        });
+
+    let mut generators: Vec<SplitRowsExpr> = vec![];
+    projections
+        .iter()
+        .chain(order_by_exprs.iter())
+        .flat_map(|expr| expr.as_generator())
+        .for_each(|expr| {
+            if !generators.contains(&expr) {
+                generators.push(expr);
+            }
+        });
+    if generators.len() > 1 {
+        return Err(ZakuError::new("Only one SPLIT_ROWS is supported per query"));
+    }
 
     // no group by clause and no aggregate functions in SELECT
     if group_by_exprs.is_empty() && aggregates.is_empty() {
-        if !select.order_by.is_empty() {
-            let (order_by_exprs, asc) = parse_order_by(&select.order_by)?;
-            df = df.sort(order_by_exprs, asc)?;
+        let mut projections = projections;
+        let mut order_by_exprs = order_by_exprs;
+        if let Some(generator) = generators.first() {
+            if !windows.is_empty() {
+                return Err(ZakuError::new(
+                    "SPLIT_ROWS cannot be combined with window functions",
+                ));
+            }
+            let input_size = df.schema().fields().len();
+            df = df.generate(generator.clone())?;
+            projections = projections
+                .iter()
+                .map(|expr| retrieve_generate_col_idx(input_size, expr, generator))
+                .collect::<Result<Vec<LogicalExprs>, ZakuError>>()?;
+            order_by_exprs = order_by_exprs
+                .iter()
+                .map(|expr| retrieve_generate_col_idx(input_size, expr, generator))
+                .collect::<Result<Vec<LogicalExprs>, ZakuError>>()?;
+        }
+        if !windows.is_empty() {
+            let input_size = df.schema().fields().len();
+            df = df.window(windows.clone())?;
+            projections = projections
+                .iter()
+                .map(|expr| retrieve_window_col_idx(input_size, expr, &windows))
+                .collect::<Result<Vec<LogicalExprs>, ZakuError>>()?;
+            order_by_exprs = order_by_exprs
+                .iter()
+                .map(|expr| retrieve_window_col_idx(input_size, expr, &windows))
+                .collect::<Result<Vec<LogicalExprs>, ZakuError>>()?;
+        }
+
+        if !order_by_exprs.is_empty() {
+            df = df.sort_with_options(order_by_exprs, sort_options)?;
         }
 
         if !projections.is_empty() {
             df = df.projection(projections)?;
         }
 
+        if let Some(offset) = select.offset {
+            df = df.offset(offset)?;
+        }
+
         if let Some(limit) = select.limit {
             df = df.limit(limit)?;
         }
         return Ok(df);
     }
 
-    let group_by_size = group_by_exprs.len();
-    let aggr_projections = get_aggregate_indexes(group_by_size, projections, &aggregates)?;
-    df = df.aggregate(group_by_exprs, aggregates.clone())?;
+    if !windows.is_empty() {
+        return Err(ZakuError::new(
+            "Window functions cannot be combined with aggregates or GROUP BY",
+        ));
+    }
+
+    if !generators.is_empty() {
+        return Err(ZakuError::new(
+            "SPLIT_ROWS cannot be combined with aggregates or GROUP BY",
+        ));
+    }
+
+    // aggregates only referenced in ORDER BY are computed too, then dropped by the projection
+    order_by_exprs
+        .iter()
+        .flat_map(|expr| expr.as_aggregate())
+        .for_each(|expr| {
+            if !aggregates.contains(&expr) {
+                aggregates.push(expr);
+            }
+        });
+
+    let aggr_projections = get_aggregate_indexes(&group_by_exprs, projections, &aggregates)?;
+    let order_by_exprs = order_by_exprs
+        .iter()
+        .map(|expr| retrieve_aggregate_col_idx(&group_by_exprs, expr, &aggregates))
+        .collect::<Result<Vec<LogicalExprs>, ZakuError>>()?;
+    df = if is_cube {
+        df.cube(group_by_exprs.clone(), aggregates.clone())?
+    } else {
+        df.aggregate(group_by_exprs.clone(), aggregates.clone())?
+    };
 
-    let having = select.body.having.as_ref().map(parse_expr);
     if let Some(have) = having {
-        let aggr_havings = retrieve_aggregate_col_idx(group_by_size, &have?, &aggregates);
+        let aggr_havings = retrieve_aggregate_col_idx(&group_by_exprs, &have, &aggregates)?;
         df = df.filter(aggr_havings)?;
     }
 
-    if !select.order_by.is_empty() {
-        let (order_by_exprs, asc) = parse_order_by(&select.order_by)?;
-        df = df.sort(order_by_exprs, asc)?;
+    if !order_by_exprs.is_empty() {
+        df = df.sort_with_options(order_by_exprs, sort_options)?;
     }
 
+    // also drops the columns of aggregates only referenced in HAVING
     df = df.projection(aggr_projections)?;
 
+    if let Some(offset) = select.offset {
+        df = df.offset(offset)?;
+    }
+
     if let Some(limit) = select.limit {
         df = df.limit(limit)?;
     }
@@ -255,10 +1051,13 @@ fn create_df(select: &SelectStmt, dataframe: Dataframe) -> Result<Dataframe, Zak
 }
 
 fn parse_copy(
-    df: Dataframe,
+    ctx: &ParseContext,
     to: &bool,
     source: &CopySource,
     target: &CopyTarget,
+    options: &[CopyOption],
+    legacy_options: &[CopyLegacyOption],
+    warnings: &mut Vec<String>,
 ) -> Result<Stmt, ZakuError> {
     if to == &false {
         return Err(ZakuError::new("COPY FROM is not supported"));
@@ -272,19 +1071,247 @@ fn parse_copy(
     let df = match source {
         CopySource::Query(query) => {
             let select_stmt = parse_select(query)?;
-            create_df(&select_stmt, df)
+            create_df(&select_stmt, ctx, warnings)
         }
         _ => Err(ZakuError::new("COPY is only supported from SELECT queries")),
     };
 
-    Ok(Stmt::CopyTo(df?, filename?.to_string()))
+    let delimiter = options
+        .iter()
+        .filter_map(|o| match o {
+            CopyOption::Delimiter(c) => Some(c),
+            _ => None,
+        })
+        .chain(legacy_options.iter().filter_map(|o| match o {
+            CopyLegacyOption::Delimiter(c) => Some(c),
+            _ => None,
+        }))
+        .last()
+        .map(|c| {
+            // u8::try_from would also accept the latin-1 characters, which take two bytes in utf-8
+            c.is_ascii()
+                .then_some(*c as u8)
+                .ok_or_else(|| ZakuError::new("COPY delimiter must be a single ASCII character"))
+        })
+        .transpose()?;
+
+    Ok(Stmt::CopyTo(df?, filename?.to_string(), delimiter))
+}
+
+// CREATE TABLE name AS SELECT ..., which fails if the table exists unless OR REPLACE is given
+fn parse_create_table(
+    ctx: &ParseContext,
+    name: &ObjectName,
+    query: &Option<Box<Query>>,
+    or_replace: bool,
+    warnings: &mut Vec<String>,
+) -> Result<Stmt, ZakuError> {
+    let query = query
+        .as_ref()
+        .ok_or_else(|| ZakuError::new("Only CREATE TABLE AS SELECT is supported"))?;
+    let ObjectName(idents) = name;
+    let name = idents
+        .last()
+        .map(|ident| ident.value.clone())
+        .ok_or_else(|| ZakuError::new("CREATE TABLE requires a table name"))?;
+    if !or_replace && ctx.catalog.get(&name).is_some() {
+        return Err(ZakuError::new(
+            format!("Table '{name}' already exists").as_str(),
+        ));
+    }
+    let df = create_df(&parse_select(query)?, ctx, warnings)?;
+    Ok(Stmt::CreateTable(name, df))
 }
 
-pub fn parse(sql: &str, df: Dataframe) -> Result<Stmt, ZakuError> {
+// sqlparser recurses for each prefix operator such as NOT or -, so a long run of them overflows
+// the stack before the depth of the parsed query can be checked. The tokens are checked first:
+// parentheses and prefix operators count towards the depth of the expression they are in,
+// which ends at a comma or a clause keyword
+fn check_token_depth(tokens: &[TokenWithLocation], max_depth: usize) -> Result<(), ZakuError> {
+    // the depth at each open parenthesis, with the prefix operators and tokens read since the
+    // start of the current expression
+    let mut frames = vec![(0, 0, 0)];
+    let mut prev: Option<&Token> = None;
+    for token in tokens
+        .iter()
+        .map(|t| &t.token)
+        .filter(|t| !matches!(t, Token::Whitespace(_)))
+    {
+        let nested = frames.len() > 1;
+        let (base, prefixes, len) = frames.last_mut().expect("The outer frame is never closed");
+        *len += 1;
+        if *len > MAX_EXPR_TOKENS {
+            return Err(ZakuError::new(
+                format!("Expression is too long, the maximum is {MAX_EXPR_TOKENS} tokens").as_str(),
+            ));
+        }
+        let is_prefix = match token {
+            Token::Word(w) => w.keyword == Keyword::NOT,
+            Token::Minus
+            | Token::Plus
+            | Token::Tilde
+            | Token::AtSign
+            | Token::PGSquareRoot
+            | Token::PGCubeRoot => !prev.is_some_and(ends_operand),
+            _ => false,
+        };
+        match token {
+            _ if is_prefix => {
+                *prefixes += 1;
+                if *base + *prefixes > max_depth {
+                    return Err(depth_error(max_depth));
+                }
+            }
+            Token::LParen | Token::LBracket => {
+                let depth = *base + *prefixes + 1;
+                if depth > max_depth {
+                    return Err(depth_error(max_depth));
+                }
+                frames.push((depth, 0, 0));
+            }
+            Token::RParen | Token::RBracket if nested => {
+                frames.pop();
+            }
+            Token::Comma | Token::SemiColon => (*prefixes, *len) = (0, 0),
+            Token::Word(w)
+                if matches!(
+                    w.keyword,
+                    Keyword::SELECT
+                        | Keyword::FROM
+                        | Keyword::WHERE
+                        | Keyword::HAVING
+                        | Keyword::ON
+                        | Keyword::BY
+                        | Keyword::WHEN
+                        | Keyword::THEN
+                        | Keyword::ELSE
+                        | Keyword::END
+                        | Keyword::LIMIT
+                        | Keyword::OFFSET
+                ) =>
+            {
+                (*prefixes, *len) = (0, 0)
+            }
+            _ => {}
+        }
+        prev = Some(token);
+    }
+    Ok(())
+}
+
+// whether an operator after the token is binary, as in a - b, rather than a prefix, as in = -b
+fn ends_operand(token: &Token) -> bool {
+    match token {
+        Token::Word(w) => !matches!(
+            w.keyword,
+            Keyword::NOT
+                | Keyword::AND
+                | Keyword::OR
+                | Keyword::IS
+                | Keyword::IN
+                | Keyword::LIKE
+                | Keyword::ILIKE
+                | Keyword::BETWEEN
+                | Keyword::SELECT
+                | Keyword::WHERE
+                | Keyword::HAVING
+                | Keyword::ON
+                | Keyword::WHEN
+                | Keyword::THEN
+                | Keyword::ELSE
+        ),
+        Token::Number(..)
+        | Token::SingleQuotedString(_)
+        | Token::DoubleQuotedString(_)
+        | Token::RParen
+        | Token::RBracket => true,
+        _ => false,
+    }
+}
+
+// sqlparser reads a single expression inside ANY( and ALL(, so the list of x = ANY(1, 2) is
+// wrapped in another pair of parentheses, to be parsed as the tuple of x = ANY((1, 2))
+fn wrap_quantified_lists(tokens: Vec<TokenWithLocation>) -> Vec<TokenWithLocation> {
+    let code: Vec<usize> = (0..tokens.len())
+        .filter(|i| !matches!(tokens[*i].token, Token::Whitespace(_)))
+        .collect();
+    let mut wrapped = vec![];
+    for k in 1..code.len().saturating_sub(1) {
+        let is_comparison = matches!(
+            tokens[code[k - 1]].token,
+            Token::Eq | Token::Neq | Token::Lt | Token::Gt | Token::LtEq | Token::GtEq
+        );
+        let is_quantifier = matches!(
+            &tokens[code[k]].token,
+            Token::Word(w) if matches!(w.keyword, Keyword::ANY | Keyword::ALL)
+        );
+        if !is_comparison || !is_quantifier || tokens[code[k + 1]].token != Token::LParen {
+            continue;
+        }
+        let mut depth = 0;
+        let mut has_list = false;
+        for &i in &code[k + 1..] {
+            match tokens[i].token {
+                Token::LParen | Token::LBracket => depth += 1,
+                Token::RParen if depth == 1 => {
+                    if has_list {
+                        wrapped.push((code[k + 1], i));
+                    }
+                    break;
+                }
+                Token::RParen | Token::RBracket => depth -= 1,
+                Token::Comma if depth == 1 => has_list = true,
+                _ => {}
+            }
+        }
+    }
+
+    let mut result = Vec::with_capacity(tokens.len() + 2 * wrapped.len());
+    for (i, token) in tokens.into_iter().enumerate() {
+        let location = token.location;
+        if wrapped.iter().any(|(_, close)| *close == i) {
+            result.push(TokenWithLocation::new(
+                Token::RParen,
+                location.line,
+                location.column,
+            ));
+        }
+        result.push(token);
+        if wrapped.iter().any(|(open, _)| *open == i) {
+            result.push(TokenWithLocation::new(
+                Token::LParen,
+                location.line,
+                location.column,
+            ));
+        }
+    }
+    result
+}
+
+// Returns the statement along with any warnings about the query
+// Expressions nested deeper than max_depth are rejected, rather than overflowing the stack
+pub fn parse(
+    sql: &str,
+    catalog: &Catalog,
+    max_depth: usize,
+) -> Result<(Stmt, Vec<String>), ZakuError> {
     let dialect = sqlparser::dialect::GenericDialect {};
-    let ast = sqlparser::parser::Parser::parse_sql(&dialect, sql)?;
+    let tokens = Tokenizer::new(&dialect, sql)
+        .tokenize_with_location()
+        .map_err(ParserError::from)?;
+    check_token_depth(&tokens, max_depth)?;
+    let ast = sqlparser::parser::Parser::new(&dialect)
+        .with_recursion_limit(max_depth)
+        .with_tokens_with_locations(wrap_quantified_lists(tokens))
+        .parse_statements()?;
+    let ctx = &ParseContext::new(catalog, max_depth);
+
+    let statement = ast
+        .first()
+        .ok_or(ZakuError::new("No SQL statement provided"))?;
 
-    match &ast[0] {
+    let mut warnings = vec![];
+    let stmt = match statement {
         Statement::Explain {
             describe_alias: _,
             analyze: _,
@@ -294,7 +1321,7 @@ pub fn parse(sql: &str, df: Dataframe) -> Result<Stmt, ZakuError> {
         } => match statement.deref() {
             Statement::Query(query) => {
                 let select_stmt = parse_select(query)?;
-                let df = create_df(&select_stmt, df)?;
+                let df = create_df(&select_stmt, ctx, &mut warnings)?;
                 Ok(Stmt::Explain(df))
             }
             _ => Err(ZakuError::new("Only SELECT queries are supported")),
@@ -303,15 +1330,32 @@ pub fn parse(sql: &str, df: Dataframe) -> Result<Stmt, ZakuError> {
             source,
             to,
             target,
-            options: _,
-            legacy_options: _,
+            options,
+            legacy_options,
             values: _,
-        } => parse_copy(df, to, source, target),
+        } => parse_copy(
+            ctx,
+            to,
+            source,
+            target,
+            options,
+            legacy_options,
+            &mut warnings,
+        ),
+        Statement::CreateTable {
+            name,
+            query,
+            or_replace,
+            ..
+        } => parse_create_table(ctx, name, query, *or_replace, &mut warnings),
         Statement::Query(query) => {
             let select_stmt = parse_select(query)?;
-            let df = create_df(&select_stmt, df)?;
+            let df = create_df(&select_stmt, ctx, &mut warnings)?;
             Ok(Stmt::Select(df))
         }
-        _ => Err(ZakuError::new("Only SELECT and EXPLAIN are supported")),
-    }
+        _ => Err(ZakuError::new(
+            "Only SELECT, EXPLAIN, COPY and CREATE TABLE AS are supported",
+        )),
+    }?;
+    Ok((stmt, warnings))
 }
@@ -5,20 +5,42 @@ use crate::logical_plans::dataframe::Dataframe;
 pub enum Stmt {
     Select(Dataframe),
     Explain(Dataframe),
-    CopyTo(Dataframe, String),
+    // the query, the file to write and the delimiter of its fields, if one is given
+    CopyTo(Dataframe, String, Option<u8>),
+    CreateTable(String, Dataframe),
 }
 
-pub struct SelectStmt {
-    pub body: Box<Select>,
+impl Stmt {
+    pub fn dataframe(&self) -> &Dataframe {
+        match self {
+            Stmt::Select(df)
+            | Stmt::Explain(df)
+            | Stmt::CopyTo(df, _, _)
+            | Stmt::CreateTable(_, df) => df,
+        }
+    }
+}
+
+// Borrows from the parsed query rather than cloning it, as deeply nested expressions
+// would overflow the stack while being cloned before their depth can be checked
+pub struct SelectStmt<'a> {
+    pub body: &'a Select,
     pub limit: Option<usize>,
-    pub order_by: Vec<OrderByExpr>,
+    pub offset: Option<usize>,
+    pub order_by: &'a [OrderByExpr],
 }
 
-impl SelectStmt {
-    pub fn new(body: Box<Select>, limit: Option<usize>, order_by: Vec<OrderByExpr>) -> Self {
+impl<'a> SelectStmt<'a> {
+    pub fn new(
+        body: &'a Select,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        order_by: &'a [OrderByExpr],
+    ) -> Self {
         Self {
             body,
             limit,
+            offset,
             order_by,
         }
     }
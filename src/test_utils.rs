@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{str::FromStr, sync::Arc};
 
 use crate::datatypes::{
     column_vector::{ColumnVector, Vectors},
@@ -23,16 +23,6 @@ impl ContainerDataBuilder {
         ContainerDataBuilder { schema, data }
     }
 
-    fn get_datatype_from_str(str_val: &str) -> DataType {
-        match str_val {
-            "num" => DataType::Number,
-            "text" => DataType::Text,
-            "bool" => DataType::Boolean,
-            "date" => DataType::Date,
-            _ => panic!("Unsupported datatype"),
-        }
-    }
-
     pub fn add_schema(
         mut self,
         col_names: Vec<&str>,
@@ -40,7 +30,7 @@ impl ContainerDataBuilder {
     ) -> ContainerDataBuilder {
         let datatypes = datatypes
             .iter()
-            .map(|d| ContainerDataBuilder::get_datatype_from_str(d))
+            .map(|d| DataType::from_str(d).expect("Unsupported datatype"))
             .collect::<Vec<DataType>>();
         let fields = col_names
             .iter()
@@ -71,7 +61,8 @@ impl ContainerDataBuilder {
         data.iter().for_each(|row| {
             row.iter().enumerate().for_each(|(i, str_val)| {
                 let datatype = datatypes[i];
-                let val = Value::get_value_from_string_val(str_val, datatype);
+                let val = Value::get_value_from_string_val(str_val, datatype)
+                    .expect("Test data should match the schema");
                 cols[i].push(val);
             })
         });
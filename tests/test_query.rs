@@ -1,15 +1,29 @@
-use std::path::Path;
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 use zaku::{
-    execute,
+    execute, execute_dataframe, execute_in_catalog, execute_with_config,
     test_utils::{ContainerData, ContainerDataBuilder},
-    Dataframe, ZakuError,
+    Catalog, CsvOptions, DataType, Dataframe, ExecutionConfig, LogicalPlans, OptimizerRule,
+    OutputSummary, Schema, ZakuError,
 };
 
-async fn run(sql: &str) -> Result<ContainerData, ZakuError> {
-    let binding = Path::new("resources").join("test.csv");
-    let path = binding.to_str().expect("test.csv file should exist");
-    let df = Dataframe::from_csv(path, None)?;
+fn load_df(filename: &str) -> Result<Dataframe, ZakuError> {
+    let binding = Path::new("resources").join(filename);
+    let path = binding.to_str().expect("test file should exist");
+    Dataframe::from_csv(path, None)
+}
+
+fn test_df() -> Result<Dataframe, ZakuError> {
+    load_df("test.csv")
+}
+
+async fn run_on(sql: &str, df: Dataframe) -> Result<ContainerData, ZakuError> {
     let res = execute(sql, df).await?;
     Ok(ContainerData {
         schema: res.schema().clone(),
@@ -17,6 +31,36 @@ async fn run(sql: &str) -> Result<ContainerData, ZakuError> {
     })
 }
 
+async fn run(sql: &str) -> Result<ContainerData, ZakuError> {
+    run_on(sql, test_df()?).await
+}
+
+// test_nulls.csv has nulls in the name, flag and score columns
+async fn run_nulls(sql: &str) -> Result<ContainerData, ZakuError> {
+    run_on(sql, load_df("test_nulls.csv")?).await
+}
+
+// a single id column with the given ids
+fn id_result(ids: Vec<&str>) -> ContainerData {
+    ContainerDataBuilder::default()
+        .add_schema(vec!["id"], vec!["num"])
+        .add_data(ids.into_iter().map(|id| vec![id]).collect())
+        .build()
+}
+
+fn negated_result(values: Vec<&str>) -> ContainerData {
+    ContainerDataBuilder::default()
+        .add_schema(vec!["id", "res"], vec!["num", "bool"])
+        .add_data(
+            values
+                .iter()
+                .enumerate()
+                .map(|(i, v)| vec![["1", "2", "3", "4"][i], v])
+                .collect(),
+        )
+        .build()
+}
+
 #[tokio::test]
 async fn basic_query() {
     let sql = "SELECT * FROM test";
@@ -43,6 +87,50 @@ async fn basic_query() {
     assert_eq!(run(sql).await.unwrap(), expected);
 }
 
+#[tokio::test]
+async fn bom_csv_query() {
+    let sql = "SELECT id, name FROM test";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id", "name"], vec!["num", "text"])
+        .add_data(vec![vec!["1", "a"]])
+        .build();
+    let res = run_on(sql, load_df("test_bom.csv").unwrap()).await.unwrap();
+    assert_eq!(res, expected);
+}
+
+#[tokio::test]
+async fn group_by_all_query() {
+    let explicit = "SELECT is_available AS available, COUNT(id), SUM(price) FROM test GROUP BY is_available ORDER BY is_available";
+    let all = "SELECT is_available AS available, COUNT(id), SUM(price) FROM test GROUP BY ALL ORDER BY is_available";
+    assert_eq!(run(all).await.unwrap(), run(explicit).await.unwrap());
+}
+
+#[tokio::test]
+async fn union_by_name_query() {
+    let paths = ["test_union_a.csv", "test_union_b.csv"].map(|name| {
+        Path::new("resources")
+            .join(name)
+            .to_str()
+            .unwrap()
+            .to_string()
+    });
+    let df = Dataframe::from_csv_files(&[&paths[0], &paths[1]], &CsvOptions::default()).unwrap();
+    let sql = "SELECT id, name, price, category FROM test";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(
+            vec!["id", "name", "price", "category"],
+            vec!["num", "text", "num", "text"],
+        )
+        .add_data(vec![
+            vec!["1", "apple", "1.50", ""],
+            vec!["2", "banana", "0.25", ""],
+            vec!["3", "", "2.00", "fruit"],
+            vec!["4", "", "3.10", "veg"],
+        ])
+        .build();
+    assert_eq!(run_on(sql, df).await.unwrap(), expected);
+}
+
 #[tokio::test]
 async fn projection_query() {
     let sql = "SELECT id, product_name FROM test";
@@ -83,6 +171,16 @@ async fn filter_query() {
     assert_eq!(run(sql).await.unwrap(), expected);
 }
 
+#[tokio::test]
+async fn numeric_scale_equality_query() {
+    let sql = "SELECT id, price FROM test WHERE price = 10 OR price = 20.0";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id", "price"], vec!["num", "num"])
+        .add_data(vec![vec!["2", "10.00"], vec!["5", "20.00"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+}
+
 #[tokio::test]
 async fn filter_no_records_query() {
     let sql = "SELECT * FROM test WHERE price < 0";
@@ -144,6 +242,332 @@ async fn limit_zero_query() {
     assert_eq!(run(sql).await.unwrap(), expected);
 }
 
+#[tokio::test]
+async fn offset_query() {
+    let sql = "SELECT id FROM test ORDER BY id DESC LIMIT 2 OFFSET 1";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id"], vec!["num"])
+        .add_data(vec![vec!["4"], vec!["3"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn mysql_limit_query() {
+    // LIMIT offset, count
+    let sql = "SELECT id FROM test LIMIT 1, 2";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id"], vec!["num"])
+        .add_data(vec![vec!["2"], vec!["3"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    assert!(run("SELECT id FROM test LIMIT 1, 2 OFFSET 3")
+        .await
+        .is_err());
+    assert!(run("SELECT id FROM test LIMIT 1 OFFSET 3, 2")
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn invalid_limit_query() {
+    let cases = [
+        (
+            "SELECT id FROM test LIMIT -1",
+            "Limit should be a non-negative integer, got -1",
+        ),
+        (
+            "SELECT id FROM test LIMIT 1.5",
+            "Limit should be a non-negative integer, got 1.5",
+        ),
+        (
+            "SELECT id FROM test LIMIT 1 OFFSET id",
+            "Offset should be a non-negative integer, got id",
+        ),
+    ];
+    for (sql, msg) in cases {
+        let err = run(sql).await.unwrap_err();
+        assert_eq!(err.to_string(), format!("ZakuError: {msg}"), "{sql}");
+    }
+}
+
+#[tokio::test]
+async fn page_query() {
+    // successive pages cover every row exactly once
+    let pages = [
+        vec![vec!["1"], vec!["2"]],
+        vec![vec!["3"], vec!["4"]],
+        vec![vec!["5"]],
+    ];
+    for (page, rows) in pages.into_iter().enumerate() {
+        let df = test_df().unwrap().page(page, 2).unwrap();
+        let expected = ContainerDataBuilder::default()
+            .add_schema(vec!["id"], vec!["num"])
+            .add_data(rows)
+            .build();
+        assert_eq!(run_on("SELECT id FROM test", df).await.unwrap(), expected);
+    }
+
+    let df = test_df().unwrap().page(3, 2).unwrap();
+    let res = run_on("SELECT id FROM test", df).await.unwrap();
+    assert_eq!(res.data.iter().map(|rb| rb.row_count()).sum::<usize>(), 0);
+
+    assert!(test_df().unwrap().page(0, 0).is_err());
+}
+
+#[tokio::test]
+async fn value_counts_query() {
+    // most frequent first, with ties ordered by value
+    let df = test_df().unwrap().value_counts("is_available").unwrap();
+    let res = execute_dataframe(df, ExecutionConfig::default())
+        .await
+        .unwrap();
+    let res = ContainerData {
+        schema: res.schema().clone(),
+        data: res.materialize().await.unwrap(),
+    };
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["is_available", "count"], vec!["bool", "num"])
+        .add_data(vec![vec!["true", "4"], vec!["false", "1"]])
+        .build();
+    assert_eq!(res, expected);
+
+    let df = test_df().unwrap().value_counts("quantity").unwrap();
+    let res = run_on("SELECT * FROM test", df).await.unwrap();
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["quantity", "count"], vec!["num", "num"])
+        .add_data(vec![
+            vec!["0", "1"],
+            vec!["10", "1"],
+            vec!["25", "1"],
+            vec!["50", "1"],
+            vec!["100", "1"],
+        ])
+        .build();
+    assert_eq!(res, expected);
+
+    assert!(test_df().unwrap().value_counts("missing").is_err());
+}
+
+#[tokio::test]
+async fn literal_projection_query() {
+    let sql = "SELECT 'constant' AS label, id, 'item', 42 FROM test LIMIT 2";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(
+            vec!["label", "id", "item", "42"],
+            vec!["text", "num", "text", "num"],
+        )
+        .add_data(vec![
+            vec!["constant", "1", "item", "42"],
+            vec!["constant", "2", "item", "42"],
+        ])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    let res = run("SELECT '' FROM test").await.unwrap();
+    assert_eq!(res.schema.fields()[0].name(), "?column?");
+}
+
+#[tokio::test]
+async fn escaped_string_literal_query() {
+    let sql = "SELECT 'it''s a test' AS quoted, E'a\\tb' AS escaped, E'it\\'s' AS apostrophe \
+               FROM test LIMIT 1";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(
+            vec!["quoted", "escaped", "apostrophe"],
+            vec!["text", "text", "text"],
+        )
+        .add_data(vec![vec!["it's a test", "a\tb", "it's"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn copy_delimiter_query() {
+    let path = std::env::temp_dir().join(format!("zaku_copy_{}.tsv", std::process::id()));
+    let path = path.to_str().unwrap();
+    let sql = format!(
+        "COPY (SELECT id, product_name FROM test LIMIT 2) TO '{}' WITH (DELIMITER E'\\t')",
+        path
+    );
+    execute(&sql, test_df().unwrap()).await.unwrap();
+    let written = std::fs::read_to_string(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(written, "id\tproduct_name\n1\ttoothbrush\n2\ttoothpaste\n");
+
+    let sql = format!(
+        "COPY (SELECT id FROM test) TO '{}' WITH (DELIMITER E'\\t\\t')",
+        path
+    );
+    assert!(execute(&sql, test_df().unwrap()).await.is_err());
+
+    let sql = format!(
+        "COPY (SELECT id FROM test) TO '{}' WITH (DELIMITER 'é')",
+        path
+    );
+    assert!(execute(&sql, test_df().unwrap()).await.is_err());
+}
+
+#[tokio::test]
+async fn delimiter_out_query() {
+    let path = std::env::temp_dir().join(format!("zaku_delimiter_out_{}.tsv", std::process::id()));
+    let path = path.to_str().unwrap();
+    let copy = |sql: String, config: ExecutionConfig| async move {
+        execute_with_config(&sql, test_df().unwrap(), config).await?;
+        let written = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        Ok::<String, ZakuError>(written)
+    };
+    let config = ExecutionConfig::default().with_delimiter_out(b'\t');
+
+    let sql = format!(
+        "COPY (SELECT id, product_name FROM test LIMIT 1) TO '{}'",
+        path
+    );
+    assert_eq!(
+        copy(sql, config.clone()).await.unwrap(),
+        "id\tproduct_name\n1\ttoothbrush\n"
+    );
+
+    // the delimiter of the statement takes precedence
+    let sql = format!(
+        "COPY (SELECT id, product_name FROM test LIMIT 1) TO '{}' WITH (DELIMITER ';')",
+        path
+    );
+    assert_eq!(
+        copy(sql, config.clone()).await.unwrap(),
+        "id;product_name\n1;toothbrush\n"
+    );
+
+    let res = execute("SELECT id, price FROM test LIMIT 1", test_df().unwrap())
+        .await
+        .unwrap();
+    let mut buf = vec![];
+    res.write_csv_with_delimiter(&mut buf, config.delimiter_out())
+        .await
+        .unwrap();
+    assert_eq!(String::from_utf8(buf).unwrap(), "id\tprice\n1\t5.00\n");
+
+    let config = ExecutionConfig::default().with_delimiter_out(b'"');
+    assert_eq!(
+        config.validate().unwrap_err().to_string(),
+        "ZakuError: Output delimiter cannot be a quote or a line break"
+    );
+    assert!(
+        execute_with_config("SELECT id FROM test", test_df().unwrap(), config)
+            .await
+            .is_err()
+    );
+
+    let config = ExecutionConfig::default().with_delimiter_out(0xe9);
+    assert_eq!(
+        config.validate().unwrap_err().to_string(),
+        "ZakuError: Output delimiter must be an ASCII character"
+    );
+}
+
+#[tokio::test]
+async fn wildcard_with_expression_query() {
+    let sql = "SELECT *, price * quantity AS total FROM test LIMIT 2";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(
+            vec![
+                "id",
+                "product_name",
+                "is_available",
+                "price",
+                "quantity",
+                "updated_on",
+                "total",
+            ],
+            vec!["num", "text", "bool", "num", "num", "date", "num"],
+        )
+        .add_data(vec![
+            vec![
+                "1",
+                "toothbrush",
+                "true",
+                "5.00",
+                "100",
+                "2023-06-06",
+                "500.00",
+            ],
+            vec![
+                "2",
+                "toothpaste",
+                "true",
+                "10.00",
+                "50",
+                "2023-01-01",
+                "500.00",
+            ],
+        ])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    let res = run("SELECT id * 2 AS doubled, * FROM test").await.unwrap();
+    let names: Vec<&String> = res.schema.fields().iter().map(|f| f.name()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "doubled",
+            "id",
+            "product_name",
+            "is_available",
+            "price",
+            "quantity",
+            "updated_on"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn projection_alias_reference_query() {
+    let sql = "SELECT price * quantity AS total, total * 2 AS doubled FROM test LIMIT 2";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["total", "doubled"], vec!["num", "num"])
+        .add_data(vec![vec!["500.00", "1000.00"], vec!["500.00", "1000.00"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    let sql = "SELECT SUM(quantity) AS total, total / COUNT(*) AS mean FROM test";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["total", "mean"], vec!["num", "num"])
+        .add_data(vec![vec!["185", "37"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    // columns of the table are not shadowed by aliases
+    let sql = "SELECT id * 10 AS price, price AS original FROM test LIMIT 1";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["price", "original"], vec!["num", "num"])
+        .add_data(vec![vec!["10", "5.00"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    // each alias doubles the one before it, which is only copied while the result stays small
+    let doubled = |n: usize| {
+        let aliases: Vec<String> = (1..=n)
+            .map(|i| format!("a{} + a{} AS a{i}", i - 1, i - 1))
+            .collect();
+        format!("SELECT id AS a0, {} FROM test LIMIT 1", aliases.join(", "))
+    };
+    let expected = ContainerDataBuilder::default()
+        .add_schema(
+            vec!["a0", "a1", "a2", "a3"],
+            vec!["num", "num", "num", "num"],
+        )
+        .add_data(vec![vec!["1", "2", "4", "8"]])
+        .build();
+    assert_eq!(run(&doubled(3)).await.unwrap(), expected);
+    let err = run(&doubled(40)).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "ZakuError: Aliases expand to more than 10000 expressions, use a subquery instead"
+    );
+}
+
 #[tokio::test]
 async fn order_by_query() {
     let sql = "SELECT id FROM test ORDER BY id DESC";
@@ -154,6 +578,51 @@ async fn order_by_query() {
     assert_eq!(run(sql).await.unwrap(), expected);
 }
 
+// Rows with tied sort keys keep their input order
+#[tokio::test]
+async fn order_by_ties_query() {
+    let sql = "SELECT id, is_available FROM test ORDER BY is_available DESC";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id", "is_available"], vec!["num", "bool"])
+        .add_data(vec![
+            vec!["1", "true"],
+            vec!["2", "true"],
+            vec!["3", "true"],
+            vec!["5", "true"],
+            vec!["4", "false"],
+        ])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+}
+
+// test_sort.csv has names differing only in case, and nulls in both columns
+#[tokio::test]
+async fn order_by_collation_nulls_query() {
+    let order = |order_by: &str| {
+        let sql = format!("SELECT id FROM test ORDER BY {order_by}");
+        async move { run_on(&sql, load_df("test_sort.csv")?).await }
+    };
+
+    // by default text compares case sensitively, and nulls are larger than any value
+    assert_eq!(
+        order("name, price DESC").await.unwrap(),
+        id_result(vec!["2", "5", "3", "1", "6", "7", "4"])
+    );
+    assert_eq!(
+        order("name COLLATE NOCASE ASC NULLS LAST, price DESC NULLS FIRST")
+            .await
+            .unwrap(),
+        id_result(vec!["2", "3", "5", "1", "6", "7", "4"])
+    );
+    assert_eq!(
+        order("name COLLATE NOCASE DESC NULLS FIRST, price ASC NULLS LAST")
+            .await
+            .unwrap(),
+        id_result(vec!["4", "7", "6", "1", "5", "3", "2"])
+    );
+    assert!(order("name COLLATE fr_FR").await.is_err());
+}
+
 #[tokio::test]
 async fn aggregate_query() {
     let sql = "SELECT SUM(price*2.0) AS inflation FROM test";
@@ -174,6 +643,22 @@ async fn aggregate_group_by_query() {
     assert_eq!(run(sql).await.unwrap(), expected);
 }
 
+#[tokio::test]
+async fn count_distinct_group_by_query() {
+    // distinct values are counted within each group, globally there are 5 names and 2 values of price > 10
+    let sql = "SELECT is_available, COUNT(DISTINCT product_name), COUNT(DISTINCT price > 10) FROM test GROUP BY is_available ORDER BY is_available";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(
+            vec!["is_available", "count", "count"],
+            vec!["bool", "num", "num"],
+        )
+        .add_data(vec![vec!["false", "1", "1"], vec!["true", "4", "2"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    assert!(run("SELECT SUM(DISTINCT price) FROM test").await.is_err());
+}
+
 #[tokio::test]
 async fn group_by_wildcard_query() {
     let sql = "SELECT * FROM test GROUP BY is_available";
@@ -186,6 +671,26 @@ async fn aggregate_in_where_query() {
     assert!(run(sql).await.is_err());
 }
 
+#[tokio::test]
+async fn order_by_aggregate_ratio_query() {
+    let sql = "SELECT is_available, COUNT(*) AS count FROM test GROUP BY is_available \
+               ORDER BY COUNT(*) * 1.0 / SUM(price)";
+    let expected = |rows: Vec<Vec<&str>>| {
+        ContainerDataBuilder::default()
+            .add_schema(vec!["is_available", "count"], vec!["bool", "num"])
+            .add_data(rows)
+            .build()
+    };
+    assert_eq!(
+        run(sql).await.unwrap(),
+        expected(vec![vec!["true", "4"], vec!["false", "1"]])
+    );
+    assert_eq!(
+        run(&format!("{sql} DESC")).await.unwrap(),
+        expected(vec![vec!["false", "1"], vec!["true", "4"]])
+    );
+}
+
 #[tokio::test]
 async fn having_query() {
     let sql = "SELECT COUNT(id) AS count FROM test GROUP BY is_available HAVING COUNT(id) > 2 AND is_available = true";
@@ -197,36 +702,1447 @@ async fn having_query() {
 }
 
 #[tokio::test]
-async fn having_aggregate_query() {
+async fn having_alias_query() {
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["is_available", "c"], vec!["bool", "num"])
+        .add_data(vec![vec!["true", "4"]])
+        .build();
+    let sql = "SELECT is_available, COUNT(*) AS c FROM test GROUP BY is_available HAVING c > 2";
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    // aliases of expressions over aggregates are expanded too
+    let sql = "SELECT is_available, SUM(price) * 2 AS doubled FROM test \
+               GROUP BY is_available HAVING doubled > 10";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["is_available", "doubled"], vec!["bool", "num"])
+        .add_data(vec![vec!["true", "101.00"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn case_query() {
     let sql =
-        "SELECT COUNT(id), SUM(price) FROM test GROUP BY is_available HAVING MIN(quantity) > 0";
+        "SELECT id, CASE WHEN price > 10 THEN 'high' WHEN price > 5 THEN 'mid' END AS bucket, \
+               CASE id WHEN 1 THEN price ELSE 0 END FROM test LIMIT 3";
     let expected = ContainerDataBuilder::default()
-        .add_schema(vec!["count", "sum"], vec!["num", "num"])
-        .add_data(vec![vec!["4", "50.50"]])
+        .add_schema(vec!["id", "bucket", "case"], vec!["num", "text", "num"])
+        .add_data(vec![
+            vec!["1", "", "5.00"],
+            vec!["2", "mid", "0"],
+            vec!["3", "high", "0"],
+        ])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    // soap has a quantity of 0, which only the guarded branch sees
+    let sql = "SELECT id, CASE WHEN quantity = 0 THEN 0 ELSE price / quantity END FROM test";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id", "case"], vec!["num", "num"])
+        .add_data(vec![
+            vec!["1", "0.05"],
+            vec!["2", "0.2"],
+            vec!["3", "0.62"],
+            vec!["4", "0"],
+            vec!["5", "2"],
+        ])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+    let sql = "SELECT id, CASE WHEN quantity > 0 THEN price / quantity END FROM test";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id", "case"], vec!["num", "num"])
+        .add_data(vec![
+            vec!["1", "0.05"],
+            vec!["2", "0.2"],
+            vec!["3", "0.62"],
+            vec!["4", ""],
+            vec!["5", "2"],
+        ])
         .build();
     assert_eq!(run(sql).await.unwrap(), expected);
+
+    let sql = "SELECT CASE WHEN price > 10 THEN 'high' ELSE 1 END FROM test";
+    assert!(run(sql).await.is_err());
+    let sql = "SELECT CASE WHEN price THEN 'high' END FROM test";
+    assert!(run(sql).await.is_err());
 }
 
 #[tokio::test]
-async fn complex_query() {
+async fn group_by_case_query() {
+    let expected = |name: &str| {
+        ContainerDataBuilder::default()
+            .add_schema(vec![name, "count"], vec!["text", "num"])
+            .add_data(vec![vec!["high", "2"], vec!["low", "3"]])
+            .build()
+    };
     let sql =
-        "SELECT id, product_name, (price*quantity) AS total FROM test WHERE quantity <> 0 LIMIT 3";
+        "SELECT CASE WHEN price > 10 THEN 'high' ELSE 'low' END AS bucket, COUNT(*) AS count \
+               FROM test GROUP BY CASE WHEN price > 10 THEN 'high' ELSE 'low' END \
+               ORDER BY CASE WHEN price > 10 THEN 'high' ELSE 'low' END";
+    assert_eq!(run(sql).await.unwrap(), expected("bucket"));
+
+    let sql = "SELECT CASE WHEN price > 10 THEN 'high' ELSE 'low' END, COUNT(*) AS count \
+               FROM test GROUP BY ALL HAVING COUNT(*) > 1 ORDER BY count";
+    assert_eq!(run(sql).await.unwrap(), expected("case"));
+}
+
+#[tokio::test]
+async fn cube_query() {
+    // every combination of region and product, with nulls in the columns not grouped by
+    let sql = "SELECT region, product, COUNT(*) AS count, SUM(amount) AS total FROM sales \
+               GROUP BY CUBE(region, product) ORDER BY region, product";
     let expected = ContainerDataBuilder::default()
         .add_schema(
-            vec!["id", "product_name", "total"],
-            vec!["num", "text", "num"],
+            vec!["region", "product", "count", "total"],
+            vec!["text", "text", "num", "num"],
         )
         .add_data(vec![
-            vec!["1", "toothbrush", "500.00"],
-            vec!["2", "toothpaste", "500.00"],
-            vec!["3", "shampoo", "387.50"],
+            vec!["north", "apple", "1", "10"],
+            vec!["north", "pear", "1", "20"],
+            vec!["north", "", "2", "30"],
+            vec!["south", "apple", "2", "35"],
+            vec!["south", "", "2", "35"],
+            vec!["", "apple", "3", "45"],
+            vec!["", "pear", "1", "20"],
+            vec!["", "", "4", "65"],
         ])
         .build();
+    let df = load_df("test_sales.csv").unwrap();
+    assert_eq!(run_on(sql, df).await.unwrap(), expected);
+
+    // the grand total is still produced for an empty input
+    let sql = "SELECT COUNT(*) AS count FROM test WHERE false GROUP BY CUBE(is_available)";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["count"], vec!["num"])
+        .add_data(vec![vec!["0"]])
+        .build();
     assert_eq!(run(sql).await.unwrap(), expected);
+
+    let err = run("SELECT COUNT(*) FROM test GROUP BY is_available, CUBE(price)")
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "ZakuError: CUBE must be the only GROUP BY expression"
+    );
+    let err = run("SELECT COUNT(*) FROM test GROUP BY CUBE(id, id, id, id, id, id, id, id, id)")
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "ZakuError: CUBE supports at most 8 expressions, got 9"
+    );
 }
 
 #[tokio::test]
-async fn explain_query() {
-    let sql = "EXPLAIN SELECT * FROM test";
-    assert!(run(sql).await.is_ok());
+async fn empty_input_aggregate_query() {
+    // without GROUP BY an empty input still has one group, which HAVING then filters
+    let sql = "SELECT COUNT(*) AS count, SUM(price), MIN(updated_on), AVG(quantity) FROM test WHERE price < 0";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(
+            vec!["count", "sum", "min", "avg"],
+            vec!["num", "num", "date", "num"],
+        )
+        .add_data(vec![vec!["0", "", "", ""]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    let sql = "SELECT SUM(price) FROM test WHERE false HAVING SUM(price) > 0";
+    let res = run(sql).await.unwrap();
+    assert_eq!(res.data.iter().map(|rb| rb.row_count()).sum::<usize>(), 0);
+
+    let sql = "SELECT COUNT(*) AS count FROM test WHERE price < 0 HAVING COUNT(*) = 0";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["count"], vec!["num"])
+        .add_data(vec![vec!["0"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    // groups are only formed from rows, so an empty input has none
+    let sql = "SELECT is_available, COUNT(*) FROM test WHERE price < 0 GROUP BY is_available";
+    let res = run(sql).await.unwrap();
+    assert_eq!(res.data.iter().map(|rb| rb.row_count()).sum::<usize>(), 0);
+}
+
+#[tokio::test]
+async fn having_aggregate_query() {
+    let sql =
+        "SELECT COUNT(id), SUM(price) FROM test GROUP BY is_available HAVING MIN(quantity) > 0";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["count", "sum"], vec!["num", "num"])
+        .add_data(vec![vec!["4", "50.50"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn having_only_aggregate_is_not_projected_query() {
+    let sql = "SELECT is_available, SUM(price) FROM test GROUP BY is_available HAVING MAX(quantity) > 0 AND MIN(quantity) < 50";
+    let res = run(sql).await.unwrap();
+    assert_eq!(
+        res.schema.as_header(),
+        vec!["is_available".to_string(), "sum".to_string()]
+    );
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["is_available", "sum"], vec!["bool", "num"])
+        .add_data(vec![vec!["true", "50.50"]])
+        .build();
+    assert_eq!(res, expected);
+}
+
+#[tokio::test]
+async fn write_csv_query() {
+    let res = execute(
+        "SELECT id, product_name FROM test LIMIT 2",
+        test_df().unwrap(),
+    )
+    .await
+    .unwrap();
+    let mut buf = vec![];
+    let row_count = res.write_csv(&mut buf).await.unwrap();
+    assert_eq!(row_count, 2);
+    assert_eq!(
+        String::from_utf8(buf).unwrap(),
+        "id,product_name\n1,toothbrush\n2,toothpaste\n"
+    );
+}
+
+#[tokio::test]
+async fn write_csv_round_trip_query() {
+    // untouched values keep the scale of the source file
+    let res = execute("SELECT * FROM test", test_df().unwrap())
+        .await
+        .unwrap();
+    let mut buf = vec![];
+    res.write_csv(&mut buf).await.unwrap();
+    let source = std::fs::read_to_string(Path::new("resources").join("test.csv")).unwrap();
+    assert_eq!(String::from_utf8(buf).unwrap(), source);
+
+    let res = execute(
+        "SELECT price, price * 2 AS doubled, id * 10 AS scaled FROM test LIMIT 1",
+        test_df().unwrap(),
+    )
+    .await
+    .unwrap();
+    let mut buf = vec![];
+    res.write_csv(&mut buf).await.unwrap();
+    assert_eq!(
+        String::from_utf8(buf).unwrap(),
+        "price,doubled,scaled\n5.00,10.00,10\n"
+    );
+}
+
+#[tokio::test]
+async fn decimal_column_query() {
+    let schema = Schema::from_pairs(&[
+        ("id", DataType::Number),
+        ("price", DataType::decimal(10, 2).unwrap()),
+        ("quantity", DataType::Number),
+    ]);
+    let df =
+        || Dataframe::from_csv_with_schema("resources/test.csv", schema.clone(), None).unwrap();
+    let write = |sql: &'static str| {
+        let df = df();
+        async move {
+            let res = execute(sql, df).await?;
+            let mut buf = vec![];
+            res.write_csv(&mut buf).await?;
+            Ok::<String, ZakuError>(String::from_utf8(buf).unwrap())
+        }
+    };
+
+    // arithmetic with a decimal is rounded half away from zero to its scale
+    let sql =
+        "SELECT price, price * 1.5 AS marked_up, price / 3 AS third, price + 0.005 AS rounded \
+               FROM test WHERE price IN (5, 10)";
+    assert_eq!(
+        write(sql).await.unwrap(),
+        "price,marked_up,third,rounded\n5.00,7.50,1.67,5.01\n10.00,15.00,3.33,10.01\n"
+    );
+    let res = execute(sql, df()).await.unwrap();
+    assert_eq!(
+        res.schema().fields()[1].datatype(),
+        &DataType::decimal(10, 2).unwrap()
+    );
+
+    let sql = "SELECT SUM(price) AS total, CAST(quantity AS DECIMAL(5, 1)) AS quantity FROM test \
+               GROUP BY quantity ORDER BY quantity LIMIT 2";
+    assert_eq!(
+        write(sql).await.unwrap(),
+        "total,quantity\n2.00,0.0\n20.00,10.0\n"
+    );
+
+    let sql = "SELECT CAST(price * 1000 AS DECIMAL(4, 2)) FROM test";
+    assert_eq!(
+        write(sql).await.unwrap_err().to_string(),
+        "ZakuError: Value 5000.00 does not fit in decimal(4,2)"
+    );
+
+    let missing = Schema::from_pairs(&[("cost", DataType::decimal(10, 2).unwrap())]);
+    assert!(Dataframe::from_csv_with_schema("resources/test.csv", missing, None).is_err());
+}
+
+#[tokio::test]
+async fn array_agg_query() {
+    let write = |sql: &'static str, json: bool| async move {
+        let res = execute(sql, test_df().unwrap()).await.unwrap();
+        let mut buf = vec![];
+        if json {
+            res.write_ndjson(&mut buf).await.unwrap();
+        } else {
+            res.write_csv(&mut buf).await.unwrap();
+        }
+        String::from_utf8(buf).unwrap()
+    };
+
+    let sql = "SELECT is_available, ARRAY_AGG(product_name) AS names FROM test \
+               GROUP BY is_available ORDER BY is_available";
+    let res = execute(sql, test_df().unwrap()).await.unwrap();
+    assert_eq!(res.schema().fields()[1].datatype(), &DataType::List);
+    assert_eq!(
+        write(sql, false).await,
+        "is_available,names\n\
+         false,[soap]\n\
+         true,\"[toothbrush, toothpaste, shampoo, shaving cream]\"\n"
+    );
+    assert_eq!(
+        write(sql, true).await,
+        "{\"is_available\":false,\"names\":[\"soap\"]}\n\
+         {\"is_available\":true,\"names\":[\"toothbrush\",\"toothpaste\",\"shampoo\",\"shaving cream\"]}\n"
+    );
+
+    // an empty group is null, as in Postgres
+    let sql = "SELECT ARRAY_AGG(id) AS ids FROM test WHERE price > 100";
+    assert_eq!(write(sql, false).await, "ids\n\"\"\n");
+
+    let sql = "SELECT ARRAY_AGG(DISTINCT id) FROM test";
+    assert!(execute(sql, test_df().unwrap()).await.is_err());
+}
+
+#[tokio::test]
+async fn write_ndjson_query() {
+    let res = execute(
+        "SELECT id, product_name, is_available, updated_on FROM test WHERE id < 3",
+        test_df().unwrap(),
+    )
+    .await
+    .unwrap();
+    let mut buf = vec![];
+    let row_count = res.write_ndjson(&mut buf).await.unwrap();
+    assert_eq!(row_count, 2);
+    let lines = String::from_utf8(buf).unwrap();
+    let rows = lines
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0]["id"], serde_json::json!(1));
+    assert_eq!(rows[1]["product_name"], serde_json::json!("toothpaste"));
+    assert_eq!(rows[1]["is_available"], serde_json::json!(true));
+    assert!(rows[0]["updated_on"].is_string());
+}
+
+#[tokio::test]
+async fn mismatched_table_name_query() {
+    let res = execute("SELECT id FROM lineitem LIMIT 1", test_df().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(res.materialize().await.unwrap()[0].row_count(), 1);
+    assert_eq!(
+        res.warnings(),
+        &vec![
+            "Warning: table 'lineitem' does not exist, reading from 'test' instead. \
+               Referring to the loaded table by another name is deprecated"
+                .to_string()
+        ]
+    );
+
+    let res = execute("SELECT id FROM TEST", test_df().unwrap())
+        .await
+        .unwrap();
+    assert!(res.warnings().is_empty());
+}
+
+#[tokio::test]
+async fn create_table_as_query() {
+    let mut catalog = Catalog::from_dataframe(test_df().unwrap());
+    let config = ExecutionConfig::default();
+    let sql = "CREATE TABLE summary AS \
+               SELECT is_available, SUM(quantity) AS total FROM test GROUP BY is_available";
+    let res = execute_in_catalog(sql, &mut catalog, config.clone())
+        .await
+        .unwrap();
+    let rows = ContainerDataBuilder::default()
+        .add_schema(vec!["rows"], vec!["num"])
+        .add_data(vec![vec!["2"]])
+        .build();
+    assert_eq!(
+        ContainerData {
+            schema: res.schema().clone(),
+            data: res.materialize().await.unwrap(),
+        },
+        rows
+    );
+
+    let res = execute_in_catalog(
+        "SELECT total FROM Summary WHERE is_available = false",
+        &mut catalog,
+        config.clone(),
+    )
+    .await
+    .unwrap();
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["total"], vec!["num"])
+        .add_data(vec![vec!["0"]])
+        .build();
+    assert_eq!(
+        ContainerData {
+            schema: res.schema().clone(),
+            data: res.materialize().await.unwrap(),
+        },
+        expected
+    );
+    assert_eq!(
+        catalog.get("summary").unwrap().schema().as_header(),
+        vec!["is_available", "total"]
+    );
+
+    // the original table is still there, and names must now match a table
+    assert!(
+        execute_in_catalog("SELECT id FROM test", &mut catalog, config.clone())
+            .await
+            .is_ok()
+    );
+    assert!(
+        execute_in_catalog("SELECT id FROM lineitem", &mut catalog, config.clone())
+            .await
+            .is_err()
+    );
+    assert!(execute_in_catalog(sql, &mut catalog, config.clone())
+        .await
+        .is_err());
+    let sql = "CREATE OR REPLACE TABLE summary AS SELECT id FROM test";
+    execute_in_catalog(sql, &mut catalog, config).await.unwrap();
+    assert_eq!(
+        catalog.get("summary").unwrap().schema().as_header(),
+        vec!["id"]
+    );
+}
+
+#[tokio::test]
+async fn complex_query() {
+    let sql =
+        "SELECT id, product_name, (price*quantity) AS total FROM test WHERE quantity <> 0 LIMIT 3";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(
+            vec!["id", "product_name", "total"],
+            vec!["num", "text", "num"],
+        )
+        .add_data(vec![
+            vec!["1", "toothbrush", "500.00"],
+            vec!["2", "toothpaste", "500.00"],
+            vec!["3", "shampoo", "387.50"],
+        ])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn power_query() {
+    let sql = "SELECT 2 ^ 10 AS caret, POWER(2, 10) AS power FROM test LIMIT 1";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["caret", "power"], vec!["num", "num"])
+        .add_data(vec![vec!["1024", "1024"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn power_precedence_query() {
+    // ^ binds tighter than * and +, and is left associative, as in Postgres
+    let sql = "SELECT 2 * 3 ^ 2 AS mul, 1 + 2 ^ 2 AS add, 2 ^ 2 * 3 AS mul_right, \
+               (2 * 3) ^ 2 AS nested, 2 ^ 3 ^ 2 AS chained, \
+               10 - 2 ^ 3 / 4 AS mixed FROM test LIMIT 1";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(
+            vec!["mul", "add", "mul_right", "nested", "chained", "mixed"],
+            vec!["num", "num", "num", "num", "num", "num"],
+        )
+        .add_data(vec![vec!["18", "5", "12", "36", "64", "8"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    let sql = "SELECT id FROM test WHERE id * 2 ^ 2 > 12";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id"], vec!["num"])
+        .add_data(vec![vec!["4"], vec!["5"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn power_column_query() {
+    let sql = "SELECT id ^ 2 AS squared FROM test WHERE id ^ 0.5 > 2";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["squared"], vec!["num"])
+        .add_data(vec![vec!["25"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn ascii_chr_query() {
+    let sql = "SELECT id, ASCII(product_name) AS code, CHR(ASCII('A')) AS chr FROM test LIMIT 2";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id", "code", "chr"], vec!["num", "num", "text"])
+        .add_data(vec![vec!["1", "116", "A"], vec!["2", "116", "A"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    let sql = "SELECT ASCII(''), CHR(ASCII(CHR(955))) FROM test LIMIT 1";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["ascii", "chr"], vec!["num", "text"])
+        .add_data(vec![vec!["0", "λ"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn trim_query() {
+    let sql = "SELECT TRIM(LEADING '0' FROM '00' || CAST(id AS TEXT) || '0') AS code, \
+               TRIM(TRAILING 'ph' FROM product_name) AS rtrim, \
+               TRIM('t' FROM product_name) AS trim, \
+               TRIM('  x ') AS spaces \
+               FROM test LIMIT 2";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(
+            vec!["code", "rtrim", "trim", "spaces"],
+            vec!["text", "text", "text", "text"],
+        )
+        .add_data(vec![
+            vec!["10", "toothbrus", "oothbrush", "x"],
+            vec!["20", "toothpaste", "oothpaste", "x"],
+        ])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    let sql = "SELECT id, TRIM(LEADING 'A' FROM name) AS name FROM test";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id", "name"], vec!["num", "text"])
+        .add_data(vec![
+            vec!["1", "lice"],
+            vec!["2", ""],
+            vec!["3", "bob"],
+            vec!["4", "Carol"],
+        ])
+        .build();
+    assert_eq!(run_nulls(sql).await.unwrap(), expected);
+
+    assert!(run("SELECT TRIM(LEADING '0' FROM id) FROM test")
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn datediff_query() {
+    let sql = "SELECT id, DATEDIFF(day, '2023-01-01', updated_on) AS days, \
+               DATEDIFF('month', updated_on, '2024-01-31') AS months, \
+               DATEDIFF(YEAR, updated_on, '2021-12-31') AS years \
+               FROM test WHERE id < 3";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(
+            vec!["id", "days", "months", "years"],
+            vec!["num", "num", "num", "num"],
+        )
+        .add_data(vec![
+            vec!["1", "156", "7", "-2"],
+            vec!["2", "0", "12", "-2"],
+        ])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    for sql in [
+        "SELECT DATEDIFF(week, updated_on, updated_on) FROM test",
+        "SELECT DATEDIFF(day, updated_on, id) FROM test",
+        "SELECT DATEDIFF(day, updated_on) FROM test",
+    ] {
+        assert!(run(sql).await.is_err(), "{sql}");
+    }
+}
+
+#[tokio::test]
+async fn age_query() {
+    let sql =
+        "SELECT id, AGE(updated_on, '2021-12-31') AS age, AGE('2023-01-01', updated_on) AS back \
+               FROM test WHERE id < 4";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id", "age", "back"], vec!["num", "text", "text"])
+        .add_data(vec![
+            vec!["1", "1 year 5 mons 6 days", "-5 mons -5 days"],
+            vec!["2", "1 year 1 day", "0 days"],
+            vec!["3", "1 year 3 mons 4 days", "-3 mons -3 days"],
+        ])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    // days are borrowed from the month of the earlier date
+    let sql = "SELECT AGE('2023-03-01', '2023-01-31') AS age FROM test LIMIT 1";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["age"], vec!["text"])
+        .add_data(vec![vec!["1 mon 1 day"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    for sql in [
+        "SELECT AGE(updated_on, id) FROM test",
+        "SELECT AGE(updated_on) FROM test",
+    ] {
+        assert!(run(sql).await.is_err(), "{sql}");
+    }
+}
+
+#[tokio::test]
+async fn chr_invalid_query() {
+    for sql in [
+        "SELECT CHR(-1) FROM test",
+        "SELECT CHR(1.5) FROM test",
+        "SELECT CHR(55296) FROM test",
+        "SELECT CHR(product_name) FROM test",
+        "SELECT ASCII(id) FROM test",
+        "SELECT ASCII() FROM test",
+    ] {
+        assert!(run(sql).await.is_err(), "{sql}");
+    }
+}
+
+#[tokio::test]
+async fn concat_query() {
+    let sql = "SELECT product_name || '!' AS name FROM test WHERE id < 3";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["name"], vec!["text"])
+        .add_data(vec![vec!["toothbrush!"], vec!["toothpaste!"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn text_addition_query() {
+    let sql = "SELECT product_name + 'a' FROM test";
+    let err = run(sql).await.unwrap_err();
+    assert!(err.to_string().contains("use || to concatenate strings"));
+
+    assert!(run("SELECT price || price FROM test").await.is_err());
+}
+
+#[tokio::test]
+async fn scalar_subquery_query() {
+    let sql = "SELECT id, (SELECT MAX(price) FROM test) AS max_price FROM test LIMIT 2";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id", "max_price"], vec!["num", "num"])
+        .add_data(vec![vec!["1", "20"], vec!["2", "20"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    let sql = "SELECT id, price - (SELECT MIN(price) FROM test) AS diff FROM test WHERE price > (SELECT AVG(price) FROM test)";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id", "diff"], vec!["num", "num"])
+        .add_data(vec![vec!["3", "13.5"], vec!["5", "18"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    let sql = "SELECT id, (SELECT price FROM test) FROM test";
+    let err = run(sql).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "ZakuError: Scalar subquery must return exactly one row, got 5"
+    );
+
+    let sql = "SELECT id, (SELECT id, price FROM test LIMIT 1) FROM test";
+    assert!(run(sql).await.is_err());
+}
+
+#[tokio::test]
+async fn from_less_subquery_query() {
+    // a subquery without FROM is a single row, not a row per row of the table
+    let sql = "SELECT id, (SELECT 1 + 1) AS two FROM test LIMIT 2";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id", "two"], vec!["num", "num"])
+        .add_data(vec![vec!["1", "2"], vec!["2", "2"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    let cases = [
+        (
+            "SELECT id, (SELECT 1 WHERE true) FROM test",
+            "A subquery without FROM can only select a single expression",
+        ),
+        (
+            "SELECT id, (SELECT 1, 2) FROM test",
+            "A subquery without FROM can only select a single expression",
+        ),
+        (
+            "SELECT (SELECT MAX(price)) FROM test",
+            "A subquery without FROM cannot contain aggregate, window or generator functions",
+        ),
+    ];
+    for (sql, msg) in cases {
+        let err = run(sql).await.unwrap_err();
+        assert_eq!(err.to_string(), format!("ZakuError: {msg}"), "{sql}");
+    }
+}
+
+#[tokio::test]
+async fn cast_boolean_number_query() {
+    let sql = "SELECT id, CAST(is_available AS NUMBER) AS available, CAST(quantity AS BOOLEAN) AS in_stock FROM test";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(
+            vec!["id", "available", "in_stock"],
+            vec!["num", "num", "bool"],
+        )
+        .add_data(vec![
+            vec!["1", "1", "true"],
+            vec!["2", "1", "true"],
+            vec!["3", "1", "true"],
+            vec!["4", "0", "false"],
+            vec!["5", "1", "true"],
+        ])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    let sql = "SELECT SUM(CAST(is_available AS INTEGER)) AS available FROM test";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["available"], vec!["num"])
+        .add_data(vec![vec!["4"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    let sql = "SELECT id, CAST(flag AS NUMBER) AS flag FROM test";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id", "flag"], vec!["num", "num"])
+        .add_data(vec![
+            vec!["1", "1"],
+            vec!["2", "0"],
+            vec!["3", ""],
+            vec!["4", "1"],
+        ])
+        .build();
+    assert_eq!(run_nulls(sql).await.unwrap(), expected);
+
+    assert!(run("SELECT CAST(updated_on AS NUMBER) FROM test")
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn in_list_date_text_query() {
+    let sql = "SELECT id FROM test WHERE updated_on IN ('2023-01-01', '2023-02-02')";
+    assert_eq!(run(sql).await.unwrap(), id_result(vec!["2", "4"]));
+
+    let sql = "SELECT id FROM test WHERE product_name IN ('soap', 'shampoo')";
+    assert_eq!(run(sql).await.unwrap(), id_result(vec!["3", "4"]));
+
+    // a quoted date is compared as text against a text column
+    let sql = "SELECT id FROM test WHERE product_name NOT IN ('soap', '2023-01-01')";
+    assert_eq!(run(sql).await.unwrap(), id_result(vec!["1", "2", "3", "5"]));
+
+    let sql = "SELECT id FROM test WHERE updated_on = ANY(['2023-03-03', '2023-06-06'])";
+    assert_eq!(run(sql).await.unwrap(), id_result(vec!["1", "5"]));
+
+    assert!(run("SELECT id FROM test WHERE updated_on IN ('soap')")
+        .await
+        .is_err());
+    assert!(run("SELECT id FROM test WHERE updated_on IN (1)")
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn is_distinct_from_query() {
+    // both null, one null, equal and unequal
+    let sql = "SELECT id, score IS NOT DISTINCT FROM score * 2 AS res FROM test";
+    let expected = negated_result(vec!["false", "true", "false", "true"]);
+    assert_eq!(run_nulls(sql).await.unwrap(), expected);
+
+    let sql = "SELECT id, score IS DISTINCT FROM score * 2 AS res FROM test";
+    let expected = negated_result(vec!["true", "false", "true", "false"]);
+    assert_eq!(run_nulls(sql).await.unwrap(), expected);
+
+    let sql = "SELECT id, score IS NOT DISTINCT FROM 10 AS res FROM test";
+    let expected = negated_result(vec!["true", "false", "false", "false"]);
+    assert_eq!(run_nulls(sql).await.unwrap(), expected);
+
+    let sql = "SELECT id, score <=> 20 AS res FROM test";
+    let expected = negated_result(vec!["false", "false", "true", "false"]);
+    assert_eq!(run_nulls(sql).await.unwrap(), expected);
+
+    let sql = "SELECT id FROM test WHERE score IS DISTINCT FROM 10";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id"], vec!["num"])
+        .add_data(vec![vec!["2"], vec!["3"], vec!["4"]])
+        .build();
+    assert_eq!(run_nulls(sql).await.unwrap(), expected);
+
+    assert!(run("SELECT id IS DISTINCT FROM product_name FROM test")
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn any_all_query() {
+    let sql = "SELECT id FROM test WHERE price = ANY([5, 15.50, 20, 5])";
+    assert_eq!(run(sql).await.unwrap(), id_result(vec!["1", "3", "5"]));
+
+    let sql = "SELECT id FROM test WHERE quantity > ALL((10, 25))";
+    assert_eq!(run(sql).await.unwrap(), id_result(vec!["1", "2"]));
+
+    // the list can also be written directly inside ANY and ALL
+    let sql = "SELECT id FROM test WHERE id = ANY(1, 2) OR id = ANY (4, (5))";
+    assert_eq!(run(sql).await.unwrap(), id_result(vec!["1", "2", "4", "5"]));
+
+    let sql = "SELECT id FROM test WHERE quantity > ALL(10, 25)";
+    assert_eq!(run(sql).await.unwrap(), id_result(vec!["1", "2"]));
+
+    let sql = "SELECT id FROM test WHERE product_name <> ALL(ARRAY['soap', 'shampoo'])";
+    assert_eq!(run(sql).await.unwrap(), id_result(vec!["1", "2", "5"]));
+
+    let sql = "SELECT id FROM test WHERE id IN (1, 2) AND id >= ANY([2, 4])";
+    assert_eq!(run(sql).await.unwrap(), id_result(vec!["2"]));
+
+    // as with IN, a null comparison makes the result unknown unless another comparison decides it
+    let sql = "SELECT id, score = ANY([10, 30]) AS res FROM test";
+    let expected = negated_result(vec!["true", "", "false", ""]);
+    assert_eq!(run_nulls(sql).await.unwrap(), expected);
+
+    assert!(run("SELECT id FROM test WHERE price = ANY([quantity])")
+        .await
+        .is_err());
+    assert!(run("SELECT id FROM test WHERE price = ANY(['a'])")
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn split_rows_query() {
+    // test_tags.csv has null tags, which generate no rows
+    let tags = || load_df("test_tags.csv").unwrap();
+    let sql = "SELECT id, SPLIT_ROWS(tags, ',') AS tag FROM test";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id", "tag"], vec!["num", "text"])
+        .add_data(vec![
+            vec!["1", "red"],
+            vec!["1", "blue"],
+            vec!["3", "green"],
+            vec!["4", "red"],
+            vec!["4", "yellow"],
+            vec!["4", "blue"],
+        ])
+        .build();
+    assert_eq!(run_on(sql, tags()).await.unwrap(), expected);
+
+    assert!(run_on(
+        "SELECT id FROM test WHERE SPLIT_ROWS(tags, ',') = 'red'",
+        tags()
+    )
+    .await
+    .is_err());
+    assert!(
+        run_on("SELECT COUNT(SPLIT_ROWS(tags, ',')) FROM test", tags())
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn null_treatment_query() {
+    let result = |first: &str, last: &str| {
+        ContainerDataBuilder::default()
+            .add_schema(vec!["first", "last"], vec!["text", "num"])
+            .add_data(vec![vec![first, last]])
+            .build()
+    };
+
+    // the first name after id 1 and the last score are null
+    let sql = "SELECT FIRST_VALUE(name) AS first, LAST_VALUE(score) AS last FROM test WHERE id > 1";
+    assert_eq!(run_nulls(sql).await.unwrap(), result("", ""));
+
+    let sql = "SELECT FIRST_VALUE(name) RESPECT NULLS AS first, \
+               LAST_VALUE(score) RESPECT NULLS AS last FROM test WHERE id > 1";
+    assert_eq!(run_nulls(sql).await.unwrap(), result("", ""));
+
+    let sql = "SELECT FIRST_VALUE(name) IGNORE NULLS AS first, \
+               LAST_VALUE(score) IGNORE NULLS AS last FROM test WHERE id > 1";
+    assert_eq!(run_nulls(sql).await.unwrap(), result("bob", "20"));
+
+    let sql = "SELECT id, FIRST_VALUE(score) IGNORE NULLS OVER () AS first FROM test WHERE id > 1 \
+               ORDER BY id";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id", "first"], vec!["num", "num"])
+        .add_data(vec![vec!["2", "20"], vec!["3", "20"], vec!["4", "20"]])
+        .build();
+    assert_eq!(run_nulls(sql).await.unwrap(), expected);
+
+    assert!(run_nulls("SELECT SUM(score) IGNORE NULLS FROM test")
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn output_summary_query() {
+    let sql = "SELECT id, name, score FROM test";
+    let res = execute(sql, load_df("test_nulls.csv").unwrap())
+        .await
+        .unwrap();
+    let mut summary = OutputSummary::new(res.schema());
+    for rb in res.materialize().await.unwrap() {
+        summary.update(&rb).unwrap();
+    }
+    let rb = summary.to_record_batch().unwrap().unwrap();
+
+    // text columns are left out, and null scores are not counted
+    let expected = ContainerDataBuilder::default()
+        .add_schema(
+            vec!["column", "count", "min", "max", "mean"],
+            vec!["text", "num", "num", "num", "num"],
+        )
+        .add_data(vec![
+            vec!["id", "4", "1", "4", "2.5"],
+            vec!["score", "2", "10", "20", "15"],
+        ])
+        .build();
+    let actual = ContainerData {
+        schema: rb.schema().clone(),
+        data: vec![rb],
+    };
+    assert_eq!(actual, expected);
+
+    let res = execute("SELECT product_name FROM test", test_df().unwrap())
+        .await
+        .unwrap();
+    assert!(OutputSummary::new(res.schema())
+        .to_record_batch()
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn window_share_query() {
+    let sql =
+        "SELECT id, quantity / SUM(quantity) OVER () AS share FROM test WHERE id <> 2 AND id <> 5";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id", "share"], vec!["num", "num"])
+        .add_data(vec![vec!["1", "0.8"], vec!["3", "0.2"], vec!["4", "0"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn window_count_query() {
+    let sql = "SELECT id, COUNT(*) OVER () AS total FROM test WHERE price > 4 ORDER BY id";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id", "total"], vec!["num", "num"])
+        .add_data(vec![
+            vec!["1", "4"],
+            vec!["2", "4"],
+            vec!["3", "4"],
+            vec!["5", "4"],
+        ])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn count_wildcard_query() {
+    let sql = "SELECT COUNT(*) AS count FROM test";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["count"], vec!["num"])
+        .add_data(vec![vec!["5"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+    assert!(run("SELECT SUM(*) FROM test").await.is_err());
+}
+
+#[tokio::test]
+async fn window_with_aggregate_query() {
+    let sql = "SELECT SUM(price), SUM(quantity) OVER () FROM test";
+    assert!(run(sql).await.is_err());
+}
+
+#[tokio::test]
+async fn max_expr_depth_query() {
+    let chain = |n: usize| format!("SELECT id{} AS id FROM test LIMIT 1", " + 1".repeat(n));
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id"], vec!["num"])
+        .add_data(vec![vec!["51"]])
+        .build();
+    assert_eq!(run(&chain(50)).await.unwrap(), expected);
+
+    let err = run(&chain(1000)).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "ZakuError: Query is nested too deeply, the maximum depth is 100"
+    );
+    let nested = format!("SELECT {}1{} FROM test", "(".repeat(1000), ")".repeat(1000));
+    assert!(run(&nested).await.is_err());
+
+    // runs of prefix operators are rejected before sqlparser recurses into them
+    for sql in [
+        format!("SELECT id FROM test WHERE {}true", "NOT ".repeat(1000)),
+        format!("SELECT {}id FROM test", "- ".repeat(1000)),
+        format!("SELECT id FROM test WHERE {}(true)", "NOT (".repeat(60)) + &")".repeat(60),
+    ] {
+        let err = run(&sql).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ZakuError: Query is nested too deeply, the maximum depth is 100"
+        );
+    }
+    let sql = format!("SELECT id FROM test WHERE {}true", "NOT ".repeat(50));
+    assert!(run(&sql).await.is_ok());
+    let err = run(&chain(100_000)).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "ZakuError: Expression is too long, the maximum is 10000 tokens"
+    );
+
+    let config = ExecutionConfig::default().with_max_expr_depth(10);
+    assert!(execute_with_config(&chain(20), test_df().unwrap(), config)
+        .await
+        .is_err());
+    let config = ExecutionConfig::default().with_max_expr_depth(0);
+    assert!(execute_with_config(&chain(1), test_df().unwrap(), config)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn batch_size_config_query() {
+    let config = ExecutionConfig::default().with_batch_size(2);
+    let res = execute_with_config(
+        "SELECT id FROM test ORDER BY id",
+        test_df().unwrap(),
+        config,
+    )
+    .await
+    .unwrap();
+    let row_counts: Vec<usize> = res
+        .materialize()
+        .await
+        .unwrap()
+        .iter()
+        .map(|rb| rb.row_count())
+        .collect();
+    assert_eq!(row_counts, vec![2, 2, 1]);
+
+    let config = ExecutionConfig::default().with_batch_size(0);
+    assert!(
+        execute_with_config("SELECT id FROM test", test_df().unwrap(), config)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn not_null_query() {
+    let sql = "SELECT id, NOT flag AS res FROM test";
+    let expected = negated_result(vec!["false", "true", "", "false"]);
+    assert_eq!(run_nulls(sql).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn not_like_null_query() {
+    let sql = "SELECT id, name NOT LIKE 'A%' AS res FROM test";
+    let expected = negated_result(vec!["false", "", "true", "true"]);
+    assert_eq!(run_nulls(sql).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn not_ilike_null_query() {
+    let sql = "SELECT id, name NOT ILIKE 'B%' AS res FROM test";
+    let expected = negated_result(vec!["true", "", "false", "true"]);
+    assert_eq!(run_nulls(sql).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn not_in_null_query() {
+    let sql = "SELECT id, score NOT IN (10, 30) AS res FROM test";
+    let expected = negated_result(vec!["false", "", "true", ""]);
+    assert_eq!(run_nulls(sql).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn not_between_null_query() {
+    let sql = "SELECT id, score NOT BETWEEN 5 AND 15 AS res FROM test";
+    let expected = negated_result(vec!["false", "", "true", ""]);
+    assert_eq!(run_nulls(sql).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn negated_predicate_filter_query() {
+    // rows where the predicate is null are filtered out
+    let sql = "SELECT id FROM test WHERE name NOT LIKE 'A%' AND score NOT IN (10)";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id"], vec!["num"])
+        .add_data(vec![vec!["3"]])
+        .build();
+    assert_eq!(run_nulls(sql).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn deterministic_hash_query() {
+    let sql = "SELECT product_name, SUM(quantity) FROM test GROUP BY product_name";
+    let mut outputs = vec![];
+    for _ in 0..2 {
+        let config = ExecutionConfig::default().with_deterministic_hash(true);
+        let res = execute_with_config(sql, test_df().unwrap(), config)
+            .await
+            .unwrap();
+        let rbs = res.materialize().await.unwrap();
+        outputs.push(rbs.iter().map(|rb| rb.print(false)).collect::<String>());
+    }
+    assert_eq!(outputs[0], outputs[1]);
+}
+
+#[tokio::test]
+async fn spilled_aggregate_query() {
+    let sql = "SELECT product_name, SUM(quantity), COUNT(id) FROM test GROUP BY product_name ORDER BY product_name";
+    let expected = run(sql).await.unwrap();
+    let config = ExecutionConfig::default()
+        .with_deterministic_hash(true)
+        .with_spill_threshold(Some(1));
+    let res = execute_with_config(sql, test_df().unwrap(), config)
+        .await
+        .unwrap();
+    let spilled = ContainerData {
+        schema: res.schema().clone(),
+        data: res.materialize().await.unwrap(),
+    };
+    assert_eq!(spilled, expected);
+}
+
+#[tokio::test]
+async fn debug_plan_query() {
+    let sql = "SELECT product_name FROM test WHERE price > 10";
+    let config = ExecutionConfig::default().with_debug_plan(true);
+    let res = execute_with_config(sql, test_df().unwrap(), config)
+        .await
+        .unwrap();
+    let plan = res.debug_plan().unwrap();
+    let (original, optimized) = plan.split_once("Optimized logical plan:").unwrap();
+    assert!(original.contains("Scan: resources/test.csv | None"));
+    assert!(optimized.contains("Scan: resources/test.csv | product_name, price"));
+
+    let res = execute(sql, test_df().unwrap()).await.unwrap();
+    assert!(res.debug_plan().is_none());
+}
+
+// Counts the passes of the optimizer, and caps the result at two rows if limit is set
+struct TestRule {
+    passes: Arc<AtomicUsize>,
+    limit: bool,
+}
+
+impl OptimizerRule for TestRule {
+    fn apply(&self, plan: LogicalPlans) -> Result<LogicalPlans, ZakuError> {
+        self.passes.fetch_add(1, Ordering::SeqCst);
+        match plan {
+            LogicalPlans::Limit(_) => Ok(plan),
+            _ if self.limit => Ok(Dataframe::new(Arc::new(plan))
+                .limit(2)?
+                .logical_plan()
+                .clone()),
+            _ => Ok(plan),
+        }
+    }
+}
+
+// Adds another limit on every pass, so the plan never stops changing
+struct UnboundedRule;
+
+impl OptimizerRule for UnboundedRule {
+    fn apply(&self, plan: LogicalPlans) -> Result<LogicalPlans, ZakuError> {
+        Ok(Dataframe::new(Arc::new(plan))
+            .limit(2)?
+            .logical_plan()
+            .clone())
+    }
+}
+
+#[tokio::test]
+async fn optimizer_rule_query() {
+    let run_with_rule = |sql: &'static str, limit: bool| async move {
+        let passes = Arc::new(AtomicUsize::new(0));
+        let rule = TestRule {
+            passes: passes.clone(),
+            limit,
+        };
+        let config = ExecutionConfig::default()
+            .with_debug_plan(true)
+            .with_optimizer_rule(Arc::new(rule));
+        let res = execute_with_config(sql, test_df().unwrap(), config)
+            .await
+            .unwrap();
+        let plan = res.debug_plan().unwrap().to_string();
+        let data = ContainerData {
+            schema: res.schema().clone(),
+            data: res.materialize().await.unwrap(),
+        };
+        (data, plan, passes.load(Ordering::SeqCst))
+    };
+
+    // the rules are applied until a pass leaves the plan unchanged
+    let sql = "SELECT * FROM test";
+    let (data, _, passes) = run_with_rule(sql, false).await;
+    assert_eq!(data, run(sql).await.unwrap());
+    assert_eq!(passes, 1);
+    let (data, _, passes) = run_with_rule(sql, true).await;
+    assert_eq!(data.data[0].row_count(), 2);
+    assert_eq!(passes, 2);
+
+    // the built-in rules are composed with the registered rule
+    let sql = "SELECT id FROM test WHERE price > 4";
+    let (data, plan, _) = run_with_rule(sql, false).await;
+    assert_eq!(data, run(sql).await.unwrap());
+    let (_, optimized) = plan.split_once("Optimized logical plan:").unwrap();
+    assert!(optimized.contains("Scan: resources/test.csv | id, price"));
+
+    let (data, plan, _) = run_with_rule(sql, true).await;
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["id"], vec!["num"])
+        .add_data(vec![vec!["1"], vec!["2"]])
+        .build();
+    assert_eq!(data, expected);
+    let (_, optimized) = plan.split_once("Optimized logical plan:").unwrap();
+    assert!(optimized.starts_with("\nLimit: 2"));
+    assert!(optimized.contains("Scan: resources/test.csv | id, price"));
+
+    let config = ExecutionConfig::default().with_optimizer_rule(Arc::new(UnboundedRule));
+    let err = execute_with_config("SELECT * FROM test", test_df().unwrap(), config)
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "ZakuError: Optimizer rules still change the plan after 10 passes"
+    );
+}
+
+#[tokio::test]
+async fn count_rows_query() {
+    let config = ExecutionConfig::default().with_debug_plan(true);
+    let sql = "SELECT COUNT(*) AS count FROM test";
+    let res = execute_with_config(sql, test_df().unwrap(), config.clone())
+        .await
+        .unwrap();
+    let plan = res.debug_plan().unwrap().to_string();
+    let (_, optimized) = plan.split_once("Optimized logical plan:").unwrap();
+    assert!(!optimized.contains("Aggregate"));
+    assert!(!optimized.contains("resources/test.csv"));
+    let expected = ContainerDataBuilder::default()
+        .add_schema(vec!["count"], vec!["num"])
+        .add_data(vec![vec!["5"]])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
+
+    for sql in [
+        "SELECT COUNT(*) FROM test WHERE price > 10",
+        "SELECT product_name, COUNT(*) FROM test GROUP BY product_name",
+        "SELECT COUNT(price) FROM test",
+    ] {
+        let res = execute_with_config(sql, test_df().unwrap(), config.clone())
+            .await
+            .unwrap();
+        let plan = res.debug_plan().unwrap().to_string();
+        let (_, optimized) = plan.split_once("Optimized logical plan:").unwrap();
+        assert!(optimized.contains("Aggregate"));
+    }
+}
+
+#[test]
+fn estimated_rows_query() {
+    let df = test_df().unwrap();
+    assert_eq!(df.estimated_rows(), 5);
+    assert_eq!(df.limit(2).unwrap().estimated_rows(), 2);
+    assert_eq!(df.limit(10).unwrap().estimated_rows(), 5);
+    assert_eq!(df.offset(4).unwrap().estimated_rows(), 1);
+    assert_eq!(df.offset(10).unwrap().estimated_rows(), 0);
+    // five rows are assumed to make a single group
+    let counts = df.value_counts("product_name").unwrap();
+    assert_eq!(counts.estimated_rows(), 1);
+    assert_eq!(counts.limit(0).unwrap().estimated_rows(), 0);
+}
+
+#[tokio::test]
+async fn explain_query() {
+    let sql = "EXPLAIN SELECT * FROM test";
+    assert!(run(sql).await.is_ok());
+}
+
+#[tokio::test]
+async fn function_arity_query() {
+    let cases = [
+        (
+            "SELECT SUM(price, quantity) FROM test",
+            "SUM expects 1 argument, got 2",
+        ),
+        (
+            "SELECT COUNT() FROM test",
+            "COUNT expects 1 argument, got 0",
+        ),
+        (
+            "SELECT POWER(price) FROM test",
+            "POWER expects 2 arguments, got 1",
+        ),
+        (
+            "SELECT POW(price, 2, 3) FROM test",
+            "POW expects 2 arguments, got 3",
+        ),
+        (
+            "SELECT ASCII() FROM test",
+            "ASCII expects 1 argument, got 0",
+        ),
+        (
+            "SELECT CHR(65, 66) FROM test",
+            "CHR expects 1 argument, got 2",
+        ),
+    ];
+    for (sql, msg) in cases {
+        let err = run(sql).await.unwrap_err();
+        assert_eq!(err.to_string(), format!("ZakuError: {msg}"), "{sql}");
+    }
+}
+
+#[tokio::test]
+async fn unknown_column_query() {
+    let queries = [
+        "SELECT id FROM test ORDER BY prce",
+        "SELECT id, prce FROM test",
+    ];
+    for sql in queries {
+        let err = run(sql).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ZakuError: Field 'prce' not found (did you mean 'price'?)",
+            "{sql}"
+        );
+    }
+
+    // only the columns of the aggregate output are suggested after a GROUP BY
+    let sql = "SELECT is_available, COUNT(id) FROM test GROUP BY is_available ORDER BY prce";
+    let err = run(sql).await.unwrap_err();
+    assert_eq!(err.to_string(), "ZakuError: Field 'prce' not found");
+}
+
+#[tokio::test]
+async fn malformed_query() {
+    let queries = [
+        "",
+        ";",
+        "SELECT",
+        "SELECT * FROM test LIMIT -1",
+        "SELECT * FROM test LIMIT 99999999999999999999999",
+        "SELECT SUM() FROM test",
+        "SELECT price / 0 FROM test",
+        "SELECT price % 0 FROM test",
+        "SELECT product_name + 1 FROM test",
+        "SELECT * FROM test WHERE is_available > true",
+        "SELECT * FROM test WHERE product_name AND is_available",
+        "SELECT * FROM test ORDER BY unknown",
+        "SELECT MIN(product_name) FROM test",
+        "SELECT * FROM test GROUP BY",
+        "EXPLAIN",
+        "DROP TABLE test",
+    ];
+    for sql in queries {
+        assert!(run(sql).await.is_err(), "'{}' should return an error", sql);
+    }
+}
+
+// Throws randomly generated token sequences at the engine, which must return instead of panicking
+#[tokio::test]
+async fn random_query_does_not_panic() {
+    let tokens = [
+        "SELECT",
+        "*",
+        "FROM",
+        "test",
+        "WHERE",
+        "GROUP",
+        "BY",
+        "ORDER",
+        "LIMIT",
+        "HAVING",
+        "EXPLAIN",
+        "id",
+        "price",
+        "quantity",
+        "product_name",
+        "is_available",
+        "updated_on",
+        "SUM(",
+        "COUNT(",
+        "AVG(",
+        "MIN(",
+        "MAX(",
+        "(",
+        ")",
+        "+",
+        "-",
+        "*",
+        "/",
+        "%",
+        "=",
+        "<>",
+        ">",
+        "<=",
+        "AND",
+        "OR",
+        "NOT",
+        "0",
+        "1",
+        "2.5",
+        "'a'",
+        "'2023-01-01'",
+        "true",
+        ",",
+        "AS",
+        "DESC",
+    ];
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed as usize
+    };
+    for _ in 0..500 {
+        let len = next() % 12 + 1;
+        let mut sql = vec!["SELECT"];
+        (0..len).for_each(|_| sql.push(tokens[next() % tokens.len()]));
+        let _ = run(&sql.join(" ")).await;
+    }
+}
+
+#[tokio::test]
+async fn manual_avg_query() {
+    let sql =
+        "SELECT is_available, SUM(price) / COUNT(*) AS manual_price, AVG(price) AS avg_price, \
+               SUM(quantity) / COUNT(*) AS manual_quantity, AVG(quantity) AS avg_quantity \
+               FROM test GROUP BY is_available ORDER BY is_available";
+    let expected = ContainerDataBuilder::default()
+        .add_schema(
+            vec![
+                "is_available",
+                "manual_price",
+                "avg_price",
+                "manual_quantity",
+                "avg_quantity",
+            ],
+            vec!["bool", "num", "num", "num", "num"],
+        )
+        .add_data(vec![
+            vec!["false", "2", "2", "0", "0"],
+            vec!["true", "12.625", "12.625", "46.25", "46.25"],
+        ])
+        .build();
+    assert_eq!(run(sql).await.unwrap(), expected);
 }